@@ -0,0 +1,76 @@
+-- Tables as they were created by the inline block in init_database.
+-- IF NOT EXISTS lets installations that predate migrations adopt this version.
+
+CREATE TABLE IF NOT EXISTS users (
+    id TEXT PRIMARY KEY,
+    login TEXT NOT NULL UNIQUE,
+    password TEXT NOT NULL,
+    role TEXT NOT NULL CHECK (role IN ('admin', 'user')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS clients (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    nuit TEXT NOT NULL,
+    contact TEXT NOT NULL,
+    category TEXT NOT NULL,
+    observations TEXT NOT NULL,
+    debt DOUBLE PRECISION NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS orders (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    client_id TEXT NOT NULL REFERENCES clients(id) ON DELETE CASCADE,
+    order_number INTEGER NOT NULL,
+    client_requisition_number INTEGER NOT NULL,
+    due_date DATE,
+    discount DOUBLE PRECISION DEFAULT 0,
+    iva DOUBLE PRECISION DEFAULT 0,
+    subtotal DOUBLE PRECISION NOT NULL,
+    total DOUBLE PRECISION NOT NULL,
+    status TEXT NOT NULL DEFAULT 'order_received',
+    debt DOUBLE PRECISION NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS impressions (
+    id TEXT PRIMARY KEY NOT NULL,
+    order_id TEXT NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    size TEXT NOT NULL,
+    material TEXT NOT NULL,
+    description TEXT NOT NULL,
+    price DOUBLE PRECISION NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS clothes (
+    id TEXT PRIMARY KEY,
+    order_id TEXT NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    clothing_type TEXT NOT NULL,
+    custom_type TEXT,
+    unit_price DOUBLE PRECISION NOT NULL DEFAULT 0,
+    sizes TEXT NOT NULL, -- JSON string: {"S": 2, "M": 5, "L": 3}
+    color TEXT NOT NULL,
+    total_quantity INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS clothing_services (
+    id TEXT PRIMARY KEY,
+    clothes_id TEXT NOT NULL REFERENCES clothes(id) ON DELETE CASCADE,
+    service_type TEXT NOT NULL,
+    location TEXT NOT NULL,
+    description TEXT,
+    unit_price DOUBLE PRECISION NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,3 @@
+-- Accounts can be disabled without deleting them
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS active BOOLEAN NOT NULL DEFAULT TRUE;
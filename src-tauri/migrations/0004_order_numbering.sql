@@ -0,0 +1,24 @@
+-- Order and requisition numbers come from a locked counter instead of MAX()+1
+
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS order_year INTEGER;
+UPDATE orders SET order_year = EXTRACT(YEAR FROM created_at)::INTEGER WHERE order_year IS NULL;
+ALTER TABLE orders ALTER COLUMN order_year SET NOT NULL;
+
+-- Two workstations may already have handed out the same number. The oldest
+-- order keeps it; the others are moved to the end of the sequence.
+WITH ranked AS (
+    SELECT id, created_at,
+           ROW_NUMBER() OVER (PARTITION BY order_number ORDER BY created_at, id) AS rn
+    FROM orders
+),
+renumbered AS (
+    SELECT id,
+           (SELECT COALESCE(MAX(order_number), 0) FROM orders)
+               + ROW_NUMBER() OVER (ORDER BY created_at, id) AS new_number
+    FROM ranked
+    WHERE rn > 1
+)
+UPDATE orders SET order_number = renumbered.new_number
+FROM renumbered
+WHERE orders.id = renumbered.id;
+
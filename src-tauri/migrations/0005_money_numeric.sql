@@ -0,0 +1,57 @@
+-- Money moves from DOUBLE PRECISION to exact NUMERIC. Stored values are
+-- rounded to centavos (half away from zero, same as crate::money) and the
+-- derived totals and debts are recomputed from the rounded figures.
+
+UPDATE orders SET discount = 0 WHERE discount IS NULL;
+UPDATE orders SET iva = 0 WHERE iva IS NULL;
+
+ALTER TABLE clients
+    ALTER COLUMN debt TYPE NUMERIC(14, 2) USING ROUND(debt::NUMERIC, 2);
+
+ALTER TABLE orders
+    ALTER COLUMN discount TYPE NUMERIC(14, 2) USING ROUND(discount::NUMERIC, 2),
+    ALTER COLUMN discount SET NOT NULL,
+    ALTER COLUMN iva TYPE NUMERIC(5, 2) USING ROUND(iva::NUMERIC, 2),
+    ALTER COLUMN iva SET NOT NULL,
+    ALTER COLUMN subtotal TYPE NUMERIC(14, 2) USING ROUND(subtotal::NUMERIC, 2),
+    ALTER COLUMN total TYPE NUMERIC(14, 2) USING ROUND(total::NUMERIC, 2),
+    ALTER COLUMN debt TYPE NUMERIC(14, 2) USING ROUND(debt::NUMERIC, 2);
+
+ALTER TABLE impressions
+    ALTER COLUMN price TYPE NUMERIC(14, 2) USING ROUND(price::NUMERIC, 2);
+
+ALTER TABLE clothes
+    ALTER COLUMN unit_price TYPE NUMERIC(14, 2) USING ROUND(unit_price::NUMERIC, 2);
+
+ALTER TABLE clothing_services
+    ALTER COLUMN unit_price TYPE NUMERIC(14, 2) USING ROUND(unit_price::NUMERIC, 2);
+
+ALTER TABLE payments
+    ALTER COLUMN amount TYPE NUMERIC(14, 2) USING ROUND(amount::NUMERIC, 2);
+
+-- Orders with garments or impressions get their totals recomputed exactly;
+-- orders without lines keep the (rounded) values that were typed in.
+WITH lines AS (
+    SELECT o.id,
+           COALESCE((
+               SELECT SUM((c.unit_price + COALESCE((
+                          SELECT SUM(s.unit_price) FROM clothing_services s WHERE s.clothes_id = c.id
+                      ), 0)) * c.total_quantity)
+               FROM clothes c WHERE c.order_id = o.id
+           ), 0)
+           + COALESCE((SELECT SUM(i.price) FROM impressions i WHERE i.order_id = o.id), 0) AS subtotal
+    FROM orders o
+    WHERE EXISTS (SELECT 1 FROM clothes c WHERE c.order_id = o.id)
+       OR EXISTS (SELECT 1 FROM impressions i WHERE i.order_id = o.id)
+)
+UPDATE orders
+SET subtotal = lines.subtotal,
+    total = lines.subtotal + ROUND(lines.subtotal * orders.iva / 100, 2) - orders.discount
+FROM lines
+WHERE orders.id = lines.id;
+
+UPDATE orders
+SET debt = GREATEST(total - (SELECT COALESCE(SUM(amount), 0) FROM payments WHERE order_id = orders.id), 0);
+
+UPDATE clients
+SET debt = (SELECT COALESCE(SUM(debt), 0) FROM orders WHERE client_id = clients.id);
@@ -0,0 +1,30 @@
+-- Every status change of an order, including the status it was created with
+
+CREATE TABLE IF NOT EXISTS order_status_history (
+    id TEXT PRIMARY KEY,
+    order_id TEXT NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    from_status TEXT,
+    to_status TEXT NOT NULL,
+    changed_by TEXT REFERENCES users(id) ON DELETE SET NULL,
+    note TEXT,
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_order_status_history_order_id ON order_status_history(order_id, changed_at);
+
+-- Only statuses the backend knows about. Anything else was shown as
+-- order_received by the app already, so it is stored as such.
+UPDATE orders SET status = 'order_received'
+WHERE status NOT IN ('order_received', 'in_production', 'ready_for_delivery', 'delivered', 'on_hold', 'cancelled');
+
+ALTER TABLE orders DROP CONSTRAINT IF EXISTS orders_status_check;
+ALTER TABLE orders ADD CONSTRAINT orders_status_check
+    CHECK (status IN ('order_received', 'in_production', 'ready_for_delivery', 'delivered', 'on_hold', 'cancelled'));
+
+-- Existing orders start their history with the status they have now. The
+-- real date of that change is unknown, so the last update time is used.
+INSERT INTO order_status_history (id, order_id, from_status, to_status, changed_by, note, changed_at)
+SELECT gen_random_uuid()::text, o.id, NULL, o.status, NULL,
+       'Estado anterior ao registo de histórico', o.updated_at
+FROM orders o
+WHERE NOT EXISTS (SELECT 1 FROM order_status_history h WHERE h.order_id = o.id);
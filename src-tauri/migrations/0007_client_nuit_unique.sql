@@ -0,0 +1,11 @@
+-- A NUIT belongs to one client. Clients without one keep an empty NUIT,
+-- which the unique index leaves out.
+
+UPDATE clients SET nuit = regexp_replace(nuit, '[[:space:]]', '', 'g')
+WHERE nuit ~ '[[:space:]]';
+
+-- Same placeholders as nuit::normalize
+UPDATE clients SET nuit = ''
+WHERE upper(nuit) IN ('-', 'N/A', 'NA', 'S/N', 'SN', 'SEMNUIT')
+   OR nuit ~ '^0+$';
+
@@ -0,0 +1,10 @@
+-- The counter an order's number came from: 'order' for global numbering,
+-- 'order:<year>' when numbers restart every year. A number is unique within
+-- its scope, so global numbering is enforced as well as yearly numbering.
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS number_scope TEXT;
+
+-- Orders numbered before this keep the per-year guarantee they already had
+UPDATE orders SET number_scope = 'order:' || order_year WHERE number_scope IS NULL;
+ALTER TABLE orders ALTER COLUMN number_scope SET NOT NULL;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_orders_scope_number ON orders(number_scope, order_number);
@@ -0,0 +1,10 @@
+CREATE UNIQUE INDEX IF NOT EXISTS idx_orders_year_number ON orders(order_year, order_number);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_orders_client_requisition ON orders(client_id, client_requisition_number);
+
+-- One row per numbering scope ('order', 'order:2026', 'requisition:<client id>').
+-- Rows are created on first use and the row lock serialises allocation.
+CREATE TABLE IF NOT EXISTS number_sequences (
+    scope TEXT PRIMARY KEY,
+    last_value INTEGER NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1 @@
+CREATE UNIQUE INDEX IF NOT EXISTS idx_clients_nuit ON clients(nuit) WHERE nuit <> '';
@@ -0,0 +1,16 @@
+-- The oldest client keeps a NUIT registered more than once. The others lose
+-- it, with a note in their observations so they can be merged or corrected.
+WITH ranked AS (
+    SELECT id, nuit,
+           ROW_NUMBER() OVER (PARTITION BY nuit ORDER BY created_at, id) AS rn
+    FROM clients
+    WHERE nuit <> ''
+)
+UPDATE clients
+SET observations = CASE WHEN clients.observations = '' THEN '' ELSE clients.observations || E'\n' END
+        || 'NUIT ' || ranked.nuit || ' removido: já registado noutro cliente',
+    nuit = '',
+    updated_at = CURRENT_TIMESTAMP
+FROM ranked
+WHERE clients.id = ranked.id AND ranked.rn > 1;
+
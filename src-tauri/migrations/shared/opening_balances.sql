@@ -0,0 +1,7 @@
+-- Whatever was already paid before the ledger existed becomes one opening payment
+INSERT INTO payments (id, order_id, amount, payment_date, method, reference, received_by, created_at, updated_at)
+SELECT gen_random_uuid()::text, o.id, o.total - o.debt, o.updated_at::date, 'other',
+       'Saldo anterior ao registo de pagamentos', NULL, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP
+FROM orders o
+WHERE o.total > o.debt
+  AND NOT EXISTS (SELECT 1 FROM payments p WHERE p.order_id = o.id);
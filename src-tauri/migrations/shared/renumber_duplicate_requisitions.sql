@@ -0,0 +1,16 @@
+WITH ranked AS (
+    SELECT id, client_id, created_at,
+           ROW_NUMBER() OVER (PARTITION BY client_id, client_requisition_number ORDER BY created_at, id) AS rn
+    FROM orders
+),
+renumbered AS (
+    SELECT ranked.id,
+           (SELECT COALESCE(MAX(o.client_requisition_number), 0) FROM orders o WHERE o.client_id = ranked.client_id)
+               + ROW_NUMBER() OVER (PARTITION BY ranked.client_id ORDER BY ranked.created_at, ranked.id) AS new_number
+    FROM ranked
+    WHERE rn > 1
+)
+UPDATE orders SET client_requisition_number = renumbered.new_number
+FROM renumbered
+WHERE orders.id = renumbered.id;
+
@@ -0,0 +1,78 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use crate::error::{AppError, AppResult};
+use std::sync::OnceLock;
+
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasswordCheck {
+    Valid,
+    /// Correct password, but stored in a legacy format that should be re-hashed
+    ValidNeedsUpgrade,
+    Invalid,
+}
+
+pub fn hash_password(password: &str) -> AppResult<String> {
+    let salt = SaltString::generate(&mut OsRng);
+
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| AppError::Failed(format!("Failed to hash password: {}", e)))
+}
+
+pub fn verify_password(password: &str, stored: &str) -> PasswordCheck {
+    match PasswordHash::new(stored) {
+        Ok(hash) => {
+            if Argon2::default().verify_password(password.as_bytes(), &hash).is_ok() {
+                PasswordCheck::Valid
+            } else {
+                PasswordCheck::Invalid
+            }
+        }
+        // Not a PHC string: a plaintext password from before hashing was introduced
+        Err(_) => {
+            if constant_time_eq(password.as_bytes(), stored.as_bytes()) {
+                PasswordCheck::ValidNeedsUpgrade
+            } else {
+                PasswordCheck::Invalid
+            }
+        }
+    }
+}
+
+/// Runs the same Argon2 verification as a real login against a throwaway
+/// hash, so a login that does not exist takes as long as a wrong password.
+pub fn verify_dummy_password(password: &str) {
+    static DUMMY_HASH: OnceLock<Option<String>> = OnceLock::new();
+    let dummy_hash = DUMMY_HASH.get_or_init(|| {
+        let mut random = [0u8; 16];
+        OsRng.fill_bytes(&mut random);
+        let dummy_password: String = random.iter().map(|byte| format!("{:02x}", byte)).collect();
+        hash_password(&dummy_password).ok()
+    });
+    if let Some(dummy_hash) = dummy_hash {
+        verify_password(password, dummy_hash);
+    }
+}
+
+/// `field` names the request field the password came in, for the error.
+pub fn validate_password_strength(field: &str, password: &str) -> AppResult<()> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(AppError::invalid(
+            field,
+            "too_short",
+            format!("Password must be at least {} characters long", MIN_PASSWORD_LENGTH),
+        ));
+    }
+    Ok(())
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
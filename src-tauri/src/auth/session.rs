@@ -0,0 +1,218 @@
+use rand_core::{OsRng, RngCore};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::models::Role;
+
+/// Sessions are dropped after this long without any command being called
+pub const SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub user_id: String,
+    pub login: String,
+    pub role: Role,
+    /// Logged in with a shipped default password: only `change_password` is allowed
+    pub must_change_password: bool,
+    last_seen: Instant,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum AuthError {
+    /// No session matches the token (never issued, logged out or revoked)
+    InvalidSession,
+    SessionExpired,
+    Forbidden { required: Role },
+    PasswordChangeRequired,
+}
+
+impl std::fmt::Display for AuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthError::InvalidSession => write!(f, "Invalid session, please log in again"),
+            AuthError::SessionExpired => write!(f, "Session expired, please log in again"),
+            AuthError::Forbidden { required } => {
+                write!(f, "This operation requires the '{}' role", required.as_str())
+            }
+            AuthError::PasswordChangeRequired => {
+                write!(f, "This account still uses its default password; change it first")
+            }
+        }
+    }
+}
+
+/// Active sessions, held in Tauri managed state. Tokens are opaque random
+/// strings that only mean something to this process.
+#[derive(Default)]
+pub struct SessionStore {
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl SessionStore {
+    pub fn create(&self, user_id: String, login: String, role: Role, must_change_password: bool) -> String {
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        let token: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        sessions.retain(|_, session| session.last_seen.elapsed() < SESSION_IDLE_TIMEOUT);
+        sessions.insert(token.clone(), Session {
+            user_id,
+            login,
+            role,
+            must_change_password,
+            last_seen: Instant::now(),
+        });
+
+        token
+    }
+
+    /// Checks that the token belongs to a live session whose role satisfies
+    /// `required`, and refreshes its inactivity timer.
+    pub fn authorize(&self, token: &str, required: Role) -> Result<Session, AuthError> {
+        let session = self.authorize_password_change(token)?;
+        if session.must_change_password {
+            return Err(AuthError::PasswordChangeRequired);
+        }
+        if !session.role.satisfies(required) {
+            return Err(AuthError::Forbidden { required });
+        }
+        Ok(session)
+    }
+
+    /// Like `authorize`, but also lets through a session that still has to
+    /// change its password, for `change_password` and `get_current_session`.
+    pub fn authorize_password_change(&self, token: &str) -> Result<Session, AuthError> {
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+
+        let session = sessions.get_mut(token).ok_or(AuthError::InvalidSession)?;
+        if session.last_seen.elapsed() >= SESSION_IDLE_TIMEOUT {
+            sessions.remove(token);
+            return Err(AuthError::SessionExpired);
+        }
+
+        session.last_seen = Instant::now();
+        Ok(session.clone())
+    }
+
+    /// Lifts the password change requirement once the password was changed
+    pub fn password_changed(&self, token: &str) {
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(session) = sessions.get_mut(token) {
+            session.must_change_password = false;
+        }
+    }
+
+    pub fn revoke(&self, token: &str) -> bool {
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        sessions.remove(token).is_some()
+    }
+
+    /// Ends every session, e.g. after switching to another database whose
+    /// users are not the ones who logged in.
+    pub fn revoke_all(&self) {
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        sessions.clear();
+    }
+
+    /// Ends every session of a user, e.g. after the account is disabled,
+    /// deleted or has its role or password changed by an admin.
+    pub fn revoke_user(&self, user_id: &str) {
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        sessions.retain(|_, session| session.user_id != user_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+
+    fn login(store: &SessionStore, role: Role) -> String {
+        store.create("user-1".to_string(), "ana".to_string(), role, false)
+    }
+
+    #[test]
+    fn admins_can_do_what_users_can_but_not_the_reverse() {
+        let store = SessionStore::default();
+        let admin = login(&store, Role::Admin);
+        let user = login(&store, Role::User);
+
+        assert!(store.authorize(&admin, Role::Admin).is_ok());
+        assert!(store.authorize(&admin, Role::User).is_ok());
+        assert!(store.authorize(&user, Role::User).is_ok());
+        assert!(matches!(
+            store.authorize(&user, Role::Admin),
+            Err(AuthError::Forbidden { required: Role::Admin })
+        ));
+    }
+
+    #[test]
+    fn default_passwords_only_allow_changing_them() {
+        let store = SessionStore::default();
+        let token = store.create("user-1".to_string(), "ana".to_string(), Role::Admin, true);
+
+        assert!(matches!(store.authorize(&token, Role::User), Err(AuthError::PasswordChangeRequired)));
+        assert!(store.authorize_password_change(&token).is_ok());
+
+        store.password_changed(&token);
+        assert!(store.authorize(&token, Role::Admin).is_ok());
+    }
+
+    #[test]
+    fn idle_sessions_expire_and_are_dropped() {
+        let store = SessionStore::default();
+        let token = login(&store, Role::User);
+        {
+            let mut sessions = store.sessions.lock().unwrap();
+            let session = sessions.get_mut(&token).unwrap();
+            session.last_seen = Instant::now().checked_sub(SESSION_IDLE_TIMEOUT).unwrap();
+        }
+
+        assert!(matches!(store.authorize(&token, Role::User), Err(AuthError::SessionExpired)));
+        // Reported once; afterwards the token is simply unknown
+        assert!(matches!(store.authorize(&token, Role::User), Err(AuthError::InvalidSession)));
+    }
+
+    #[test]
+    fn using_a_session_keeps_it_alive() {
+        let store = SessionStore::default();
+        let token = login(&store, Role::User);
+        {
+            let mut sessions = store.sessions.lock().unwrap();
+            let session = sessions.get_mut(&token).unwrap();
+            session.last_seen = Instant::now().checked_sub(SESSION_IDLE_TIMEOUT / 2).unwrap();
+        }
+
+        store.authorize(&token, Role::User).unwrap();
+        let sessions = store.sessions.lock().unwrap();
+        assert!(sessions[&token].last_seen.elapsed() < SESSION_IDLE_TIMEOUT / 2);
+    }
+
+    #[test]
+    fn revoked_sessions_are_invalid() {
+        let store = SessionStore::default();
+        let first = login(&store, Role::User);
+        let second = login(&store, Role::User);
+        let other = store.create("user-2".to_string(), "rui".to_string(), Role::User, false);
+
+        store.revoke_user("user-1");
+        assert!(matches!(store.authorize(&first, Role::User), Err(AuthError::InvalidSession)));
+        assert!(matches!(store.authorize(&second, Role::User), Err(AuthError::InvalidSession)));
+        assert!(store.authorize(&other, Role::User).is_ok());
+    }
+
+    #[test]
+    fn expired_and_invalid_sessions_report_different_reasons() {
+        let expired = serde_json::to_value(AppError::from(AuthError::SessionExpired)).unwrap();
+        let invalid = serde_json::to_value(AppError::from(AuthError::InvalidSession)).unwrap();
+
+        assert_eq!(expired["code"], "unauthorized");
+        assert_eq!(expired["details"]["reason"], "session_expired");
+        assert_eq!(invalid["code"], "unauthorized");
+        assert_eq!(invalid["details"]["reason"], "invalid_session");
+    }
+}
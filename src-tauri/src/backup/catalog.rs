@@ -0,0 +1,184 @@
+use crate::backup::container::Compression;
+use crate::backup::format::BackupRecordCounts;
+use crate::config::get_data_dir;
+use crate::error::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BackupKind {
+    #[default]
+    #[serde(rename = "manual")]
+    Manual,
+    /// Taken automatically right before a restore overwrites the data
+    #[serde(rename = "pre_restore")]
+    PreRestore,
+    /// Written by the backup scheduler; the only kind retention prunes
+    #[serde(rename = "scheduled")]
+    Scheduled,
+}
+
+impl BackupKind {
+    pub fn file_prefix(&self) -> &'static str {
+        match self {
+            BackupKind::Manual => "editart",
+            BackupKind::PreRestore => "editart-pre-restore",
+            BackupKind::Scheduled => "editart-auto",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BackupFormat {
+    /// The app's own NDJSON backup, see `backup::stream`
+    #[default]
+    #[serde(rename = "json")]
+    Json,
+    /// A pg_dump custom-format archive, restored with pg_restore
+    #[serde(rename = "pg_dump")]
+    PgDump,
+}
+
+/// One backup file known to the app. The file can live anywhere; the catalog
+/// is what lets us list backups written to a user-chosen destination.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupInfo {
+    pub id: String,
+    pub file_name: String,
+    #[serde(default)]
+    pub kind: BackupKind,
+    #[serde(default)]
+    pub format: BackupFormat,
+    #[serde(default)]
+    pub compression: Compression,
+    #[serde(default)]
+    pub encrypted: bool,
+    pub path: String,
+    pub created_at: String,
+    pub version: String,
+    #[serde(default)]
+    pub schema_version: i64,
+    pub size_bytes: u64,
+    /// SHA-256 of the file as written, hex encoded
+    pub checksum: String,
+    /// Left empty for pg_dump backups
+    pub record_counts: BackupRecordCounts,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupInspection {
+    pub info: BackupInfo,
+    pub file_exists: bool,
+    pub current_size_bytes: Option<u64>,
+    /// None when the file is missing or unreadable
+    pub checksum_valid: Option<bool>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BackupCatalog {
+    backups: Vec<BackupInfo>,
+}
+
+impl BackupCatalog {
+    pub fn load() -> AppResult<Self> {
+        let path = catalog_path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&path)
+            .map_err(|e| AppError::io("Failed to read backup catalog", e))?;
+        serde_json::from_str(&content)
+            .map_err(|e| AppError::Failed(format!("Failed to parse backup catalog: {}", e)))
+    }
+
+    pub fn save(&self) -> AppResult<()> {
+        let path = catalog_path()?;
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| AppError::Failed(format!("Failed to serialize backup catalog: {}", e)))?;
+
+        // Write next to the catalog and rename, so a crash never leaves it half written
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, content)
+            .map_err(|e| AppError::io("Failed to write backup catalog", e))?;
+        fs::rename(&tmp_path, &path)
+            .map_err(|e| AppError::io("Failed to replace backup catalog", e))
+    }
+
+    /// Newest first
+    pub fn entries(&self) -> Vec<BackupInfo> {
+        let mut entries = self.backups.clone();
+        entries.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        entries
+    }
+
+    pub fn find(&self, id: &str) -> Option<&BackupInfo> {
+        self.backups.iter().find(|backup| backup.id == id)
+    }
+
+    pub fn contains_path(&self, path: &Path) -> bool {
+        let path = path.to_string_lossy();
+        self.backups.iter().any(|backup| backup.path == path)
+    }
+
+    pub fn add(&mut self, info: BackupInfo) {
+        self.backups.push(info);
+    }
+
+    pub fn remove(&mut self, id: &str) -> Option<BackupInfo> {
+        let index = self.backups.iter().position(|backup| backup.id == id)?;
+        Some(self.backups.remove(index))
+    }
+}
+
+impl BackupInfo {
+    pub fn inspect(&self) -> BackupInspection {
+        let path = Path::new(&self.path);
+        let current_size_bytes = fs::metadata(path).ok().map(|metadata| metadata.len());
+        let checksum_valid = file_checksum(path).ok().map(|checksum| checksum == self.checksum);
+
+        BackupInspection {
+            info: self.clone(),
+            file_exists: path.exists(),
+            current_size_bytes,
+            checksum_valid,
+        }
+    }
+}
+
+pub fn file_checksum(path: &Path) -> AppResult<String> {
+    let mut file = fs::File::open(path)
+        .map_err(|e| AppError::io(&format!("Failed to open {:?}", path), e))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)
+            .map_err(|e| AppError::io(&format!("Failed to read {:?}", path), e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Where backups go when no destination is given. It sits next to the
+/// config file, so each profile keeps its own backups.
+pub fn default_backup_dir() -> AppResult<PathBuf> {
+    let backup_dir = get_data_dir()?.join("backups");
+    fs::create_dir_all(&backup_dir)
+        .map_err(|e| AppError::io("Failed to create backup directory", e))?;
+    Ok(backup_dir)
+}
+
+/// The single file older versions overwrote on every backup
+pub fn legacy_backup_path() -> AppResult<PathBuf> {
+    Ok(get_data_dir()?.join("database_backup.json"))
+}
+
+fn catalog_path() -> AppResult<PathBuf> {
+    Ok(default_backup_dir()?.join("catalog.json"))
+}
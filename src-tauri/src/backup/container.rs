@@ -0,0 +1,621 @@
+use crate::error::{AppError, AppResult};
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use argon2::{Algorithm, Argon2, Params, Version};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Cursor, Read, Write};
+use std::path::Path;
+
+/// First bytes of a backup container. Anything else is read as a plain backup.
+const MAGIC: &[u8; 8] = b"EDITARTB";
+/// 1: one JSON document, encrypted in a single piece.
+/// 2: NDJSON, encrypted in chunks so it can be streamed.
+const CONTAINER_VERSION: u32 = 2;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+/// Chunk nonces are this prefix, the chunk counter and a last-chunk flag
+const NONCE_PREFIX_LEN: usize = 7;
+const CHUNK_SIZE: usize = 64 * 1024;
+const TAG_LEN: usize = 16;
+/// Headers are a few hundred bytes; anything near this is not a backup
+const MAX_HEADER_LEN: usize = 64 * 1024;
+/// Upper bounds for the key derivation a header asks for, well above what
+/// the app writes, so a crafted backup cannot exhaust memory or CPU
+const MAX_KDF_MEMORY_KIB: u32 = 256 * 1024;
+const MAX_KDF_ITERATIONS: u32 = 16;
+const MAX_KDF_PARALLELISM: u32 = 16;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Compression {
+    #[default]
+    #[serde(rename = "none")]
+    None,
+    #[serde(rename = "gzip")]
+    Gzip,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Cipher {
+    #[serde(rename = "none")]
+    None,
+    /// AES-256-GCM with the key derived from the passphrase by Argon2id
+    #[serde(rename = "aes-256-gcm")]
+    Aes256Gcm,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyDerivation {
+    pub algorithm: String,
+    pub salt: Vec<u8>,
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+/// Stored in the clear after the magic bytes. When encrypted it is also the
+/// associated data, so tampering with it fails decryption.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContainerHeader {
+    pub format_version: u32,
+    pub compression: Compression,
+    pub cipher: Cipher,
+    pub key_derivation: Option<KeyDerivation>,
+    pub nonce: Option<Vec<u8>>,
+}
+
+/// How a backup file is written. The defaults give plain NDJSON.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BackupFileOptions {
+    #[serde(default)]
+    pub compression: Compression,
+    /// Encrypts the backup when set
+    #[serde(default)]
+    pub passphrase: Option<String>,
+}
+
+impl BackupFileOptions {
+    pub fn is_plain(&self) -> bool {
+        self.compression == Compression::None && self.passphrase.is_none()
+    }
+
+    pub fn file_extension(&self) -> &'static str {
+        if self.is_plain() { "ndjson" } else { "editart" }
+    }
+}
+
+fn derive_key(passphrase: &str, kdf: &KeyDerivation) -> AppResult<Aes256Gcm> {
+    if kdf.algorithm != "argon2id" {
+        return Err(AppError::Failed(format!("Unsupported key derivation: {}", kdf.algorithm)));
+    }
+    if kdf.memory_kib > MAX_KDF_MEMORY_KIB || kdf.iterations > MAX_KDF_ITERATIONS || kdf.parallelism > MAX_KDF_PARALLELISM {
+        return Err(AppError::Failed(format!(
+            "Key derivation parameters are too costly (memory {} KiB, {} iterations, parallelism {})",
+            kdf.memory_kib, kdf.iterations, kdf.parallelism
+        )));
+    }
+    let params = Params::new(kdf.memory_kib, kdf.iterations, kdf.parallelism, Some(32))
+        .map_err(|e| AppError::Failed(format!("Invalid key derivation parameters: {}", e)))?;
+
+    let mut key = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), &kdf.salt, &mut key)
+        .map_err(|e| AppError::Failed(format!("Failed to derive backup key: {}", e)))?;
+    Aes256Gcm::new_from_slice(&key)
+        .map_err(|e| AppError::Failed(format!("Failed to initialize backup cipher: {}", e)))
+}
+
+fn chunk_nonce(prefix: &[u8], counter: u32, last: bool) -> [u8; NONCE_LEN] {
+    let mut nonce = [0u8; NONCE_LEN];
+    nonce[..NONCE_PREFIX_LEN].copy_from_slice(prefix);
+    nonce[NONCE_PREFIX_LEN..NONCE_LEN - 1].copy_from_slice(&counter.to_be_bytes());
+    nonce[NONCE_LEN - 1] = u8::from(last);
+    nonce
+}
+
+/// Encrypts everything written to it in `CHUNK_SIZE` pieces. Each chunk is
+/// framed as `[last flag][u32 LE length][ciphertext]`; the flag is part of
+/// the nonce, so dropping trailing chunks is detected on read.
+pub struct EncryptWriter<W: Write> {
+    inner: W,
+    cipher: Box<Aes256Gcm>,
+    nonce_prefix: Vec<u8>,
+    aad: Vec<u8>,
+    counter: u32,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> EncryptWriter<W> {
+    fn write_chunk(&mut self, last: bool) -> io::Result<()> {
+        let nonce = chunk_nonce(&self.nonce_prefix, self.counter, last);
+        let ciphertext = self.cipher
+            .encrypt(Nonce::from_slice(&nonce), Payload { msg: &self.buffer, aad: &self.aad })
+            .map_err(|_| io::Error::other("Failed to encrypt backup"))?;
+
+        self.inner.write_all(&[u8::from(last)])?;
+        self.inner.write_all(&(ciphertext.len() as u32).to_le_bytes())?;
+        self.inner.write_all(&ciphertext)?;
+        self.buffer.clear();
+        self.counter = self.counter.checked_add(1)
+            .ok_or_else(|| io::Error::other("Backup is too large to encrypt"))?;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_chunk(true)?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for EncryptWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let take = data.len().min(CHUNK_SIZE - self.buffer.len());
+        self.buffer.extend_from_slice(&data[..take]);
+        if self.buffer.len() == CHUNK_SIZE {
+            self.write_chunk(false)?;
+        }
+        Ok(take)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+pub struct DecryptReader<R: Read> {
+    inner: R,
+    cipher: Box<Aes256Gcm>,
+    nonce_prefix: Vec<u8>,
+    aad: Vec<u8>,
+    counter: u32,
+    plain: Vec<u8>,
+    position: usize,
+    finished: bool,
+}
+
+impl<R: Read> DecryptReader<R> {
+    fn read_chunk(&mut self) -> io::Result<()> {
+        let mut flag = [0u8; 1];
+        if self.inner.read(&mut flag)? == 0 {
+            return Err(io::Error::other("Backup is truncated"));
+        }
+        let mut length = [0u8; 4];
+        self.inner.read_exact(&mut length)?;
+        let length = u32::from_le_bytes(length) as usize;
+        if length > CHUNK_SIZE + TAG_LEN {
+            return Err(io::Error::other("Corrupted backup: chunk is too large"));
+        }
+        let mut ciphertext = vec![0u8; length];
+        self.inner.read_exact(&mut ciphertext)?;
+
+        let last = flag[0] == 1;
+        let nonce = chunk_nonce(&self.nonce_prefix, self.counter, last);
+        self.plain = self.cipher
+            .decrypt(Nonce::from_slice(&nonce), Payload { msg: &ciphertext, aad: &self.aad })
+            .map_err(|_| io::Error::other("Wrong passphrase or corrupted backup"))?;
+        self.position = 0;
+        self.counter = self.counter.checked_add(1)
+            .ok_or_else(|| io::Error::other("Corrupted backup: too many chunks"))?;
+        self.finished = last;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for DecryptReader<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.plain.len() {
+            if self.finished {
+                return Ok(0);
+            }
+            self.read_chunk()?;
+        }
+        let count = out.len().min(self.plain.len() - self.position);
+        out[..count].copy_from_slice(&self.plain[self.position..self.position + count]);
+        self.position += count;
+        Ok(count)
+    }
+}
+
+/// Backup bytes go through compression, then encryption, then to the file.
+pub enum ContainerWriter {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+    Encrypted(EncryptWriter<BufWriter<File>>),
+    GzipEncrypted(GzEncoder<EncryptWriter<BufWriter<File>>>),
+}
+
+impl ContainerWriter {
+    /// Plain options write bare NDJSON with no container header.
+    pub fn create(path: &Path, options: &BackupFileOptions) -> AppResult<Self> {
+        let file = File::create(path)
+            .map_err(|e| AppError::io(&format!("Failed to create backup file {:?}", path), e))?;
+        let mut file = BufWriter::new(file);
+        if options.is_plain() {
+            return Ok(ContainerWriter::Plain(file));
+        }
+
+        let mut header = ContainerHeader {
+            format_version: CONTAINER_VERSION,
+            compression: options.compression,
+            cipher: Cipher::None,
+            key_derivation: None,
+            nonce: None,
+        };
+
+        let mut cipher = None;
+        if let Some(passphrase) = &options.passphrase {
+            let mut salt = vec![0u8; SALT_LEN];
+            OsRng.fill_bytes(&mut salt);
+            let mut nonce_prefix = vec![0u8; NONCE_PREFIX_LEN];
+            OsRng.fill_bytes(&mut nonce_prefix);
+
+            let defaults = Params::default();
+            let kdf = KeyDerivation {
+                algorithm: "argon2id".to_string(),
+                salt,
+                memory_kib: defaults.m_cost(),
+                iterations: defaults.t_cost(),
+                parallelism: defaults.p_cost(),
+            };
+            cipher = Some(derive_key(passphrase, &kdf)?);
+
+            header.cipher = Cipher::Aes256Gcm;
+            header.key_derivation = Some(kdf);
+            header.nonce = Some(nonce_prefix);
+        }
+
+        let header_bytes = serde_json::to_vec(&header)
+            .map_err(|e| AppError::Failed(format!("Failed to serialize backup header: {}", e)))?;
+        file.write_all(MAGIC)
+            .and_then(|_| file.write_all(&(header_bytes.len() as u32).to_le_bytes()))
+            .and_then(|_| file.write_all(&header_bytes))
+            .map_err(|e| AppError::io("Failed to write backup header", e))?;
+
+        let gzip_level = flate2::Compression::default();
+        Ok(match (cipher, header.nonce) {
+            (Some(cipher), Some(nonce_prefix)) => {
+                let encrypted = EncryptWriter {
+                    inner: file,
+                    cipher: Box::new(cipher),
+                    nonce_prefix,
+                    aad: header_bytes,
+                    counter: 0,
+                    buffer: Vec::with_capacity(CHUNK_SIZE),
+                };
+                match options.compression {
+                    Compression::None => ContainerWriter::Encrypted(encrypted),
+                    Compression::Gzip => ContainerWriter::GzipEncrypted(GzEncoder::new(encrypted, gzip_level)),
+                }
+            }
+            _ => ContainerWriter::Gzip(GzEncoder::new(file, gzip_level)),
+        })
+    }
+
+    /// Writes the trailing gzip block and last encrypted chunk.
+    pub fn finish(self) -> AppResult<()> {
+        let result = match self {
+            ContainerWriter::Plain(mut file) => file.flush(),
+            ContainerWriter::Gzip(gzip) => gzip.finish().and_then(|mut file| file.flush()),
+            ContainerWriter::Encrypted(encrypted) => encrypted.finish().and_then(|mut file| file.flush()),
+            ContainerWriter::GzipEncrypted(gzip) => gzip.finish()
+                .and_then(|encrypted| encrypted.finish())
+                .and_then(|mut file| file.flush()),
+        };
+        result.map_err(|e| AppError::io("Failed to finish backup file", e))
+    }
+}
+
+impl Write for ContainerWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        match self {
+            ContainerWriter::Plain(writer) => writer.write(data),
+            ContainerWriter::Gzip(writer) => writer.write(data),
+            ContainerWriter::Encrypted(writer) => writer.write(data),
+            ContainerWriter::GzipEncrypted(writer) => writer.write(data),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            ContainerWriter::Plain(writer) => writer.flush(),
+            ContainerWriter::Gzip(writer) => writer.flush(),
+            ContainerWriter::Encrypted(writer) => writer.flush(),
+            ContainerWriter::GzipEncrypted(writer) => writer.flush(),
+        }
+    }
+}
+
+pub enum ContainerReader {
+    Plain(BufReader<File>),
+    Gzip(GzDecoder<BufReader<File>>),
+    Encrypted(DecryptReader<BufReader<File>>),
+    GzipEncrypted(GzDecoder<DecryptReader<BufReader<File>>>),
+    /// Version 1 containers, decoded up front
+    Buffered(Cursor<Vec<u8>>),
+}
+
+impl ContainerReader {
+    /// Detects the container from its magic bytes; files without them are
+    /// read as they are (NDJSON or a legacy JSON document).
+    pub fn open(path: &Path, passphrase: Option<&str>) -> AppResult<Self> {
+        let file = File::open(path)
+            .map_err(|e| AppError::io(&format!("Failed to open backup file {:?}", path), e))?;
+        let mut file = BufReader::new(file);
+
+        let mut magic = [0u8; MAGIC.len()];
+        let magic_len = read_up_to(&mut file, &mut magic)
+            .map_err(|e| AppError::io("Failed to read backup file", e))?;
+        if magic_len < MAGIC.len() || &magic != MAGIC {
+            // Not a container: start over from the first byte
+            let file = File::open(path)
+                .map_err(|e| AppError::io(&format!("Failed to open backup file {:?}", path), e))?;
+            return Ok(ContainerReader::Plain(BufReader::new(file)));
+        }
+
+        let mut length = [0u8; 4];
+        file.read_exact(&mut length)
+            .map_err(|_| AppError::Failed("Backup header is truncated".to_string()))?;
+        let length = u32::from_le_bytes(length) as usize;
+        if length > MAX_HEADER_LEN {
+            return Err(AppError::Failed("Backup header is too large".to_string()));
+        }
+        let mut header_bytes = vec![0u8; length];
+        file.read_exact(&mut header_bytes)
+            .map_err(|_| AppError::Failed("Backup header is truncated".to_string()))?;
+        let header: ContainerHeader = serde_json::from_slice(&header_bytes)
+            .map_err(|e| AppError::Failed(format!("Failed to parse backup header: {}", e)))?;
+
+        if header.format_version > CONTAINER_VERSION {
+            return Err(AppError::Failed(format!(
+                "Backup container version {} is newer than this app supports ({})",
+                header.format_version, CONTAINER_VERSION
+            )));
+        }
+
+        let cipher = match header.cipher {
+            Cipher::None => None,
+            Cipher::Aes256Gcm => {
+                let passphrase = passphrase
+                    .ok_or_else(|| AppError::invalid("passphrase", "required", "This backup is encrypted, a passphrase is required"))?;
+                let kdf = header.key_derivation.as_ref()
+                    .ok_or_else(|| AppError::Failed("Encrypted backup has no key derivation parameters".to_string()))?;
+                Some(derive_key(passphrase, kdf)?)
+            }
+        };
+
+        if header.format_version == 1 {
+            let mut payload = Vec::new();
+            file.read_to_end(&mut payload)
+                .map_err(|e| AppError::io("Failed to read backup file", e))?;
+            let json = decode_single_piece(&header, &header_bytes, cipher, payload)?;
+            return Ok(ContainerReader::Buffered(Cursor::new(json)));
+        }
+
+        Ok(match cipher {
+            Some(cipher) => {
+                let nonce_prefix = header.nonce.clone()
+                    .filter(|nonce| nonce.len() == NONCE_PREFIX_LEN)
+                    .ok_or_else(|| AppError::Failed("Encrypted backup has no valid nonce".to_string()))?;
+                let decrypted = DecryptReader {
+                    inner: file,
+                    cipher: Box::new(cipher),
+                    nonce_prefix,
+                    aad: header_bytes,
+                    counter: 0,
+                    plain: Vec::new(),
+                    position: 0,
+                    finished: false,
+                };
+                match header.compression {
+                    Compression::None => ContainerReader::Encrypted(decrypted),
+                    Compression::Gzip => ContainerReader::GzipEncrypted(GzDecoder::new(decrypted)),
+                }
+            }
+            None => match header.compression {
+                Compression::None => ContainerReader::Plain(file),
+                Compression::Gzip => ContainerReader::Gzip(GzDecoder::new(file)),
+            },
+        })
+    }
+}
+
+impl Read for ContainerReader {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        match self {
+            ContainerReader::Plain(reader) => reader.read(out),
+            ContainerReader::Gzip(reader) => reader.read(out),
+            ContainerReader::Encrypted(reader) => reader.read(out),
+            ContainerReader::GzipEncrypted(reader) => reader.read(out),
+            ContainerReader::Buffered(reader) => reader.read(out),
+        }
+    }
+}
+
+fn read_up_to(reader: &mut impl Read, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..])? {
+            0 => break,
+            read => filled += read,
+        }
+    }
+    Ok(filled)
+}
+
+fn decode_single_piece(header: &ContainerHeader, header_bytes: &[u8], cipher: Option<Aes256Gcm>, mut payload: Vec<u8>) -> AppResult<Vec<u8>> {
+    if let Some(cipher) = cipher {
+        let nonce = header.nonce.as_ref()
+            .filter(|nonce| nonce.len() == NONCE_LEN)
+            .ok_or_else(|| AppError::Failed("Encrypted backup has no valid nonce".to_string()))?;
+        payload = cipher
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: &payload, aad: header_bytes })
+            .map_err(|_| AppError::invalid("passphrase", "incorrect", "Wrong passphrase or corrupted backup"))?;
+    }
+
+    match header.compression {
+        Compression::None => Ok(payload),
+        Compression::Gzip => {
+            let mut json = Vec::new();
+            GzDecoder::new(payload.as_slice())
+                .read_to_end(&mut json)
+                .map_err(|e| AppError::Failed(format!("Failed to decompress backup: {}", e)))?;
+            Ok(json)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Spans several chunks, with a partial one at the end
+    fn payload() -> Vec<u8> {
+        (0..CHUNK_SIZE * 2 + 123).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn temp_path() -> PathBuf {
+        std::env::temp_dir().join(format!("editart-container-{}.editart", uuid::Uuid::new_v4()))
+    }
+
+    fn write(path: &Path, options: &BackupFileOptions, data: &[u8]) {
+        let mut writer = ContainerWriter::create(path, options).unwrap();
+        writer.write_all(data).unwrap();
+        writer.finish().unwrap();
+    }
+
+    fn read(path: &Path, passphrase: Option<&str>) -> AppResult<io::Result<Vec<u8>>> {
+        let mut reader = ContainerReader::open(path, passphrase)?;
+        let mut data = Vec::new();
+        Ok(reader.read_to_end(&mut data).map(|_| data))
+    }
+
+    fn encrypted(compression: Compression) -> BackupFileOptions {
+        BackupFileOptions { compression, passphrase: Some("correct horse".to_string()) }
+    }
+
+    #[test]
+    fn round_trips_every_combination() {
+        let data = payload();
+        for options in [
+            BackupFileOptions::default(),
+            BackupFileOptions { compression: Compression::Gzip, passphrase: None },
+            encrypted(Compression::None),
+            encrypted(Compression::Gzip),
+        ] {
+            let path = temp_path();
+            write(&path, &options, &data);
+            let read_back = read(&path, options.passphrase.as_deref()).unwrap().unwrap();
+            std::fs::remove_file(&path).unwrap();
+            assert!(read_back == data, "{:?} did not round trip", options);
+        }
+    }
+
+    #[test]
+    fn plain_backups_have_no_header() {
+        let path = temp_path();
+        write(&path, &BackupFileOptions::default(), b"{\"table\":\"users\"}\n");
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(bytes, b"{\"table\":\"users\"}\n");
+    }
+
+    #[test]
+    fn encrypted_backups_need_the_right_passphrase() {
+        let path = temp_path();
+        write(&path, &encrypted(Compression::None), &payload());
+        let missing = read(&path, None);
+        let wrong = read(&path, Some("wrong horse"));
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(missing, Err(AppError::Validation(_))));
+        assert!(wrong.unwrap().is_err());
+    }
+
+    #[test]
+    fn rejects_tampered_ciphertext() {
+        let path = temp_path();
+        write(&path, &encrypted(Compression::None), &payload());
+        let mut bytes = std::fs::read(&path).unwrap();
+        let middle = bytes.len() / 2;
+        bytes[middle] ^= 0x01;
+        std::fs::write(&path, &bytes).unwrap();
+        let result = read(&path, Some("correct horse"));
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(result.unwrap().is_err());
+    }
+
+    #[test]
+    fn rejects_a_tampered_header() {
+        let path = temp_path();
+        write(&path, &encrypted(Compression::None), &payload());
+        let bytes = std::fs::read(&path).unwrap();
+        // Same length, still valid JSON, but no longer the associated data
+        let header_start = MAGIC.len() + 4;
+        let text = String::from_utf8_lossy(&bytes[header_start..]).into_owned();
+        let position = header_start + text.find("\"format_version\":2").unwrap() + "\"format_version\":".len();
+        let mut tampered = bytes.clone();
+        tampered[position] = b'1';
+        std::fs::write(&path, &tampered).unwrap();
+        let result = read(&path, Some("correct horse"));
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(!matches!(result, Ok(Ok(_))));
+    }
+
+    #[test]
+    fn rejects_dropped_trailing_chunks() {
+        let path = temp_path();
+        write(&path, &encrypted(Compression::None), &payload());
+        let bytes = std::fs::read(&path).unwrap();
+        // The last chunk holds the 123 trailing bytes plus its frame and tag
+        let last_chunk = 1 + 4 + 123 + 16;
+        std::fs::write(&path, &bytes[..bytes.len() - last_chunk]).unwrap();
+        let result = read(&path, Some("correct horse"));
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(result.unwrap().is_err());
+    }
+
+    #[test]
+    fn rejects_oversized_chunks() {
+        let path = temp_path();
+        write(&path, &encrypted(Compression::None), &payload());
+        let mut bytes = std::fs::read(&path).unwrap();
+        let header_len = u32::from_le_bytes(bytes[MAGIC.len()..MAGIC.len() + 4].try_into().unwrap()) as usize;
+        // Length of the first chunk, after its last-chunk flag
+        let length_at = MAGIC.len() + 4 + header_len + 1;
+        bytes[length_at..length_at + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        std::fs::write(&path, &bytes).unwrap();
+        let result = read(&path, Some("correct horse"));
+        std::fs::remove_file(&path).unwrap();
+
+        let error = result.unwrap().unwrap_err();
+        assert!(error.to_string().contains("too large"), "{}", error);
+    }
+
+    #[test]
+    fn refuses_costly_key_derivation() {
+        let kdf = |memory_kib, iterations, parallelism| KeyDerivation {
+            algorithm: "argon2id".to_string(),
+            salt: vec![0; SALT_LEN],
+            memory_kib,
+            iterations,
+            parallelism,
+        };
+        let defaults = Params::default();
+        assert!(derive_key("correct horse", &kdf(defaults.m_cost(), defaults.t_cost(), defaults.p_cost())).is_ok());
+
+        for costly in [
+            kdf(u32::MAX, defaults.t_cost(), defaults.p_cost()),
+            kdf(defaults.m_cost(), u32::MAX, defaults.p_cost()),
+            kdf(defaults.m_cost(), defaults.t_cost(), 1024),
+        ] {
+            assert!(matches!(derive_key("correct horse", &costly), Err(AppError::Failed(_))));
+        }
+    }
+}
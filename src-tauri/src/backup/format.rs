@@ -0,0 +1,355 @@
+use crate::backup::selection::BackupTable;
+use crate::error::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use time::OffsetDateTime;
+use rust_decimal::Decimal;
+
+/// Amounts are written as strings, which keep every digit. Backups that
+/// hold them as JSON numbers are still read, since `Decimal` accepts both.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseBackup {
+    /// App version that wrote the backup, informational only
+    pub version: String,
+    /// Migration the database was at; see `backup::upgrade`
+    pub schema_version: i64,
+    pub created_at: String,
+    pub users: Vec<UserBackup>,
+    pub clients: Vec<ClientBackup>,
+    pub orders: Vec<OrderBackup>,
+    pub impressions: Vec<ImpressionBackup>,
+    pub clothes: Vec<ClothesBackup>,
+    pub clothing_services: Vec<ClothingServiceBackup>,
+    #[serde(default)]
+    pub payments: Vec<PaymentBackup>,
+    #[serde(default)]
+    pub order_status_history: Vec<OrderStatusChangeBackup>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct UserBackup {
+    pub id: String,
+    pub login: String,
+    pub password: String,
+    pub role: String,
+    pub active: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ClientBackup {
+    pub id: String,
+    pub name: String,
+    pub nuit: String,
+    pub contact: String,
+    pub category: String,
+    pub observations: String,
+    #[serde(serialize_with = "rust_decimal::serde::str::serialize")]
+    pub debt: Decimal,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct OrderBackup {
+    pub id: String,
+    pub name: String,
+    pub client_id: String,
+    pub order_number: i32,
+    pub order_year: i32,
+    pub number_scope: String,
+    pub client_requisition_number: i32,
+    pub due_date: Option<String>,
+    #[serde(serialize_with = "rust_decimal::serde::str::serialize")]
+    pub discount: Decimal,
+    #[serde(serialize_with = "rust_decimal::serde::str::serialize")]
+    pub iva: Decimal,
+    #[serde(serialize_with = "rust_decimal::serde::str::serialize")]
+    pub subtotal: Decimal,
+    #[serde(serialize_with = "rust_decimal::serde::str::serialize")]
+    pub total: Decimal,
+    pub status: String,
+    #[serde(serialize_with = "rust_decimal::serde::str::serialize")]
+    pub debt: Decimal,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ImpressionBackup {
+    pub id: String,
+    pub order_id: String,
+    pub name: String,
+    pub size: String,
+    pub material: String,
+    pub description: String,
+    #[serde(serialize_with = "rust_decimal::serde::str::serialize")]
+    pub price: Decimal,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ClothesBackup {
+    pub id: String,
+    pub order_id: String,
+    pub clothing_type: String,
+    pub custom_type: Option<String>,
+    #[serde(serialize_with = "rust_decimal::serde::str::serialize")]
+    pub unit_price: Decimal,
+    pub sizes: String,
+    pub color: String,
+    pub total_quantity: i32,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ClothingServiceBackup {
+    pub id: String,
+    pub clothes_id: String,
+    pub service_type: String,
+    pub location: String,
+    pub description: Option<String>,
+    #[serde(serialize_with = "rust_decimal::serde::str::serialize")]
+    pub unit_price: Decimal,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PaymentBackup {
+    pub id: String,
+    pub order_id: String,
+    #[serde(serialize_with = "rust_decimal::serde::str::serialize")]
+    pub amount: Decimal,
+    pub payment_date: String,
+    pub method: String,
+    pub reference: Option<String>,
+    pub received_by: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct OrderStatusChangeBackup {
+    pub id: String,
+    pub order_id: String,
+    pub from_status: Option<String>,
+    pub to_status: String,
+    pub changed_by: Option<String>,
+    pub note: Option<String>,
+    pub changed_at: String,
+}
+
+/// Backups are upgraded before they are parsed (`backup::upgrade`), so
+/// every timestamp is RFC3339 by now.
+pub fn parse_datetime(date_str: &str) -> AppResult<OffsetDateTime> {
+    OffsetDateTime::parse(date_str, &time::format_description::well_known::Rfc3339)
+        .map_err(|e| AppError::Failed(format!("Failed to parse datetime {}: {}", date_str, e)))
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BackupRecordCounts {
+    pub users: usize,
+    pub clients: usize,
+    pub orders: usize,
+    pub impressions: usize,
+    pub clothes: usize,
+    pub clothing_services: usize,
+    pub payments: usize,
+    pub order_status_history: usize,
+}
+
+impl BackupRecordCounts {
+    pub fn get(&self, table: BackupTable) -> usize {
+        match table {
+            BackupTable::Users => self.users,
+            BackupTable::Clients => self.clients,
+            BackupTable::Orders => self.orders,
+            BackupTable::Impressions => self.impressions,
+            BackupTable::Clothes => self.clothes,
+            BackupTable::ClothingServices => self.clothing_services,
+            BackupTable::Payments => self.payments,
+            BackupTable::OrderStatusHistory => self.order_status_history,
+        }
+    }
+
+    pub fn set(&mut self, table: BackupTable, count: usize) {
+        let field = match table {
+            BackupTable::Users => &mut self.users,
+            BackupTable::Clients => &mut self.clients,
+            BackupTable::Orders => &mut self.orders,
+            BackupTable::Impressions => &mut self.impressions,
+            BackupTable::Clothes => &mut self.clothes,
+            BackupTable::ClothingServices => &mut self.clothing_services,
+            BackupTable::Payments => &mut self.payments,
+            BackupTable::OrderStatusHistory => &mut self.order_status_history,
+        };
+        *field = count;
+    }
+
+    pub fn increment(&mut self, table: BackupTable) {
+        self.set(table, self.get(table) + 1);
+    }
+}
+
+/// One row of any backed-up table, the unit backups are streamed in
+#[derive(Debug, Clone)]
+pub enum BackupRow {
+    User(UserBackup),
+    Client(ClientBackup),
+    Order(OrderBackup),
+    Impression(ImpressionBackup),
+    Clothes(ClothesBackup),
+    ClothingService(ClothingServiceBackup),
+    Payment(PaymentBackup),
+    OrderStatusChange(OrderStatusChangeBackup),
+}
+
+impl BackupRow {
+    pub fn table(&self) -> BackupTable {
+        match self {
+            BackupRow::User(_) => BackupTable::Users,
+            BackupRow::Client(_) => BackupTable::Clients,
+            BackupRow::Order(_) => BackupTable::Orders,
+            BackupRow::Impression(_) => BackupTable::Impressions,
+            BackupRow::Clothes(_) => BackupTable::Clothes,
+            BackupRow::ClothingService(_) => BackupTable::ClothingServices,
+            BackupRow::Payment(_) => BackupTable::Payments,
+            BackupRow::OrderStatusChange(_) => BackupTable::OrderStatusHistory,
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        match self {
+            BackupRow::User(user) => &user.id,
+            BackupRow::Client(client) => &client.id,
+            BackupRow::Order(order) => &order.id,
+            BackupRow::Impression(impression) => &impression.id,
+            BackupRow::Clothes(clothes) => &clothes.id,
+            BackupRow::ClothingService(service) => &service.id,
+            BackupRow::Payment(payment) => &payment.id,
+            BackupRow::OrderStatusChange(change) => &change.id,
+        }
+    }
+
+    pub fn from_value(table: BackupTable, value: serde_json::Value) -> Result<Self, serde_json::Error> {
+        Ok(match table {
+            BackupTable::Users => BackupRow::User(serde_json::from_value(value)?),
+            BackupTable::Clients => BackupRow::Client(serde_json::from_value(value)?),
+            BackupTable::Orders => BackupRow::Order(serde_json::from_value(value)?),
+            BackupTable::Impressions => BackupRow::Impression(serde_json::from_value(value)?),
+            BackupTable::Clothes => BackupRow::Clothes(serde_json::from_value(value)?),
+            BackupTable::ClothingServices => BackupRow::ClothingService(serde_json::from_value(value)?),
+            BackupTable::Payments => BackupRow::Payment(serde_json::from_value(value)?),
+            BackupTable::OrderStatusHistory => BackupRow::OrderStatusChange(serde_json::from_value(value)?),
+        })
+    }
+
+    pub fn to_value(&self) -> Result<serde_json::Value, serde_json::Error> {
+        match self {
+            BackupRow::User(user) => serde_json::to_value(user),
+            BackupRow::Client(client) => serde_json::to_value(client),
+            BackupRow::Order(order) => serde_json::to_value(order),
+            BackupRow::Impression(impression) => serde_json::to_value(impression),
+            BackupRow::Clothes(clothes) => serde_json::to_value(clothes),
+            BackupRow::ClothingService(service) => serde_json::to_value(service),
+            BackupRow::Payment(payment) => serde_json::to_value(payment),
+            BackupRow::OrderStatusChange(change) => serde_json::to_value(change),
+        }
+    }
+}
+
+impl DatabaseBackup {
+    pub fn new(version: String, schema_version: i64, created_at: String) -> Self {
+        DatabaseBackup {
+            version,
+            schema_version,
+            created_at,
+            users: Vec::new(),
+            clients: Vec::new(),
+            orders: Vec::new(),
+            impressions: Vec::new(),
+            clothes: Vec::new(),
+            clothing_services: Vec::new(),
+            payments: Vec::new(),
+            order_status_history: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, row: BackupRow) {
+        match row {
+            BackupRow::User(user) => self.users.push(user),
+            BackupRow::Client(client) => self.clients.push(client),
+            BackupRow::Order(order) => self.orders.push(order),
+            BackupRow::Impression(impression) => self.impressions.push(impression),
+            BackupRow::Clothes(clothes) => self.clothes.push(clothes),
+            BackupRow::ClothingService(service) => self.clothing_services.push(service),
+            BackupRow::Payment(payment) => self.payments.push(payment),
+            BackupRow::OrderStatusChange(change) => self.order_status_history.push(change),
+        }
+    }
+
+    /// Every row, tables in dependency order
+    pub fn rows(&self) -> impl Iterator<Item = BackupRow> + '_ {
+        self.users.iter().cloned().map(BackupRow::User)
+            .chain(self.clients.iter().cloned().map(BackupRow::Client))
+            .chain(self.orders.iter().cloned().map(BackupRow::Order))
+            .chain(self.impressions.iter().cloned().map(BackupRow::Impression))
+            .chain(self.clothes.iter().cloned().map(BackupRow::Clothes))
+            .chain(self.clothing_services.iter().cloned().map(BackupRow::ClothingService))
+            .chain(self.payments.iter().cloned().map(BackupRow::Payment))
+            .chain(self.order_status_history.iter().cloned().map(BackupRow::OrderStatusChange))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::str::FromStr;
+
+    fn payment(amount: &str) -> BackupRow {
+        BackupRow::Payment(PaymentBackup {
+            id: "payment-1".to_string(),
+            order_id: "order-1".to_string(),
+            amount: Decimal::from_str(amount).unwrap(),
+            payment_date: "2025-03-01T10:00:00Z".to_string(),
+            method: "cash".to_string(),
+            reference: None,
+            received_by: None,
+            created_at: "2025-03-01T10:00:00Z".to_string(),
+            updated_at: "2025-03-01T10:00:00Z".to_string(),
+        })
+    }
+
+    fn amount(row: BackupRow) -> Decimal {
+        match row {
+            BackupRow::Payment(payment) => payment.amount,
+            other => panic!("expected a payment, got {:?}", other.table()),
+        }
+    }
+
+    #[test]
+    fn amounts_are_written_as_strings_and_read_back_exactly() {
+        // Written as given: as a float, 0.10 would become 0.1
+        for value in ["0.10", "123456789012.34", "9999999999999.99"] {
+            let written = payment(value).to_value().unwrap();
+            assert_eq!(written["amount"], json!(value));
+
+            let read = BackupRow::from_value(BackupTable::Payments, written).unwrap();
+            assert_eq!(amount(read), Decimal::from_str(value).unwrap());
+        }
+    }
+
+    #[test]
+    fn amounts_written_as_numbers_are_still_read() {
+        let mut written = payment("0").to_value().unwrap();
+        written["amount"] = json!(25.5);
+
+        let read = BackupRow::from_value(BackupTable::Payments, written).unwrap();
+        assert_eq!(amount(read), Decimal::from_str("25.5").unwrap());
+    }
+}
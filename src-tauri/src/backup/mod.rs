@@ -0,0 +1,13 @@
+pub mod catalog;
+pub mod container;
+pub mod format;
+pub mod pg_tools;
+pub mod preview;
+pub mod retention;
+pub mod scheduler;
+pub mod selection;
+pub mod service;
+pub mod stream;
+pub mod upgrade;
+
+pub use service::BackupService;
@@ -0,0 +1,131 @@
+use crate::config::{DatabaseConfig, PgToolsConfig};
+use crate::error::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::{Output, Stdio};
+use tokio::process::Command;
+
+/// How much of a failing tool's stderr ends up in the error
+const STDERR_TAIL_LINES: usize = 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PgToolsInfo {
+    pub pg_dump_path: String,
+    pub pg_dump_version: String,
+    pub pg_restore_path: String,
+    pub pg_restore_version: String,
+}
+
+/// The local `pg_dump` and `pg_restore`, run with the app's database
+/// credentials. The password goes through `PGPASSWORD`, never the command line.
+pub struct PgTools {
+    pg_dump: PathBuf,
+    pg_restore: PathBuf,
+}
+
+impl PgTools {
+    /// Resolves both tools, checking a configured directory actually has them.
+    pub fn locate(config: &PgToolsConfig) -> AppResult<Self> {
+        Ok(PgTools {
+            pg_dump: tool_path(config.bin_dir.as_deref(), "pg_dump")?,
+            pg_restore: tool_path(config.bin_dir.as_deref(), "pg_restore")?,
+        })
+    }
+
+    /// Runs both tools once, which is the only way to know they work.
+    pub async fn check(&self) -> AppResult<PgToolsInfo> {
+        Ok(PgToolsInfo {
+            pg_dump_path: self.pg_dump.to_string_lossy().into_owned(),
+            pg_dump_version: version(&self.pg_dump).await?,
+            pg_restore_path: self.pg_restore.to_string_lossy().into_owned(),
+            pg_restore_version: version(&self.pg_restore).await?,
+        })
+    }
+
+    /// Dumps the whole database to `path` in pg_dump's custom format.
+    pub async fn dump(&self, database: &DatabaseConfig, path: &Path) -> AppResult<()> {
+        log::info!("Running pg_dump to {:?}", path);
+        let mut command = connect_command(&self.pg_dump, database);
+        command.arg("--format=custom").arg("--file").arg(path);
+        run(command, &self.pg_dump).await.map(|_| ())
+    }
+
+    /// Replaces the database with the dump at `path`. Everything runs in one
+    /// transaction, so a failed restore leaves the data as it was.
+    pub async fn restore(&self, database: &DatabaseConfig, path: &Path) -> AppResult<()> {
+        log::info!("Running pg_restore from {:?}", path);
+        let mut command = connect_command(&self.pg_restore, database);
+        command
+            .args(["--clean", "--if-exists", "--no-owner", "--single-transaction", "--exit-on-error"])
+            .arg(path);
+        run(command, &self.pg_restore).await.map(|_| ())
+    }
+}
+
+fn tool_path(bin_dir: Option<&str>, name: &str) -> AppResult<PathBuf> {
+    let file_name = format!("{}{}", name, std::env::consts::EXE_SUFFIX);
+    let Some(bin_dir) = bin_dir.filter(|dir| !dir.trim().is_empty()) else {
+        return Ok(PathBuf::from(file_name));
+    };
+
+    let dir = Path::new(bin_dir);
+    if !dir.is_dir() {
+        return Err(AppError::Config(format!("PostgreSQL tools directory {:?} does not exist", dir)));
+    }
+    let path = dir.join(&file_name);
+    if !path.is_file() {
+        return Err(AppError::Config(format!("{} not found in {:?}", file_name, dir)));
+    }
+    Ok(path)
+}
+
+fn connect_command(program: &Path, database: &DatabaseConfig) -> Command {
+    let mut command = Command::new(program);
+    command
+        .env("PGPASSWORD", &database.password)
+        .env("PGSSLMODE", database.ssl_mode.as_str())
+        .arg("--host").arg(&database.host)
+        .arg("--port").arg(database.port.to_string())
+        .arg("--username").arg(&database.user)
+        .arg("--dbname").arg(&database.dbname)
+        .arg("--no-password");
+    command
+}
+
+async fn version(program: &Path) -> AppResult<String> {
+    let mut command = Command::new(program);
+    command.arg("--version");
+    let output = run(command, program).await?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+async fn run(mut command: Command, program: &Path) -> AppResult<Output> {
+    let name = program.file_name().unwrap_or(program.as_os_str()).to_string_lossy();
+    let output = command
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| match e.kind() {
+            ErrorKind::NotFound => AppError::Config(format!(
+                "{} was not found. Install the PostgreSQL client tools or set their directory in the settings",
+                name
+            )),
+            _ => AppError::io(&format!("Failed to run {}", name), e),
+        })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let lines: Vec<&str> = stderr.lines().filter(|line| !line.trim().is_empty()).collect();
+        let tail = lines[lines.len().saturating_sub(STDERR_TAIL_LINES)..].join("\n");
+        let error_msg = if tail.is_empty() {
+            format!("{} failed ({})", name, output.status)
+        } else {
+            format!("{} failed ({}): {}", name, output.status, tail)
+        };
+        log::error!("{}", error_msg);
+        return Err(AppError::Failed(error_msg));
+    }
+    Ok(output)
+}
@@ -0,0 +1,203 @@
+use crate::backup::catalog::BackupInfo;
+use crate::backup::format::{BackupRow, DatabaseBackup};
+use crate::backup::selection::{BackupTable, RestoreOptions};
+use crate::error::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+/// What a restore would do to one table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableDiff {
+    pub table: BackupTable,
+    /// Rows the restore writes
+    pub backup_count: usize,
+    pub live_count: usize,
+    /// Written rows with no live row of the same id
+    pub added: usize,
+    /// Live rows the restore deletes
+    pub removed: usize,
+    /// Written rows whose live row differs
+    pub changed: usize,
+    pub unchanged: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrityIssue {
+    pub table: BackupTable,
+    pub record_id: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestorePreview {
+    pub backup: BackupInfo,
+    pub full_restore: bool,
+    pub tables: Vec<TableDiff>,
+    /// A restore is refused while this is not empty
+    pub integrity_issues: Vec<IntegrityIssue>,
+}
+
+impl RestorePreview {
+    pub fn build(backup: BackupInfo, selected: &DatabaseBackup, live: &DatabaseBackup, options: &RestoreOptions) -> AppResult<Self> {
+        // Rows of the chosen clients' orders disappear unless the backup has them too
+        let live_scope = if options.replaces_client_orders() {
+            Some(live.select(options))
+        } else {
+            None
+        };
+
+        let mut tables = Vec::new();
+        for table in BackupTable::ALL {
+            if !options.includes(table) {
+                continue;
+            }
+            let removable = if options.is_full() {
+                Some(live)
+            } else if table == BackupTable::Orders || table.is_order_child() {
+                live_scope.as_ref()
+            } else {
+                None
+            };
+            tables.push(diff_table(table, selected, live, removable)?);
+        }
+
+        let live_parents = if options.is_full() { None } else { Some(live) };
+        Ok(Self {
+            backup,
+            full_restore: options.is_full(),
+            tables,
+            integrity_issues: integrity_issues(selected, live_parents),
+        })
+    }
+}
+
+fn table_rows(backup: &DatabaseBackup, table: BackupTable) -> AppResult<Vec<Value>> {
+    backup.rows()
+        .filter(|row| row.table() == table)
+        .map(|row| row.to_value())
+        .collect::<Result<_, _>>()
+        .map_err(|e| AppError::Failed(format!("Failed to compare {}: {}", table.as_str(), e)))
+}
+
+fn row_id(row: &Value) -> String {
+    row.get("id").and_then(Value::as_str).unwrap_or_default().to_string()
+}
+
+/// Rows are matched by id and compared field by field in their backup form,
+/// so both sides go through the same serialization.
+fn diff_table(table: BackupTable, selected: &DatabaseBackup, live: &DatabaseBackup, removable: Option<&DatabaseBackup>) -> AppResult<TableDiff> {
+    let backup_rows = table_rows(selected, table)?;
+    let live_rows: HashMap<String, Value> = table_rows(live, table)?
+        .into_iter()
+        .map(|row| (row_id(&row), row))
+        .collect();
+
+    let mut diff = TableDiff {
+        table,
+        backup_count: backup_rows.len(),
+        live_count: live_rows.len(),
+        added: 0,
+        removed: 0,
+        changed: 0,
+        unchanged: 0,
+    };
+
+    let mut backup_ids = HashSet::new();
+    for row in &backup_rows {
+        let id = row_id(row);
+        match live_rows.get(&id) {
+            None => diff.added += 1,
+            Some(live_row) if live_row == row => diff.unchanged += 1,
+            Some(_) => diff.changed += 1,
+        }
+        backup_ids.insert(id);
+    }
+
+    if let Some(removable) = removable {
+        diff.removed = table_rows(removable, table)?
+            .iter()
+            .filter(|row| !backup_ids.contains(&row_id(row)))
+            .count();
+    }
+
+    Ok(diff)
+}
+
+/// Finds references the restored rows make to rows that will not exist.
+/// Rows are checked as they come, in dependency order; a parent counts when
+/// it was checked before or, for a partial restore, is live.
+#[derive(Default)]
+pub struct IntegrityChecker {
+    user_ids: HashSet<String>,
+    client_ids: HashSet<String>,
+    order_ids: HashSet<String>,
+    clothes_ids: HashSet<String>,
+    issues: Vec<IntegrityIssue>,
+}
+
+impl IntegrityChecker {
+    /// Records a row as an available parent without checking it
+    pub fn add_known(&mut self, table: BackupTable, id: &str) {
+        let ids = match table {
+            BackupTable::Users => &mut self.user_ids,
+            BackupTable::Clients => &mut self.client_ids,
+            BackupTable::Orders => &mut self.order_ids,
+            BackupTable::Clothes => &mut self.clothes_ids,
+            _ => return,
+        };
+        ids.insert(id.to_string());
+    }
+
+    pub fn check(&mut self, row: &BackupRow) {
+        let mut references: Vec<(&str, &str, &HashSet<String>)> = Vec::new();
+        match row {
+            BackupRow::User(_) | BackupRow::Client(_) => {}
+            BackupRow::Order(order) => references.push(("Client", &order.client_id, &self.client_ids)),
+            BackupRow::Impression(impression) => references.push(("Order", &impression.order_id, &self.order_ids)),
+            BackupRow::Clothes(clothes) => references.push(("Order", &clothes.order_id, &self.order_ids)),
+            BackupRow::ClothingService(service) => references.push(("Clothes", &service.clothes_id, &self.clothes_ids)),
+            BackupRow::Payment(payment) => {
+                references.push(("Order", &payment.order_id, &self.order_ids));
+                if let Some(received_by) = &payment.received_by {
+                    references.push(("User", received_by, &self.user_ids));
+                }
+            }
+            BackupRow::OrderStatusChange(change) => {
+                references.push(("Order", &change.order_id, &self.order_ids));
+                if let Some(changed_by) = &change.changed_by {
+                    references.push(("User", changed_by, &self.user_ids));
+                }
+            }
+        }
+
+        let missing: Vec<IntegrityIssue> = references
+            .into_iter()
+            .filter(|(_, id, known)| !known.contains(*id))
+            .map(|(parent, id, _)| IntegrityIssue {
+                table: row.table(),
+                record_id: row.id().to_string(),
+                message: format!("{} {} not found", parent, id),
+            })
+            .collect();
+        self.issues.extend(missing);
+        self.add_known(row.table(), row.id());
+    }
+
+    pub fn into_issues(self) -> Vec<IntegrityIssue> {
+        self.issues
+    }
+}
+
+/// `IntegrityChecker` over a backup held in memory. Parents can come from
+/// the backup itself or, for a partial restore, from the live database.
+pub fn integrity_issues(selected: &DatabaseBackup, live: Option<&DatabaseBackup>) -> Vec<IntegrityIssue> {
+    let mut checker = IntegrityChecker::default();
+    for row in live.into_iter().flat_map(DatabaseBackup::rows) {
+        checker.add_known(row.table(), row.id());
+    }
+    for row in selected.rows() {
+        checker.check(&row);
+    }
+    checker.into_issues()
+}
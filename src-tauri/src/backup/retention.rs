@@ -0,0 +1,124 @@
+use crate::backup::catalog::{BackupInfo, BackupKind};
+use crate::backup::format::parse_datetime;
+use crate::local_time::local_offset;
+use crate::config::RetentionPolicy;
+use std::collections::HashSet;
+
+/// Scheduled backups outside the retention policy. Each daily, weekly and
+/// monthly slot keeps the newest backup taken in that period; a backup kept
+/// by any slot survives.
+pub fn backups_to_prune(backups: &[BackupInfo], policy: &RetentionPolicy) -> Vec<BackupInfo> {
+    let offset = local_offset();
+    let mut scheduled: Vec<(&BackupInfo, time::OffsetDateTime)> = backups
+        .iter()
+        .filter(|info| info.kind == BackupKind::Scheduled)
+        .filter_map(|info| match parse_datetime(&info.created_at) {
+            Ok(created_at) => Some((info, created_at.to_offset(offset))),
+            Err(e) => {
+                // Never prune what we cannot date
+                log::warn!("Skipping backup {} in retention: {}", info.id, e);
+                None
+            }
+        })
+        .collect();
+    scheduled.sort_by_key(|(_, created_at)| std::cmp::Reverse(*created_at));
+
+    let mut keep: HashSet<&str> = HashSet::new();
+    let mut keep_newest_per_period = |limit: usize, period: &dyn Fn(time::OffsetDateTime) -> (i32, u32)| {
+        let mut seen = HashSet::new();
+        for (info, created_at) in &scheduled {
+            if seen.len() >= limit {
+                break;
+            }
+            if seen.insert(period(*created_at)) {
+                keep.insert(info.id.as_str());
+            }
+        }
+    };
+
+    keep_newest_per_period(policy.daily, &|date| (date.year(), u32::from(date.ordinal())));
+    keep_newest_per_period(policy.weekly, &|date| {
+        let (year, week, _) = date.to_iso_week_date();
+        (year, u32::from(week))
+    });
+    keep_newest_per_period(policy.monthly, &|date| (date.year(), u32::from(u8::from(date.month()))));
+
+    scheduled
+        .iter()
+        .filter(|(info, _)| !keep.contains(info.id.as_str()))
+        .map(|(info, _)| (*info).clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::catalog::BackupFormat;
+    use crate::backup::container::Compression;
+    use crate::backup::format::BackupRecordCounts;
+
+    fn backup(id: &str, kind: BackupKind, created_at: &str) -> BackupInfo {
+        BackupInfo {
+            id: id.to_string(),
+            file_name: format!("{}.ndjson", id),
+            kind,
+            format: BackupFormat::Json,
+            compression: Compression::None,
+            encrypted: false,
+            path: format!("/backups/{}.ndjson", id),
+            created_at: created_at.to_string(),
+            version: "1.0.0".to_string(),
+            schema_version: 8,
+            size_bytes: 0,
+            checksum: String::new(),
+            record_counts: BackupRecordCounts::default(),
+        }
+    }
+
+    fn scheduled(id: &str, created_at: &str) -> BackupInfo {
+        backup(id, BackupKind::Scheduled, created_at)
+    }
+
+    fn policy(daily: usize, weekly: usize, monthly: usize) -> RetentionPolicy {
+        RetentionPolicy { daily, weekly, monthly }
+    }
+
+    fn pruned_ids(backups: &[BackupInfo], policy: &RetentionPolicy) -> Vec<String> {
+        backups_to_prune(backups, policy).into_iter().map(|info| info.id).collect()
+    }
+
+    #[test]
+    fn keeps_the_newest_backup_of_each_recent_day() {
+        let backups = [
+            scheduled("day1-morning", "2025-03-01T10:00:00Z"),
+            scheduled("day1-night", "2025-03-01T22:00:00Z"),
+            scheduled("day2", "2025-03-02T10:00:00Z"),
+            scheduled("day3", "2025-03-03T10:00:00Z"),
+        ];
+        assert_eq!(pruned_ids(&backups, &policy(2, 0, 0)), ["day1-night", "day1-morning"]);
+    }
+
+    #[test]
+    fn a_backup_kept_by_any_slot_survives() {
+        let backups = [
+            scheduled("jan", "2025-01-10T10:00:00Z"),
+            scheduled("feb", "2025-02-15T10:00:00Z"),
+            scheduled("sat-w13", "2025-03-29T10:00:00Z"),
+            scheduled("sun-w13", "2025-03-30T10:00:00Z"),
+            scheduled("mon-w14", "2025-03-31T10:00:00Z"),
+        ];
+        // Daily keeps mon-w14, weekly adds sun-w13, monthly adds feb
+        assert_eq!(pruned_ids(&backups, &policy(1, 2, 2)), ["sat-w13", "jan"]);
+    }
+
+    #[test]
+    fn never_prunes_manual_pre_restore_or_undated_backups() {
+        let backups = [
+            backup("manual", BackupKind::Manual, "2020-01-01T00:00:00Z"),
+            backup("snapshot", BackupKind::PreRestore, "2020-01-01T00:00:00Z"),
+            scheduled("undated", "not a date"),
+            scheduled("old", "2020-01-01T00:00:00Z"),
+        ];
+        assert_eq!(pruned_ids(&backups, &policy(0, 0, 0)), ["old"]);
+    }
+}
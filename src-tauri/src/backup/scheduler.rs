@@ -0,0 +1,125 @@
+use crate::backup::catalog::{BackupInfo, BackupKind};
+use crate::backup::format::parse_datetime;
+use crate::backup::stream::emit_progress;
+use crate::backup::BackupService;
+use crate::config::{load_config, BackupSchedule, BackupScheduleConfig};
+use crate::database::is_connected;
+use crate::error::{AppError, AppResult};
+use crate::local_time::local_offset;
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use time::{OffsetDateTime, Time};
+
+/// Emitted after every automatic backup, successful or not
+pub const SCHEDULED_BACKUP_EVENT: &str = "scheduled-backup";
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduledBackupEvent {
+    pub backup: Option<BackupInfo>,
+    /// Older scheduled backups removed by the retention policy
+    pub pruned: Vec<BackupInfo>,
+    pub error: Option<String>,
+}
+
+/// Rejects schedules the scheduler could never run.
+pub fn validate_schedule(schedule: &BackupSchedule) -> AppResult<()> {
+    match schedule {
+        BackupSchedule::Daily { time } => parse_time_of_day(time)
+            .map(|_| ())
+            .map_err(|message| AppError::invalid("schedule.time", "invalid_time", message)),
+        BackupSchedule::EveryHours { hours } if *hours == 0 => Err(AppError::invalid(
+            "schedule.hours",
+            "too_small",
+            "Backup interval must be at least 1 hour",
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn parse_time_of_day(value: &str) -> Result<Time, String> {
+    let invalid = || format!("Invalid backup time '{}', expected HH:MM", value);
+    let (hour, minute) = value.split_once(':').ok_or_else(invalid)?;
+    let hour: u8 = hour.trim().parse().map_err(|_| invalid())?;
+    let minute: u8 = minute.trim().parse().map_err(|_| invalid())?;
+    Time::from_hms(hour, minute, 0).map_err(|_| invalid())
+}
+
+fn is_due(schedule: &BackupSchedule, last_run: Option<OffsetDateTime>, now: OffsetDateTime) -> bool {
+    match schedule {
+        BackupSchedule::Daily { time } => match parse_time_of_day(time) {
+            Ok(time) => {
+                let today_at = now.replace_time(time);
+                now >= today_at && last_run.is_none_or(|last| last < today_at)
+            }
+            Err(_) => false,
+        },
+        BackupSchedule::EveryHours { hours } if *hours > 0 => {
+            last_run.is_none_or(|last| now - last >= time::Duration::hours(i64::from(*hours)))
+        }
+        _ => false,
+    }
+}
+
+fn last_scheduled_backup() -> Option<OffsetDateTime> {
+    let backups = BackupService::list_backups().ok()?;
+    backups
+        .iter()
+        .filter(|info| info.kind == BackupKind::Scheduled)
+        .filter_map(|info| parse_datetime(&info.created_at).ok())
+        .max()
+}
+
+/// Checks the schedule once a minute. The config is re-read on every check,
+/// so schedule changes apply without a restart.
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        // A failed attempt also counts, so a broken destination is not retried every minute
+        let mut last_attempt: Option<OffsetDateTime> = None;
+
+        loop {
+            let config = load_config().map(|config| config.backup).unwrap_or_default();
+            let now = OffsetDateTime::now_utc().to_offset(local_offset());
+            let last_run = last_scheduled_backup().max(last_attempt);
+
+            if is_connected() && is_due(&config.schedule, last_run, now) {
+                last_attempt = Some(now);
+                run_backup(&app, &config).await;
+            }
+
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
+
+/// Called while the app shuts down, for the on-close schedule.
+pub async fn run_on_close(app: &AppHandle) {
+    let config = load_config().map(|config| config.backup).unwrap_or_default();
+    if config.schedule == BackupSchedule::OnClose {
+        run_backup(app, &config).await;
+    }
+}
+
+async fn run_backup(app: &AppHandle, config: &BackupScheduleConfig) {
+    log::info!("Running scheduled backup...");
+
+    let event = match BackupService::create_scheduled_backup(config.destination.clone(), config.compression, &emit_progress(app)).await {
+        Ok(info) => {
+            let (pruned, error) = match BackupService::apply_retention(&config.retention) {
+                Ok(pruned) => (pruned, None),
+                Err(e) => (Vec::new(), Some(format!("Backup created but retention failed: {}", e))),
+            };
+            ScheduledBackupEvent { backup: Some(info), pruned, error }
+        }
+        Err(e) => {
+            log::error!("Scheduled backup failed: {}", e);
+            ScheduledBackupEvent { backup: None, pruned: Vec::new(), error: Some(e.to_string()) }
+        }
+    };
+
+    if let Err(e) = app.emit(SCHEDULED_BACKUP_EVENT, event) {
+        log::error!("Failed to emit scheduled backup event: {}", e);
+    }
+}
@@ -0,0 +1,166 @@
+use crate::backup::format::{BackupRow, DatabaseBackup};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BackupTable {
+    #[serde(rename = "users")]
+    Users,
+    #[serde(rename = "clients")]
+    Clients,
+    #[serde(rename = "orders")]
+    Orders,
+    #[serde(rename = "impressions")]
+    Impressions,
+    #[serde(rename = "clothes")]
+    Clothes,
+    #[serde(rename = "clothing_services")]
+    ClothingServices,
+    #[serde(rename = "payments")]
+    Payments,
+    #[serde(rename = "order_status_history")]
+    OrderStatusHistory,
+}
+
+impl BackupTable {
+    /// In dependency order, parents first
+    pub const ALL: [BackupTable; 8] = [
+        BackupTable::Users,
+        BackupTable::Clients,
+        BackupTable::Orders,
+        BackupTable::Impressions,
+        BackupTable::Clothes,
+        BackupTable::ClothingServices,
+        BackupTable::Payments,
+        BackupTable::OrderStatusHistory,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BackupTable::Users => "users",
+            BackupTable::Clients => "clients",
+            BackupTable::Orders => "orders",
+            BackupTable::Impressions => "impressions",
+            BackupTable::Clothes => "clothes",
+            BackupTable::ClothingServices => "clothing_services",
+            BackupTable::Payments => "payments",
+            BackupTable::OrderStatusHistory => "order_status_history",
+        }
+    }
+
+    /// Tables whose rows are deleted along with their order (ON DELETE CASCADE)
+    pub fn is_order_child(&self) -> bool {
+        matches!(
+            self,
+            BackupTable::Impressions
+                | BackupTable::Clothes
+                | BackupTable::ClothingServices
+                | BackupTable::Payments
+                | BackupTable::OrderStatusHistory
+        )
+    }
+}
+
+/// Which part of a backup to restore. With neither field set the whole
+/// database is replaced.
+///
+/// - `tables`: only these tables are written. Rows are inserted or updated
+///   by id; live rows missing from the backup are kept.
+/// - `client_ids`: only these clients and their orders. The orders of those
+///   clients are replaced as a whole, so everything under an order (garments,
+///   impressions, payments, status history) always comes along with it.
+///   Users are left alone unless `tables` lists them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RestoreOptions {
+    #[serde(default)]
+    pub tables: Option<Vec<BackupTable>>,
+    #[serde(default)]
+    pub client_ids: Option<Vec<String>>,
+}
+
+impl RestoreOptions {
+    pub fn is_full(&self) -> bool {
+        self.tables.is_none() && self.client_ids.is_none()
+    }
+
+    pub fn includes(&self, table: BackupTable) -> bool {
+        if self.client_ids.is_some() && table.is_order_child() {
+            return self.includes(BackupTable::Orders);
+        }
+        match &self.tables {
+            Some(tables) => tables.contains(&table),
+            None => table != BackupTable::Users || self.client_ids.is_none(),
+        }
+    }
+
+    /// Whether live orders of the chosen clients are deleted before the
+    /// backup's are written
+    pub fn replaces_client_orders(&self) -> bool {
+        self.client_ids.is_some() && self.includes(BackupTable::Orders)
+    }
+}
+
+/// Decides row by row which rows a restore with `options` writes. Rows must
+/// come in dependency order, since a client's garments are only known by the
+/// orders seen before them.
+pub struct RowSelector<'a> {
+    options: &'a RestoreOptions,
+    client_ids: Option<HashSet<&'a str>>,
+    order_ids: HashSet<String>,
+    clothes_ids: HashSet<String>,
+}
+
+impl<'a> RowSelector<'a> {
+    pub fn new(options: &'a RestoreOptions) -> Self {
+        RowSelector {
+            options,
+            client_ids: options.client_ids.as_ref()
+                .map(|client_ids| client_ids.iter().map(String::as_str).collect()),
+            order_ids: HashSet::new(),
+            clothes_ids: HashSet::new(),
+        }
+    }
+
+    pub fn accepts(&mut self, row: &BackupRow) -> bool {
+        let in_scope = match &self.client_ids {
+            None => true,
+            Some(client_ids) => match row {
+                BackupRow::User(_) => true,
+                BackupRow::Client(client) => client_ids.contains(client.id.as_str()),
+                BackupRow::Order(order) => {
+                    let chosen = client_ids.contains(order.client_id.as_str());
+                    if chosen {
+                        self.order_ids.insert(order.id.clone());
+                    }
+                    chosen
+                }
+                BackupRow::Impression(impression) => self.order_ids.contains(&impression.order_id),
+                BackupRow::Clothes(clothes) => {
+                    let chosen = self.order_ids.contains(&clothes.order_id);
+                    if chosen {
+                        self.clothes_ids.insert(clothes.id.clone());
+                    }
+                    chosen
+                }
+                BackupRow::ClothingService(service) => self.clothes_ids.contains(&service.clothes_id),
+                BackupRow::Payment(payment) => self.order_ids.contains(&payment.order_id),
+                BackupRow::OrderStatusChange(change) => self.order_ids.contains(&change.order_id),
+            },
+        };
+        in_scope && self.options.includes(row.table())
+    }
+}
+
+impl DatabaseBackup {
+    /// The rows a restore with `options` writes.
+    pub fn select(&self, options: &RestoreOptions) -> DatabaseBackup {
+        let mut selected = DatabaseBackup::new(self.version.clone(), self.schema_version, self.created_at.clone());
+        let mut selector = RowSelector::new(options);
+        for row in self.rows() {
+            if selector.accepts(&row) {
+                selected.push(row);
+            }
+        }
+        selected
+    }
+}
@@ -0,0 +1,997 @@
+use crate::backup::catalog::{default_backup_dir, file_checksum, legacy_backup_path, BackupCatalog, BackupFormat, BackupInfo, BackupInspection, BackupKind};
+use crate::backup::container::{BackupFileOptions, Compression};
+use crate::backup::format::*;
+use crate::backup::pg_tools::PgTools;
+use crate::backup::preview::{IntegrityChecker, RestorePreview};
+use crate::backup::retention::backups_to_prune;
+use crate::backup::selection::{BackupTable, RestoreOptions, RowSelector};
+use crate::backup::stream::{BackupHeader, BackupOperation, BackupProgress, BackupReader, BackupWriter, ProgressCallback};
+use crate::backup::upgrade::{current_schema_version, relax_unique_indexes, restore_unique_indexes};
+use crate::config::{load_config, RetentionPolicy};
+use crate::database::{begin_transaction, commit_transaction, get_db_pool};
+use crate::error::{AppError, AppResult};
+use futures_util::TryStreamExt;
+use sqlx::postgres::PgRow;
+use sqlx::query_builder::Separated;
+use sqlx::{PgConnection, Postgres, QueryBuilder, Row};
+use std::path::{Path, PathBuf};
+use time::format_description::well_known::{Iso8601, Rfc3339};
+use time::OffsetDateTime;
+
+/// Rows per multi-row INSERT on restore, well under Postgres' bind limit
+const RESTORE_BATCH_SIZE: usize = 500;
+/// Rows between progress events while writing a backup
+const PROGRESS_INTERVAL: usize = 1000;
+
+/// What each table is backed up with, in dependency order
+const EXPORT_QUERIES: [(BackupTable, &str); 8] = [
+    (BackupTable::Users, "SELECT id, login, password, role, active, created_at, updated_at FROM users ORDER BY created_at"),
+    (BackupTable::Clients, "SELECT * FROM clients ORDER BY created_at"),
+    (BackupTable::Orders, "SELECT * FROM orders ORDER BY created_at"),
+    (BackupTable::Impressions, "SELECT * FROM impressions ORDER BY created_at"),
+    (BackupTable::Clothes, "SELECT * FROM clothes ORDER BY created_at"),
+    (BackupTable::ClothingServices, "SELECT * FROM clothing_services ORDER BY created_at"),
+    (BackupTable::Payments, "SELECT * FROM payments ORDER BY created_at"),
+    (BackupTable::OrderStatusHistory, "SELECT * FROM order_status_history ORDER BY changed_at"),
+];
+
+pub struct BackupService;
+
+impl BackupService {
+    /// Writes a new timestamped backup to `destination` (or the default
+    /// backup directory) and records it in the catalog.
+    pub async fn create_backup(destination: Option<String>, file_options: &BackupFileOptions, progress: ProgressCallback<'_>) -> AppResult<BackupInfo> {
+        Self::create_backup_of_kind(destination, BackupKind::Manual, file_options, progress).await
+    }
+
+    /// Scheduled backups run unattended, so they can be compressed but never encrypted.
+    pub async fn create_scheduled_backup(destination: Option<String>, compression: Compression, progress: ProgressCallback<'_>) -> AppResult<BackupInfo> {
+        let file_options = BackupFileOptions { compression, passphrase: None };
+        Self::create_backup_of_kind(destination, BackupKind::Scheduled, &file_options, progress).await
+    }
+
+    async fn create_backup_of_kind(destination: Option<String>, kind: BackupKind, file_options: &BackupFileOptions, progress: ProgressCallback<'_>) -> AppResult<BackupInfo> {
+        log::info!("Starting database backup process...");
+
+        let backup_dir = backup_dir(destination)?;
+        let info = Self::write_backup_file(&backup_dir, kind, file_options, progress).await?;
+        Self::add_to_catalog(&info)?;
+
+        log::info!("Database backup completed successfully! Saved to: {}", info.path);
+        Ok(info)
+    }
+
+    /// Full-fidelity backup through the local pg_dump, schema, indexes and
+    /// constraints included. It is cataloged with the app's own backups but
+    /// can only be restored whole.
+    pub async fn create_pg_dump_backup(destination: Option<String>) -> AppResult<BackupInfo> {
+        log::info!("Starting pg_dump backup...");
+
+        let config = load_config()?;
+        let tools = PgTools::locate(&config.pg_tools)?;
+        let backup_dir = backup_dir(destination)?;
+        let (file_name, backup_path) = new_backup_path(&backup_dir, BackupKind::Manual, "dump");
+        let created_at = format_timestamp(OffsetDateTime::now_utc())?;
+
+        if let Err(e) = tools.dump(&config.database, &backup_path).await {
+            let _ = std::fs::remove_file(&backup_path);
+            return Err(e);
+        }
+
+        let size_bytes = std::fs::metadata(&backup_path)
+            .map_err(|e| AppError::io("Failed to read backup file metadata", e))?
+            .len();
+        let info = BackupInfo {
+            id: uuid::Uuid::new_v4().to_string(),
+            file_name,
+            kind: BackupKind::Manual,
+            format: BackupFormat::PgDump,
+            compression: Compression::None,
+            encrypted: false,
+            path: backup_path.to_string_lossy().into_owned(),
+            created_at,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            schema_version: current_schema_version(),
+            size_bytes,
+            checksum: file_checksum(&backup_path)?,
+            record_counts: BackupRecordCounts::default(),
+        };
+        Self::add_to_catalog(&info)?;
+
+        log::info!("pg_dump backup completed successfully! Saved to: {}", info.path);
+        Ok(info)
+    }
+
+    fn add_to_catalog(info: &BackupInfo) -> AppResult<()> {
+        let mut catalog = Self::load_catalog()?;
+        catalog.add(info.clone());
+        catalog.save()
+    }
+
+    pub fn list_backups() -> AppResult<Vec<BackupInfo>> {
+        Ok(Self::load_catalog()?.entries())
+    }
+
+    /// Catalog entry plus a fresh look at the file: does it still exist and
+    /// does it still match the checksum taken when it was written.
+    pub fn get_backup_info(id: &str) -> AppResult<Option<BackupInspection>> {
+        let catalog = Self::load_catalog()?;
+        Ok(catalog.find(id).map(BackupInfo::inspect))
+    }
+
+    /// Removes the backup from the catalog and deletes its file.
+    pub fn delete_backup(id: &str) -> AppResult<bool> {
+        let mut catalog = Self::load_catalog()?;
+        let info = match catalog.remove(id) {
+            Some(info) => info,
+            None => return Ok(false),
+        };
+
+        let path = Path::new(&info.path);
+        if path.exists() {
+            std::fs::remove_file(path)
+                .map_err(|e| AppError::io(&format!("Failed to delete backup file {:?}", path), e))?;
+        }
+        catalog.save()?;
+
+        log::info!("Deleted backup {} ({})", info.id, info.path);
+        Ok(true)
+    }
+
+    /// Deletes the scheduled backups the retention policy no longer keeps.
+    pub fn apply_retention(policy: &RetentionPolicy) -> AppResult<Vec<BackupInfo>> {
+        let catalog = Self::load_catalog()?;
+        let pruned = backups_to_prune(&catalog.entries(), policy);
+
+        for info in &pruned {
+            Self::delete_backup(&info.id)?;
+        }
+        if !pruned.is_empty() {
+            log::info!("Retention removed {} scheduled backups", pruned.len());
+        }
+
+        Ok(pruned)
+    }
+
+    /// The live database in memory, for previews.
+    async fn collect_backup() -> AppResult<DatabaseBackup> {
+        let pool = get_db_pool()?;
+        let mut backup = DatabaseBackup::new(
+            env!("CARGO_PKG_VERSION").to_string(),
+            current_schema_version(),
+            format_timestamp(OffsetDateTime::now_utc())?,
+        );
+
+        for (table, sql) in EXPORT_QUERIES {
+            let mut rows = sqlx::query(sql).fetch(&pool);
+            while let Some(row) = rows.try_next().await
+                .map_err(|e| AppError::database(&format!("Failed to fetch {}", table.as_str()), e))?
+            {
+                backup.push(backup_row(table, &row)?);
+            }
+        }
+
+        Ok(backup)
+    }
+
+    async fn write_backup_file(dir: &Path, kind: BackupKind, file_options: &BackupFileOptions, progress: ProgressCallback<'_>) -> AppResult<BackupInfo> {
+        let (file_name, backup_path) = new_backup_path(dir, kind, file_options.file_extension());
+
+        let (header, record_counts) = match Self::export_to(&backup_path, file_options, progress).await {
+            Ok(written) => written,
+            Err(e) => {
+                log::error!("Failed to write backup file to {:?}: {}", backup_path, e);
+                // A half-written backup must not be mistaken for a good one
+                let _ = std::fs::remove_file(&backup_path);
+                return Err(e);
+            }
+        };
+
+        let size_bytes = std::fs::metadata(&backup_path)
+            .map_err(|e| AppError::io("Failed to read backup file metadata", e))?
+            .len();
+
+        Ok(BackupInfo {
+            id: uuid::Uuid::new_v4().to_string(),
+            file_name,
+            kind,
+            format: BackupFormat::Json,
+            compression: file_options.compression,
+            encrypted: file_options.passphrase.is_some(),
+            path: backup_path.to_string_lossy().into_owned(),
+            created_at: header.created_at,
+            version: header.version,
+            schema_version: header.schema_version,
+            size_bytes,
+            checksum: file_checksum(&backup_path)?,
+            record_counts,
+        })
+    }
+
+    /// Streams every table into `path`, all read from one snapshot of the
+    /// database so the backup is consistent while the app keeps writing.
+    async fn export_to(path: &Path, file_options: &BackupFileOptions, progress: ProgressCallback<'_>) -> AppResult<(BackupHeader, BackupRecordCounts)> {
+        let mut tx = begin_transaction().await?;
+        sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::database("Failed to start backup snapshot", e))?;
+
+        let header = BackupHeader {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            schema_version: current_schema_version(),
+            created_at: format_timestamp(OffsetDateTime::now_utc())?,
+            record_counts: Self::count_rows(&mut tx).await?,
+        };
+        let mut writer = BackupWriter::create(path, file_options, &header)?;
+
+        for (table, sql) in EXPORT_QUERIES {
+            log::info!("Backing up {} table...", table.as_str());
+            let total = header.record_counts.get(table);
+            let mut processed = 0;
+
+            let mut rows = sqlx::query(sql).fetch(&mut *tx);
+            while let Some(row) = rows.try_next().await
+                .map_err(|e| AppError::database(&format!("Failed to fetch {}", table.as_str()), e))?
+            {
+                writer.write_row(&backup_row(table, &row)?)?;
+                processed += 1;
+                if processed % PROGRESS_INTERVAL == 0 {
+                    progress(BackupProgress { operation: BackupOperation::Backup, table, processed, total });
+                }
+            }
+
+            progress(BackupProgress { operation: BackupOperation::Backup, table, processed, total });
+            log::info!("Backed up {} {}", processed, table.as_str());
+        }
+
+        commit_transaction(tx).await?;
+        let record_counts = writer.finish()?;
+        Ok((header, record_counts))
+    }
+
+    async fn count_rows(conn: &mut PgConnection) -> AppResult<BackupRecordCounts> {
+        let mut counts = BackupRecordCounts::default();
+        for table in BackupTable::ALL {
+            let count: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}", table.as_str()))
+                .fetch_one(&mut *conn)
+                .await
+                .map_err(|e| AppError::database(&format!("Failed to count {}", table.as_str()), e))?;
+            counts.set(table, count as usize);
+        }
+        Ok(counts)
+    }
+
+    /// Loads the catalog, adopting the single backup file older versions
+    /// wrote so it stays restorable after the upgrade.
+    fn load_catalog() -> AppResult<BackupCatalog> {
+        let mut catalog = BackupCatalog::load()?;
+
+        let legacy_path = legacy_backup_path()?;
+        if legacy_path.exists() && !catalog.contains_path(&legacy_path) {
+            match BackupReader::open(&legacy_path, None) {
+                Ok(reader) => {
+                    let header = reader.header();
+                    let size_bytes = std::fs::metadata(&legacy_path)
+                        .map_err(|e| AppError::io("Failed to read backup file metadata", e))?
+                        .len();
+                    catalog.add(BackupInfo {
+                        id: uuid::Uuid::new_v4().to_string(),
+                        file_name: "database_backup.json".to_string(),
+                        kind: BackupKind::Manual,
+                        format: BackupFormat::Json,
+                        compression: Compression::None,
+                        encrypted: false,
+                        path: legacy_path.to_string_lossy().into_owned(),
+                        created_at: header.created_at.clone(),
+                        version: header.version.clone(),
+                        schema_version: header.schema_version,
+                        size_bytes,
+                        checksum: file_checksum(&legacy_path)?,
+                        record_counts: header.record_counts.clone(),
+                    });
+                    catalog.save()?;
+                }
+                Err(e) => log::warn!("Ignoring unreadable legacy backup {:?}: {}", legacy_path, e),
+            }
+        }
+
+        Ok(catalog)
+    }
+
+    /// Finds a cataloged backup, refusing files changed since they were written.
+    fn verified_backup(id: &str) -> AppResult<(BackupInfo, PathBuf)> {
+        let catalog = Self::load_catalog()?;
+        let info = catalog.find(id)
+            .ok_or_else(|| AppError::not_found("backup", id))?;
+        let backup_path = PathBuf::from(&info.path);
+
+        if !backup_path.exists() {
+            let error_msg = format!("Backup file not found at: {:?}", backup_path);
+            log::error!("{}", error_msg);
+            return Err(AppError::Io(error_msg));
+        }
+
+        // A file that changed since it was written is not trusted
+        if file_checksum(&backup_path)? != info.checksum {
+            let error_msg = format!("Backup file {:?} does not match its recorded checksum", backup_path);
+            log::error!("{}", error_msg);
+            return Err(AppError::Failed(error_msg));
+        }
+
+        Ok((info.clone(), backup_path))
+    }
+
+    /// Dry run of `restore_backup`: nothing is written. Unlike the restore
+    /// itself it compares whole tables, so both sides are held in memory.
+    pub async fn preview_restore(id: &str, options: &RestoreOptions, passphrase: Option<&str>) -> AppResult<RestorePreview> {
+        let (info, backup_path) = Self::verified_backup(id)?;
+        if info.format == BackupFormat::PgDump {
+            return Err(AppError::Failed("Backups taken with pg_dump cannot be previewed".to_string()));
+        }
+        let backup = BackupReader::open(&backup_path, passphrase)?.read_all()?;
+        let selected = backup.select(options);
+        let live = Self::collect_backup().await?;
+
+        RestorePreview::build(info, &selected, &live, options)
+    }
+
+    /// Restores in two passes over the file, so only a batch of rows is in
+    /// memory at a time: the first checks every reference, the second writes.
+    pub async fn restore_backup(id: &str, options: &RestoreOptions, passphrase: Option<&str>, progress: ProgressCallback<'_>) -> AppResult<String> {
+        log::info!("Starting database restore process...");
+
+        let (info, backup_path) = Self::verified_backup(id)?;
+        if info.format == BackupFormat::PgDump {
+            return Self::restore_pg_dump(&info, &backup_path, options, progress).await;
+        }
+        let (created_at, selected_counts) = Self::check_backup(&backup_path, options, passphrase).await?;
+
+        let snapshot = Self::take_pre_restore_snapshot(progress).await?;
+
+        // Everything runs in one transaction: any failure rolls back to the data as it was
+        let mut tx = begin_transaction().await?;
+        if let Err(e) = Self::restore_into(&mut tx, &backup_path, passphrase, options, &selected_counts, progress).await {
+            log::error!("Restore failed, rolling back: {}", e);
+            return Err(e.map_message(|message| format!("{} (database left unchanged)", message)));
+        }
+        commit_transaction(tx).await?;
+
+        let success_message = format!(
+            "Database restored successfully from backup created on {}. Previous data saved as {}",
+            created_at, snapshot.file_name
+        );
+        log::info!("{}", success_message);
+        Ok(success_message)
+    }
+
+    /// Current data is saved first so a restore of the wrong backup can be undone
+    async fn take_pre_restore_snapshot(progress: ProgressCallback<'_>) -> AppResult<BackupInfo> {
+        log::info!("Taking pre-restore snapshot...");
+        let snapshot = Self::write_backup_file(&default_backup_dir()?, BackupKind::PreRestore, &BackupFileOptions::default(), progress)
+            .await
+            .map_err(|e| e.map_message(|message| format!("Failed to take pre-restore snapshot, nothing was restored: {}", message)))?;
+        Self::add_to_catalog(&snapshot)?;
+        log::info!("Pre-restore snapshot saved to: {}", snapshot.path);
+        Ok(snapshot)
+    }
+
+    /// pg_restore replaces the whole database in one transaction. A dump
+    /// from an older schema is migrated forward afterwards.
+    async fn restore_pg_dump(info: &BackupInfo, path: &Path, options: &RestoreOptions, progress: ProgressCallback<'_>) -> AppResult<String> {
+        if !options.is_full() {
+            return Err(AppError::invalid("tables", "pg_dump_full_only", "Backups taken with pg_dump can only be restored in full"));
+        }
+        let current = current_schema_version();
+        if info.schema_version > current {
+            return Err(AppError::Failed(format!(
+                "This backup was taken by a newer version of the app (schema {}, this version supports up to {}). Update the app to restore it",
+                info.schema_version, current
+            )));
+        }
+
+        let config = load_config()?;
+        let tools = PgTools::locate(&config.pg_tools)?;
+        let snapshot = Self::take_pre_restore_snapshot(progress).await?;
+
+        tools.restore(&config.database, path)
+            .await
+            .map_err(|e| e.map_message(|message| format!("{} (database left unchanged)", message)))?;
+        crate::migrations::run_migrations(&get_db_pool()?).await?;
+
+        let success_message = format!(
+            "Database restored successfully from backup created on {}. Previous data saved as {}",
+            info.created_at, snapshot.file_name
+        );
+        log::info!("{}", success_message);
+        Ok(success_message)
+    }
+
+    /// First pass of a restore: counts the rows `options` selects and makes
+    /// sure every one of their references resolves.
+    async fn check_backup(path: &Path, options: &RestoreOptions, passphrase: Option<&str>) -> AppResult<(String, BackupRecordCounts)> {
+        let mut checker = IntegrityChecker::default();
+        if !options.is_full() {
+            // Rows of a partial restore can point at rows that stay in place
+            Self::add_live_parents(&mut checker).await?;
+        }
+
+        let mut reader = BackupReader::open(path, passphrase)?;
+        let mut selector = RowSelector::new(options);
+        let mut counts = BackupRecordCounts::default();
+        while let Some(row) = reader.next_row()? {
+            if selector.accepts(&row) {
+                checker.check(&row);
+                counts.increment(row.table());
+            }
+        }
+
+        let issues = checker.into_issues();
+        if !issues.is_empty() {
+            let error_msg = format!(
+                "Backup has {} broken references (first: {} {}: {}), nothing was restored",
+                issues.len(), issues[0].table.as_str(), issues[0].record_id, issues[0].message
+            );
+            log::error!("{}", error_msg);
+            return Err(AppError::Failed(error_msg));
+        }
+
+        Ok((reader.header().created_at.clone(), counts))
+    }
+
+    async fn add_live_parents(checker: &mut IntegrityChecker) -> AppResult<()> {
+        let pool = get_db_pool()?;
+        for table in [BackupTable::Users, BackupTable::Clients, BackupTable::Orders, BackupTable::Clothes] {
+            let sql = format!("SELECT id FROM {}", table.as_str());
+            let mut ids = sqlx::query_scalar::<_, String>(&sql).fetch(&pool);
+            while let Some(id) = ids.try_next().await
+                .map_err(|e| AppError::database(&format!("Failed to fetch {}", table.as_str()), e))?
+            {
+                checker.add_known(table, &id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Second pass of a restore: writes the rows `options` selects. A full
+    /// restore replaces every table; a partial one upserts rows by id.
+    async fn restore_into(
+        conn: &mut PgConnection,
+        path: &Path,
+        passphrase: Option<&str>,
+        options: &RestoreOptions,
+        totals: &BackupRecordCounts,
+        progress: ProgressCallback<'_>,
+    ) -> AppResult<()> {
+        let mut reader = BackupReader::open(path, passphrase)?;
+        if options.is_full() {
+            Self::clear_all_tables(conn).await?;
+        } else if options.replaces_client_orders() {
+            // Cascades to everything under those orders
+            let client_ids = options.client_ids.clone().unwrap_or_default();
+            let result = sqlx::query("DELETE FROM orders WHERE client_id = ANY($1)")
+                .bind(&client_ids)
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| AppError::database("Failed to clear orders of selected clients", e))?;
+            log::info!("Cleared {} orders of {} selected clients", result.rows_affected(), client_ids.len());
+        }
+        let upsert = !options.is_full();
+        let relaxed = relax_unique_indexes(conn, reader.source_schema_version()).await?;
+
+        // Rows come in dependency order, so a batch never holds more than one table
+        let mut selector = RowSelector::new(options);
+        let mut batch: Vec<BackupRow> = Vec::with_capacity(RESTORE_BATCH_SIZE);
+        let mut restored = BackupRecordCounts::default();
+        while let Some(row) = reader.next_row()? {
+            if !selector.accepts(&row) {
+                continue;
+            }
+            if batch.first().is_some_and(|first| first.table() != row.table()) {
+                Self::flush_batch(conn, &mut batch, upsert, &mut restored, totals, progress).await?;
+            }
+            batch.push(row);
+            if batch.len() == RESTORE_BATCH_SIZE {
+                Self::flush_batch(conn, &mut batch, upsert, &mut restored, totals, progress).await?;
+            }
+        }
+        Self::flush_batch(conn, &mut batch, upsert, &mut restored, totals, progress).await?;
+        restore_unique_indexes(conn, relaxed).await?;
+
+        for table in BackupTable::ALL {
+            if options.includes(table) {
+                log::info!("Restored {} {}", restored.get(table), table.as_str());
+            }
+        }
+
+        // Backups taken before the payments ledger only carry the order debt.
+        // This runs on the restore's own connection, so it sees the restored orders.
+        if reader.source_schema_version() < 2 && options.includes(BackupTable::Orders) {
+            let backfilled = crate::repositories::PaymentRepository.backfill_opening_balances(conn).await?;
+            log::info!("Recorded opening payments for {} restored orders", backfilled);
+        }
+
+        if upsert {
+            // Restored orders and payments can sit next to live ones now
+            Self::recompute_debts(conn).await?;
+        }
+
+        // Orders from older backups still need the status they were restored with
+        let backfilled = crate::repositories::OrderStatusHistoryRepository.backfill_initial_statuses(conn).await?;
+        if backfilled > 0 {
+            log::info!("Recorded initial status for {} restored orders", backfilled);
+        }
+
+        Ok(())
+    }
+
+    async fn flush_batch(
+        conn: &mut PgConnection,
+        batch: &mut Vec<BackupRow>,
+        upsert: bool,
+        restored: &mut BackupRecordCounts,
+        totals: &BackupRecordCounts,
+        progress: ProgressCallback<'_>,
+    ) -> AppResult<()> {
+        let Some(table) = batch.first().map(BackupRow::table) else {
+            return Ok(());
+        };
+
+        insert_rows(conn, table, batch, upsert).await?;
+        restored.set(table, restored.get(table) + batch.len());
+        batch.clear();
+
+        progress(BackupProgress {
+            operation: BackupOperation::Restore,
+            table,
+            processed: restored.get(table),
+            total: totals.get(table),
+        });
+        Ok(())
+    }
+
+    async fn clear_all_tables(conn: &mut PgConnection) -> AppResult<()> {
+        // Clear all existing data (in reverse order due to foreign keys)
+        log::info!("Clearing existing database data...");
+
+        sqlx::query("DELETE FROM order_status_history")
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| AppError::database("Failed to clear order_status_history", e))?;
+        log::info!("Cleared order_status_history table");
+
+        sqlx::query("DELETE FROM payments")
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| AppError::database("Failed to clear payments", e))?;
+        log::info!("Cleared payments table");
+
+        sqlx::query("DELETE FROM clothing_services")
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| AppError::database("Failed to clear clothing_services", e))?;
+        log::info!("Cleared clothing_services table");
+
+        sqlx::query("DELETE FROM clothes")
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| AppError::database("Failed to clear clothes", e))?;
+        log::info!("Cleared clothes table");
+
+        sqlx::query("DELETE FROM impressions")
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| AppError::database("Failed to clear impressions", e))?;
+        log::info!("Cleared impressions table");
+
+        sqlx::query("DELETE FROM orders")
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| AppError::database("Failed to clear orders", e))?;
+        log::info!("Cleared orders table");
+
+        sqlx::query("DELETE FROM clients")
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| AppError::database("Failed to clear clients", e))?;
+        log::info!("Cleared clients table");
+
+        sqlx::query("DELETE FROM users")
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| AppError::database("Failed to clear users", e))?;
+        log::info!("Cleared users table");
+
+        Ok(())
+    }
+
+    /// Same recomputation as migration 0005, keeping cancelled orders at 0
+    async fn recompute_debts(conn: &mut PgConnection) -> AppResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE orders
+            SET debt = CASE WHEN status = 'cancelled' THEN 0
+                            ELSE GREATEST(total - (SELECT COALESCE(SUM(amount), 0) FROM payments WHERE order_id = orders.id), 0) END
+            "#,
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| AppError::database("Failed to recompute order debts", e))?;
+
+        sqlx::query("UPDATE clients SET debt = (SELECT COALESCE(SUM(debt), 0) FROM orders WHERE client_id = clients.id)")
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| AppError::database("Failed to recompute client debts", e))?;
+
+        Ok(())
+    }
+}
+
+fn backup_dir(destination: Option<String>) -> AppResult<PathBuf> {
+    match destination {
+        Some(destination) => {
+            let dir = PathBuf::from(destination);
+            std::fs::create_dir_all(&dir)
+                .map_err(|e| AppError::io(&format!("Failed to create backup destination {:?}", dir), e))?;
+            Ok(dir)
+        }
+        None => default_backup_dir(),
+    }
+}
+
+/// A timestamped file name in `dir` that is not taken yet
+fn new_backup_path(dir: &Path, kind: BackupKind, extension: &str) -> (String, PathBuf) {
+    let created_at = OffsetDateTime::now_utc();
+    let stamp = format!(
+        "{:04}-{:02}-{:02}T{:02}{:02}{:02}",
+        created_at.year(), u8::from(created_at.month()), created_at.day(),
+        created_at.hour(), created_at.minute(), created_at.second()
+    );
+
+    // Two backups in the same second get a numeric suffix instead of overwriting
+    let mut file_name = format!("{}-{}.{}", kind.file_prefix(), stamp, extension);
+    let mut suffix = 1;
+    while dir.join(&file_name).exists() {
+        suffix += 1;
+        file_name = format!("{}-{}-{}.{}", kind.file_prefix(), stamp, suffix, extension);
+    }
+    let path = dir.join(&file_name);
+    (file_name, path)
+}
+
+fn format_timestamp(value: OffsetDateTime) -> AppResult<String> {
+    value.format(&Rfc3339)
+        .map_err(|e| AppError::Failed(format!("Failed to format timestamp: {}", e)))
+}
+
+fn timestamp_column(row: &PgRow, column: &str) -> AppResult<String> {
+    format_timestamp(row.get::<OffsetDateTime, _>(column))
+}
+
+/// A row as returned by the table's `EXPORT_QUERIES` entry, in backup form
+fn backup_row(table: BackupTable, row: &PgRow) -> AppResult<BackupRow> {
+    Ok(match table {
+        BackupTable::Users => BackupRow::User(UserBackup {
+            id: row.get("id"),
+            login: row.get("login"),
+            password: row.get("password"),
+            role: row.get("role"),
+            active: row.get("active"),
+            created_at: timestamp_column(row, "created_at")?,
+            updated_at: timestamp_column(row, "updated_at")?,
+        }),
+        BackupTable::Clients => BackupRow::Client(ClientBackup {
+            id: row.get("id"),
+            name: row.get("name"),
+            nuit: row.get("nuit"),
+            contact: row.get("contact"),
+            category: row.get("category"),
+            observations: row.get("observations"),
+            debt: row.get("debt"),
+            created_at: timestamp_column(row, "created_at")?,
+            updated_at: timestamp_column(row, "updated_at")?,
+        }),
+        BackupTable::Orders => BackupRow::Order(OrderBackup {
+            id: row.get("id"),
+            name: row.get("name"),
+            client_id: row.get("client_id"),
+            order_number: row.get("order_number"),
+            order_year: row.get("order_year"),
+            number_scope: row.get("number_scope"),
+            client_requisition_number: row.get("client_requisition_number"),
+            due_date: row.get::<Option<time::Date>, _>("due_date").map(|d| d.to_string()),
+            discount: row.get("discount"),
+            iva: row.get("iva"),
+            subtotal: row.get("subtotal"),
+            total: row.get("total"),
+            status: row.get("status"),
+            debt: row.get("debt"),
+            created_at: timestamp_column(row, "created_at")?,
+            updated_at: timestamp_column(row, "updated_at")?,
+        }),
+        BackupTable::Impressions => BackupRow::Impression(ImpressionBackup {
+            id: row.get("id"),
+            order_id: row.get("order_id"),
+            name: row.get("name"),
+            size: row.get("size"),
+            material: row.get("material"),
+            description: row.get("description"),
+            price: row.get("price"),
+            created_at: timestamp_column(row, "created_at")?,
+            updated_at: timestamp_column(row, "updated_at")?,
+        }),
+        BackupTable::Clothes => BackupRow::Clothes(ClothesBackup {
+            id: row.get("id"),
+            order_id: row.get("order_id"),
+            clothing_type: row.get("clothing_type"),
+            custom_type: row.get("custom_type"),
+            unit_price: row.get("unit_price"),
+            sizes: row.get("sizes"),
+            color: row.get("color"),
+            total_quantity: row.get("total_quantity"),
+            created_at: timestamp_column(row, "created_at")?,
+            updated_at: timestamp_column(row, "updated_at")?,
+        }),
+        BackupTable::ClothingServices => BackupRow::ClothingService(ClothingServiceBackup {
+            id: row.get("id"),
+            clothes_id: row.get("clothes_id"),
+            service_type: row.get("service_type"),
+            location: row.get("location"),
+            description: row.get("description"),
+            unit_price: row.get("unit_price"),
+            created_at: timestamp_column(row, "created_at")?,
+            updated_at: timestamp_column(row, "updated_at")?,
+        }),
+        BackupTable::Payments => BackupRow::Payment(PaymentBackup {
+            id: row.get("id"),
+            order_id: row.get("order_id"),
+            amount: row.get("amount"),
+            payment_date: row.get::<time::Date, _>("payment_date").to_string(),
+            method: row.get("method"),
+            reference: row.get("reference"),
+            received_by: row.get("received_by"),
+            created_at: timestamp_column(row, "created_at")?,
+            updated_at: timestamp_column(row, "updated_at")?,
+        }),
+        BackupTable::OrderStatusHistory => BackupRow::OrderStatusChange(OrderStatusChangeBackup {
+            id: row.get("id"),
+            order_id: row.get("order_id"),
+            from_status: row.get("from_status"),
+            to_status: row.get("to_status"),
+            changed_by: row.get("changed_by"),
+            note: row.get("note"),
+            changed_at: timestamp_column(row, "changed_at")?,
+        }),
+    })
+}
+
+/// Columns written on restore, in the order `bind_row` binds them
+fn insert_columns(table: BackupTable) -> &'static [&'static str] {
+    match table {
+        BackupTable::Users => &["id", "login", "password", "role", "active", "created_at", "updated_at"],
+        BackupTable::Clients => &["id", "name", "nuit", "contact", "category", "observations", "debt", "created_at", "updated_at"],
+        BackupTable::Orders => &["id", "name", "client_id", "order_number", "order_year", "number_scope", "client_requisition_number", "due_date", "discount", "iva", "subtotal", "total", "status", "debt", "created_at", "updated_at"],
+        BackupTable::Impressions => &["id", "order_id", "name", "size", "material", "description", "price", "created_at", "updated_at"],
+        BackupTable::Clothes => &["id", "order_id", "clothing_type", "custom_type", "unit_price", "sizes", "color", "total_quantity", "created_at", "updated_at"],
+        BackupTable::ClothingServices => &["id", "clothes_id", "service_type", "location", "description", "unit_price", "created_at", "updated_at"],
+        BackupTable::Payments => &["id", "order_id", "amount", "payment_date", "method", "reference", "received_by", "created_at", "updated_at"],
+        BackupTable::OrderStatusHistory => &["id", "order_id", "from_status", "to_status", "changed_by", "note", "changed_at"],
+    }
+}
+
+/// One multi-row INSERT for rows of `table`; partial restores update rows
+/// whose id already exists.
+async fn insert_rows(conn: &mut PgConnection, table: BackupTable, rows: &[BackupRow], upsert: bool) -> AppResult<()> {
+    let columns = insert_columns(table);
+    let mut query = QueryBuilder::<Postgres>::new(format!("INSERT INTO {} ({}) ", table.as_str(), columns.join(", ")));
+
+    let mut bind_error = None;
+    query.push_values(rows, |mut values, row| {
+        if let Err(e) = bind_row(&mut values, row) {
+            bind_error.get_or_insert(e);
+        }
+    });
+    if let Some(e) = bind_error {
+        return Err(e);
+    }
+
+    if upsert {
+        let updates: Vec<String> = columns.iter()
+            .filter(|column| **column != "id")
+            .map(|column| format!("{} = EXCLUDED.{}", column, column))
+            .collect();
+        query.push(format!(" ON CONFLICT (id) DO UPDATE SET {}", updates.join(", ")));
+    }
+
+    query.build()
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| AppError::database(&format!(
+            "Failed to restore {} (batch starting at {})",
+            table.as_str(), rows.first().map(BackupRow::id).unwrap_or_default()
+        ), e))?;
+    Ok(())
+}
+
+fn bind_row<'args>(values: &mut Separated<'_, 'args, Postgres, &'static str>, row: &'args BackupRow) -> AppResult<()> {
+    let table = row.table().as_str();
+    let timestamp = |value: &str, column: &str| parse_datetime(value)
+        .map_err(|e| AppError::Failed(format!("Failed to parse {} for {} {}: {}", column, table, row.id(), e)));
+    let date = |value: &str, column: &str| time::Date::parse(value, &Iso8601::DATE)
+        .map_err(|e| AppError::Failed(format!("Failed to parse {} for {} {}: {}", column, table, row.id(), e)));
+
+    match row {
+        BackupRow::User(user) => {
+            values.push_bind(&user.id)
+                .push_bind(&user.login)
+                .push_bind(&user.password)
+                .push_bind(&user.role)
+                .push_bind(user.active)
+                .push_bind(timestamp(&user.created_at, "created_at")?)
+                .push_bind(timestamp(&user.updated_at, "updated_at")?);
+        }
+        BackupRow::Client(client) => {
+            values.push_bind(&client.id)
+                .push_bind(&client.name)
+                .push_bind(&client.nuit)
+                .push_bind(&client.contact)
+                .push_bind(&client.category)
+                .push_bind(&client.observations)
+                .push_bind(client.debt)
+                .push_bind(timestamp(&client.created_at, "created_at")?)
+                .push_bind(timestamp(&client.updated_at, "updated_at")?);
+        }
+        BackupRow::Order(order) => {
+            let due_date = order.due_date.as_deref()
+                .map(|due_date| date(due_date, "due_date"))
+                .transpose()?;
+            values.push_bind(&order.id)
+                .push_bind(&order.name)
+                .push_bind(&order.client_id)
+                .push_bind(order.order_number)
+                .push_bind(order.order_year)
+                .push_bind(&order.number_scope)
+                .push_bind(order.client_requisition_number)
+                .push_bind(due_date)
+                .push_bind(order.discount)
+                .push_bind(order.iva)
+                .push_bind(order.subtotal)
+                .push_bind(order.total)
+                .push_bind(&order.status)
+                .push_bind(order.debt)
+                .push_bind(timestamp(&order.created_at, "created_at")?)
+                .push_bind(timestamp(&order.updated_at, "updated_at")?);
+        }
+        BackupRow::Impression(impression) => {
+            values.push_bind(&impression.id)
+                .push_bind(&impression.order_id)
+                .push_bind(&impression.name)
+                .push_bind(&impression.size)
+                .push_bind(&impression.material)
+                .push_bind(&impression.description)
+                .push_bind(impression.price)
+                .push_bind(timestamp(&impression.created_at, "created_at")?)
+                .push_bind(timestamp(&impression.updated_at, "updated_at")?);
+        }
+        BackupRow::Clothes(clothes) => {
+            values.push_bind(&clothes.id)
+                .push_bind(&clothes.order_id)
+                .push_bind(&clothes.clothing_type)
+                .push_bind(&clothes.custom_type)
+                .push_bind(clothes.unit_price)
+                .push_bind(&clothes.sizes)
+                .push_bind(&clothes.color)
+                .push_bind(clothes.total_quantity)
+                .push_bind(timestamp(&clothes.created_at, "created_at")?)
+                .push_bind(timestamp(&clothes.updated_at, "updated_at")?);
+        }
+        BackupRow::ClothingService(service) => {
+            values.push_bind(&service.id)
+                .push_bind(&service.clothes_id)
+                .push_bind(&service.service_type)
+                .push_bind(&service.location)
+                .push_bind(&service.description)
+                .push_bind(service.unit_price)
+                .push_bind(timestamp(&service.created_at, "created_at")?)
+                .push_bind(timestamp(&service.updated_at, "updated_at")?);
+        }
+        BackupRow::Payment(payment) => {
+            values.push_bind(&payment.id)
+                .push_bind(&payment.order_id)
+                .push_bind(payment.amount)
+                .push_bind(date(&payment.payment_date, "payment_date")?)
+                .push_bind(&payment.method)
+                .push_bind(&payment.reference)
+                .push_bind(&payment.received_by)
+                .push_bind(timestamp(&payment.created_at, "created_at")?)
+                .push_bind(timestamp(&payment.updated_at, "updated_at")?);
+        }
+        BackupRow::OrderStatusChange(change) => {
+            values.push_bind(&change.id)
+                .push_bind(&change.order_id)
+                .push_bind(&change.from_status)
+                .push_bind(&change.to_status)
+                .push_bind(&change.changed_by)
+                .push_bind(&change.note)
+                .push_bind(timestamp(&change.changed_at, "changed_at")?);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migrations::run_migrations;
+    use crate::test_support::with_empty_database;
+    use serde_json::json;
+
+    fn write_backup(lines: &[serde_json::Value]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("editart-restore-{}.ndjson", uuid::Uuid::new_v4()));
+        let text: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+        std::fs::write(&path, text.join("\n")).unwrap();
+        path
+    }
+
+    fn order(id: &str, number: i32, scope: Option<&str>, created_at: &str, requisition: i32) -> serde_json::Value {
+        let mut order = json!({
+            "id": id,
+            "name": id,
+            "client_id": "c1",
+            "order_number": number,
+            "client_requisition_number": requisition,
+            "due_date": "2025-12-31",
+            "discount": "0",
+            "iva": "0",
+            "subtotal": "0",
+            "total": "0",
+            "status": "delivered",
+            "debt": "0",
+            "created_at": created_at,
+            "updated_at": created_at,
+        });
+        if let Some(scope) = scope {
+            order["number_scope"] = json!(scope);
+        }
+        json!({ "table": "orders", "row": order })
+    }
+
+    #[test]
+    fn restoring_renumbers_duplicates_within_a_scope_across_years() {
+        with_empty_database(|pool| async move {
+            run_migrations(&pool).await.unwrap();
+            // Global numbering: 1 in 2024 and 1 again in 2025 clash in the
+            // 'order' scope, though not within either year
+            let path = write_backup(&[
+                json!({ "header": { "version": "0.1.0", "schema_version": 3, "created_at": "2025-06-01T00:00:00Z" } }),
+                json!({ "table": "clients", "row": {
+                    "id": "c1", "name": "Silva", "nuit": "", "contact": "", "category": "Empresa",
+                    "observations": "", "debt": "0",
+                    "created_at": "2024-01-01T00:00:00Z", "updated_at": "2024-01-01T00:00:00Z",
+                } }),
+                order("o1", 1, Some("order"), "2024-03-01T00:00:00Z", 1),
+                order("o2", 1, Some("order"), "2025-03-01T00:00:00Z", 2),
+                order("o3", 2, None, "2025-04-01T00:00:00Z", 3),
+            ]);
+
+            let mut conn = pool.acquire().await.unwrap();
+            let restored = BackupService::restore_into(
+                &mut conn, &path, None, &RestoreOptions::default(), &BackupRecordCounts::default(), &|_| {},
+            ).await;
+            std::fs::remove_file(&path).unwrap();
+            restored.unwrap();
+
+            let numbers: Vec<(String, String, i32)> = sqlx::query_as(
+                "SELECT id, number_scope, order_number FROM orders ORDER BY id"
+            )
+            .fetch_all(&mut *conn)
+            .await
+            .unwrap();
+            assert_eq!(numbers, [
+                ("o1".to_string(), "order".to_string(), 1),
+                ("o2".to_string(), "order".to_string(), 3),
+                ("o3".to_string(), "order:2025".to_string(), 2),
+            ]);
+        });
+    }
+}
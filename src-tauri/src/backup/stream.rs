@@ -0,0 +1,316 @@
+use crate::backup::container::{BackupFileOptions, ContainerReader, ContainerWriter};
+use crate::backup::format::{BackupRecordCounts, BackupRow, DatabaseBackup};
+use crate::backup::selection::BackupTable;
+use crate::backup::upgrade::{schema_version_of, upgrade_header, upgrade_row};
+use crate::error::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+use tauri::{AppHandle, Emitter};
+
+/// Emitted while a backup is written or restored
+pub const BACKUP_PROGRESS_EVENT: &str = "backup-progress";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BackupOperation {
+    #[serde(rename = "backup")]
+    Backup,
+    #[serde(rename = "restore")]
+    Restore,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupProgress {
+    pub operation: BackupOperation,
+    pub table: BackupTable,
+    /// Rows of `table` done so far
+    pub processed: usize,
+    pub total: usize,
+}
+
+pub type ProgressCallback<'a> = &'a (dyn Fn(BackupProgress) + Send + Sync);
+
+/// Forwards progress to the frontend as `BACKUP_PROGRESS_EVENT`.
+pub fn emit_progress(app: &AppHandle) -> impl Fn(BackupProgress) + Send + Sync + '_ {
+    move |progress| {
+        if let Err(e) = app.emit(BACKUP_PROGRESS_EVENT, progress) {
+            log::warn!("Failed to emit backup progress: {}", e);
+        }
+    }
+}
+
+/// First line of a backup file. `record_counts` are the rows that follow,
+/// so readers can report progress before reaching the end.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupHeader {
+    pub version: String,
+    pub schema_version: i64,
+    pub created_at: String,
+    #[serde(default)]
+    pub record_counts: BackupRecordCounts,
+}
+
+/// Writes a backup as NDJSON: `{"header": {...}}` first, then one
+/// `{"table": ..., "row": {...}}` line per row, tables in dependency order.
+pub struct BackupWriter {
+    out: ContainerWriter,
+    written: BackupRecordCounts,
+}
+
+impl BackupWriter {
+    pub fn create(path: &Path, options: &BackupFileOptions, header: &BackupHeader) -> AppResult<Self> {
+        let mut writer = BackupWriter {
+            out: ContainerWriter::create(path, options)?,
+            written: BackupRecordCounts::default(),
+        };
+        writer.write_line(&json!({ "header": header }))?;
+        Ok(writer)
+    }
+
+    pub fn write_row(&mut self, row: &BackupRow) -> AppResult<()> {
+        let value = row.to_value()
+            .map_err(|e| AppError::Failed(format!("Failed to serialize {} {}: {}", row.table().as_str(), row.id(), e)))?;
+        self.write_line(&json!({ "table": row.table(), "row": value }))?;
+        self.written.increment(row.table());
+        Ok(())
+    }
+
+    fn write_line(&mut self, line: &Value) -> AppResult<()> {
+        serde_json::to_writer(&mut self.out, line)
+            .map_err(|e| AppError::Io(format!("Failed to write backup file: {}", e)))?;
+        self.out.write_all(b"\n")
+            .map_err(|e| AppError::io("Failed to write backup file", e))
+    }
+
+    /// Flushes the file and returns how many rows of each table went in.
+    pub fn finish(self) -> AppResult<BackupRecordCounts> {
+        self.out.finish()?;
+        Ok(self.written)
+    }
+}
+
+#[derive(Deserialize)]
+struct RowLine {
+    table: BackupTable,
+    row: Value,
+}
+
+enum RowSource {
+    Lines(Box<std::io::Lines<BufReader<ContainerReader>>>),
+    /// Backups written before streaming are one JSON document, read whole
+    Document(std::vec::IntoIter<(BackupTable, Value)>),
+}
+
+/// Reads a backup row by row, upgrading each row to the current schema.
+pub struct BackupReader {
+    header: BackupHeader,
+    source_schema_version: i64,
+    rows: RowSource,
+}
+
+impl BackupReader {
+    pub fn open(path: &Path, passphrase: Option<&str>) -> AppResult<Self> {
+        log::info!("Reading backup file from: {:?}", path);
+        let mut reader = BufReader::new(ContainerReader::open(path, passphrase)?);
+
+        let mut first_line = String::new();
+        reader.read_line(&mut first_line)
+            .map_err(|e| AppError::io("Failed to read backup file", e))?;
+
+        let streamed_header = serde_json::from_str::<Value>(&first_line)
+            .ok()
+            .and_then(|mut line| line.get_mut("header").map(Value::take));
+
+        let (mut header, rows) = match streamed_header {
+            Some(header) => (header, RowSource::Lines(Box::new(reader.lines()))),
+            None => {
+                let mut content = first_line;
+                reader.read_to_string(&mut content)
+                    .map_err(|e| AppError::io("Failed to read backup file", e))?;
+                let document: Value = serde_json::from_str(&content)
+                    .map_err(|e| AppError::Failed(format!("Failed to parse backup file: {}", e)))?;
+                split_document(document)
+            }
+        };
+
+        let source_schema_version = schema_version_of(&header)?;
+        upgrade_header(source_schema_version, &mut header)?;
+        let header: BackupHeader = serde_json::from_value(header)
+            .map_err(|e| AppError::Failed(format!("Failed to parse backup header: {}", e)))?;
+
+        log::info!("Backup opened. Version: {}, Created: {}", header.version, header.created_at);
+        Ok(BackupReader { header, source_schema_version, rows })
+    }
+
+    pub fn header(&self) -> &BackupHeader {
+        &self.header
+    }
+
+    /// The schema the backup was written at, before upgrading
+    pub fn source_schema_version(&self) -> i64 {
+        self.source_schema_version
+    }
+
+    pub fn next_row(&mut self) -> AppResult<Option<BackupRow>> {
+        let (table, mut row) = match &mut self.rows {
+            RowSource::Lines(lines) => loop {
+                let Some(line) = lines.next() else {
+                    return Ok(None);
+                };
+                let line = line.map_err(|e| AppError::io("Failed to read backup file", e))?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let line: RowLine = serde_json::from_str(&line)
+                    .map_err(|e| AppError::Failed(format!("Failed to parse backup file: {}", e)))?;
+                break (line.table, line.row);
+            },
+            RowSource::Document(rows) => match rows.next() {
+                Some(row) => row,
+                None => return Ok(None),
+            },
+        };
+
+        upgrade_row(self.source_schema_version, table, &mut row)?;
+        BackupRow::from_value(table, row)
+            .map(Some)
+            .map_err(|e| AppError::Failed(format!("Failed to parse {} row in backup: {}", table.as_str(), e)))
+    }
+
+    /// Loads the remaining rows in memory, for previews.
+    pub fn read_all(mut self) -> AppResult<DatabaseBackup> {
+        let mut backup = DatabaseBackup::new(
+            self.header.version.clone(),
+            self.header.schema_version,
+            self.header.created_at.clone(),
+        );
+        while let Some(row) = self.next_row()? {
+            backup.push(row);
+        }
+        Ok(backup)
+    }
+}
+
+/// Takes a legacy document apart into its header and rows, tables in
+/// dependency order. Tables it predates just have no rows.
+fn split_document(mut document: Value) -> (Value, RowSource) {
+    let mut record_counts = BackupRecordCounts::default();
+    let mut rows = Vec::new();
+    for table in BackupTable::ALL {
+        if let Some(Value::Array(table_rows)) = document.get_mut(table.as_str()).map(Value::take) {
+            record_counts.set(table, table_rows.len());
+            rows.extend(table_rows.into_iter().map(|row| (table, row)));
+        }
+    }
+
+    let mut header = json!({
+        "version": document.get("version").cloned().unwrap_or_else(|| json!("")),
+        "created_at": document.get("created_at").cloned().unwrap_or_else(|| json!("")),
+        "record_counts": record_counts,
+    });
+    if let Some(schema_version) = document.get("schema_version") {
+        header["schema_version"] = schema_version.clone();
+    }
+    (header, RowSource::Document(rows.into_iter()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::container::Compression;
+    use crate::backup::upgrade::current_schema_version;
+    use std::path::PathBuf;
+
+    fn temp_path() -> PathBuf {
+        std::env::temp_dir().join(format!("editart-stream-{}.backup", uuid::Uuid::new_v4()))
+    }
+
+    fn rows() -> Vec<BackupRow> {
+        let client = json!({
+            "id": "c1", "name": "Silva", "nuit": "400123456", "contact": "841234567", "category": "Empresa",
+            "observations": "", "debt": "150.50",
+            "created_at": "2025-01-01T00:00:00Z", "updated_at": "2025-01-01T00:00:00Z",
+        });
+        let payment = json!({
+            "id": "p1", "order_id": "o1", "amount": "49.50", "payment_date": "2025-02-01T00:00:00Z",
+            "method": "cash", "reference": null, "received_by": null,
+            "created_at": "2025-02-01T00:00:00Z", "updated_at": "2025-02-01T00:00:00Z",
+        });
+        vec![
+            BackupRow::from_value(BackupTable::Clients, client).unwrap(),
+            BackupRow::from_value(BackupTable::Payments, payment).unwrap(),
+        ]
+    }
+
+    fn values(rows: &[BackupRow]) -> Vec<(BackupTable, Value)> {
+        rows.iter().map(|row| (row.table(), row.to_value().unwrap())).collect()
+    }
+
+    #[test]
+    fn rows_round_trip_through_every_file_format() {
+        let header = BackupHeader {
+            version: "1.2.3".to_string(),
+            schema_version: current_schema_version(),
+            created_at: "2025-03-01T10:00:00Z".to_string(),
+            record_counts: BackupRecordCounts { clients: 1, payments: 1, ..Default::default() },
+        };
+        for options in [
+            BackupFileOptions::default(),
+            BackupFileOptions { compression: Compression::Gzip, passphrase: Some("correct horse".to_string()) },
+        ] {
+            let path = temp_path();
+            let mut writer = BackupWriter::create(&path, &options, &header).unwrap();
+            for row in rows() {
+                writer.write_row(&row).unwrap();
+            }
+            let written = writer.finish().unwrap();
+
+            let mut reader = BackupReader::open(&path, options.passphrase.as_deref()).unwrap();
+            let mut read = Vec::new();
+            while let Some(row) = reader.next_row().unwrap() {
+                read.push(row);
+            }
+            std::fs::remove_file(&path).unwrap();
+
+            assert_eq!((written.clients, written.payments, written.orders), (1, 1, 0));
+            assert_eq!(reader.source_schema_version(), current_schema_version());
+            assert_eq!(reader.header().version, "1.2.3");
+            assert_eq!(reader.header().record_counts.payments, 1);
+            assert_eq!(values(&read), values(&rows()), "{:?} did not round trip", options);
+        }
+    }
+
+    #[test]
+    fn reads_legacy_documents_in_dependency_order() {
+        let path = temp_path();
+        let document = json!({
+            "version": "0.1.0",
+            "schema_version": 2,
+            "created_at": "2025-01-01T00:00:00Z",
+            "payments": [{
+                "id": "p1", "order_id": "o1", "amount": 49.5, "payment_date": "2025-02-01T00:00:00Z",
+                "method": "cash", "reference": null, "received_by": null,
+                "created_at": "2025-02-01T00:00:00Z", "updated_at": "2025-02-01T00:00:00Z",
+            }],
+            "clients": [{
+                "id": "c1", "name": "Silva", "nuit": "", "contact": "", "category": "Empresa",
+                "observations": "", "debt": 0,
+                "created_at": "2025-01-01T00:00:00Z", "updated_at": "2025-01-01T00:00:00Z",
+            }],
+        });
+        std::fs::write(&path, serde_json::to_vec_pretty(&document).unwrap()).unwrap();
+
+        let mut reader = BackupReader::open(&path, None).unwrap();
+        let mut tables = Vec::new();
+        while let Some(row) = reader.next_row().unwrap() {
+            tables.push(row.table());
+        }
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(reader.source_schema_version(), 2);
+        assert_eq!(reader.header().schema_version, current_schema_version());
+        assert_eq!(reader.header().record_counts.clients, 1);
+        assert_eq!(tables, [BackupTable::Clients, BackupTable::Payments]);
+    }
+}
@@ -0,0 +1,362 @@
+use crate::backup::selection::BackupTable;
+use crate::error::{AppError, AppResult};
+use crate::migrations::{CLEAR_DUPLICATE_NUITS_SQL, MIGRATIONS, RENUMBER_DUPLICATE_REQUISITIONS_SQL};
+use crate::models::OrderStatus;
+use crate::nuit;
+use serde_json::{json, Map, Value};
+use sqlx::PgConnection;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+/// A backup's schema version is the database migration it was taken at.
+/// Backups written before the field existed count as version 1.
+pub fn current_schema_version() -> i64 {
+    MIGRATIONS.last().map(|migration| migration.version).unwrap_or(1)
+}
+
+type UpgradeStep = fn(BackupTable, &mut Map<String, Value>) -> AppResult<()>;
+
+/// `(version, step)`: each step brings a row from `version - 1` to
+/// `version`. A migration that changes a backed-up table needs a step here,
+/// even an empty one. Steps only fill in what is missing, because backups
+/// taken before versioning may already have some of the newer fields.
+const UPGRADE_STEPS: &[(i64, UpgradeStep)] = &[
+    (2, upgrade_to_payments),
+    (3, upgrade_to_user_active),
+    (4, upgrade_to_order_numbering),
+    (5, upgrade_to_money_numeric),
+    (6, upgrade_to_status_history),
+    (7, upgrade_to_client_nuit_unique),
+    (8, upgrade_to_order_number_scope),
+];
+
+/// What a migration did across rows, which `upgrade_row` cannot do one row
+/// at a time: while restoring a backup older than `version`, `indexes` are
+/// dropped, and `resolve` runs once all rows are in, before they are rebuilt.
+struct CrossRowStep {
+    version: i64,
+    indexes: &'static [&'static str],
+    resolve: &'static [&'static str],
+}
+
+const CROSS_ROW_STEPS: &[CrossRowStep] = &[
+    CrossRowStep {
+        version: 4,
+        indexes: &["idx_orders_year_number", "idx_orders_client_requisition", "idx_orders_scope_number"],
+        resolve: &[RENUMBER_DUPLICATE_ORDERS_SQL, RENUMBER_DUPLICATE_REQUISITIONS_SQL],
+    },
+    CrossRowStep {
+        version: 7,
+        indexes: &["idx_clients_nuit"],
+        resolve: &[CLEAR_DUPLICATE_NUITS_SQL],
+    },
+];
+
+/// Migration 0004 renumbered duplicates of the global number. Numbers must
+/// be unique within a year and within their scope (`orders.number_scope`),
+/// so duplicates are looked for in both; the oldest order keeps its number
+/// and the others go past the highest one in use, as in the migration.
+const RENUMBER_DUPLICATE_ORDERS_SQL: &str = r#"
+    WITH ranked AS (
+        SELECT id, created_at,
+               ROW_NUMBER() OVER (PARTITION BY order_year, order_number ORDER BY created_at, id) AS year_rn,
+               ROW_NUMBER() OVER (PARTITION BY number_scope, order_number ORDER BY created_at, id) AS scope_rn
+        FROM orders
+    ),
+    renumbered AS (
+        SELECT id,
+               (SELECT COALESCE(MAX(order_number), 0) FROM orders)
+                   + ROW_NUMBER() OVER (ORDER BY created_at, id) AS new_number
+        FROM ranked
+        WHERE year_rn > 1 OR scope_rn > 1
+    )
+    UPDATE orders SET order_number = renumbered.new_number
+    FROM renumbered
+    WHERE orders.id = renumbered.id
+"#;
+
+/// Unique indexes dropped for a restore, with what rebuilds them
+pub struct RelaxedIndexes {
+    resolve: Vec<&'static str>,
+    definitions: Vec<String>,
+}
+
+/// Drops the unique indexes rows from schema `from` may violate. Runs inside
+/// the restore transaction, so a failed restore gets them back.
+pub async fn relax_unique_indexes(conn: &mut PgConnection, from: i64) -> AppResult<RelaxedIndexes> {
+    let mut relaxed = RelaxedIndexes { resolve: Vec::new(), definitions: Vec::new() };
+    for step in CROSS_ROW_STEPS.iter().filter(|step| step.version > from) {
+        let definitions: Vec<String> = sqlx::query_scalar("SELECT indexdef FROM pg_indexes WHERE indexname = ANY($1)")
+            .bind(step.indexes)
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(|e| AppError::database("Failed to read unique indexes", e))?;
+
+        for index in step.indexes {
+            sqlx::query(&format!("DROP INDEX IF EXISTS {}", index))
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| AppError::database(&format!("Failed to drop index {}", index), e))?;
+        }
+        relaxed.resolve.extend(step.resolve);
+        relaxed.definitions.extend(definitions);
+    }
+    Ok(relaxed)
+}
+
+/// Resolves the duplicates the relaxed indexes let in, then rebuilds them
+pub async fn restore_unique_indexes(conn: &mut PgConnection, relaxed: RelaxedIndexes) -> AppResult<()> {
+    for sql in relaxed.resolve {
+        let result = sqlx::query(sql)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| AppError::database("Failed to resolve duplicates in restored rows", e))?;
+        if result.rows_affected() > 0 {
+            log::info!("Resolved {} duplicates in restored rows", result.rows_affected());
+        }
+    }
+    for definition in relaxed.definitions {
+        sqlx::query(&definition)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| AppError::database(&format!("Failed to rebuild index ({})", definition), e))?;
+    }
+    Ok(())
+}
+
+/// The schema a backup header (or legacy document) was written at,
+/// refusing backups newer than this app.
+pub fn schema_version_of(header: &Value) -> AppResult<i64> {
+    let current = current_schema_version();
+    let version = match header.get("schema_version") {
+        None | Some(Value::Null) => 1,
+        Some(version) => version.as_i64()
+            .ok_or_else(|| AppError::Failed(format!("Invalid backup schema version: {}", version)))?,
+    };
+
+    if version > current {
+        return Err(AppError::Failed(format!(
+            "This backup was taken by a newer version of the app (schema {}, this version supports up to {}). Update the app to restore it",
+            version, current
+        )));
+    }
+    Ok(version)
+}
+
+/// Brings the backup header from schema `from` to the current one.
+pub fn upgrade_header(from: i64, header: &mut Value) -> AppResult<()> {
+    if from < 2 {
+        if let Some(created_at) = header.get_mut("created_at") {
+            normalize_timestamp(created_at)
+                .map_err(|e| AppError::Failed(format!("Failed to upgrade backup to schema 2: {}", e)))?;
+        }
+    }
+
+    let current = current_schema_version();
+    header["schema_version"] = json!(current);
+    if from < current {
+        log::info!("Upgrading backup from schema {} to {}", from, current);
+    }
+    Ok(())
+}
+
+/// Brings one row of `table` from schema `from` to the current one, step by step.
+pub fn upgrade_row(from: i64, table: BackupTable, row: &mut Value) -> AppResult<()> {
+    let Some(row) = row.as_object_mut() else {
+        return Err(AppError::Failed(format!("Invalid {} row in backup", table.as_str())));
+    };
+    for (version, step) in UPGRADE_STEPS {
+        if *version > from {
+            step(table, row)
+                .map_err(|e| AppError::Failed(format!("Failed to upgrade backup to schema {}: {}", version, e)))?;
+        }
+    }
+    Ok(())
+}
+
+/// Pre-migration backups wrote timestamps with `OffsetDateTime`'s display
+/// format ("2025-09-10 18:22:38.09965 +00:00:00"). They become RFC3339.
+fn normalize_timestamp(value: &mut Value) -> AppResult<()> {
+    let Some(text) = value.as_str() else {
+        return Ok(());
+    };
+    if OffsetDateTime::parse(text, &Rfc3339).is_ok() {
+        return Ok(());
+    }
+
+    let display_format = time::format_description::parse(
+        "[year]-[month]-[day] [hour]:[minute]:[second].[subsecond] [offset_hour]:[offset_minute]:[offset_second]",
+    )
+    .map_err(|e| AppError::Failed(format!("Failed to create timestamp parser: {}", e)))?;
+    let parsed = OffsetDateTime::parse(text, &display_format)
+        .map_err(|_| AppError::Failed(format!("Unrecognized timestamp: {}", text)))?;
+
+    *value = json!(parsed.format(&Rfc3339)
+        .map_err(|e| AppError::Failed(format!("Failed to format timestamp: {}", e)))?);
+    Ok(())
+}
+
+/// Payments arrived with migration 2; a backup without them simply has no
+/// payment rows. Older backups are also where the legacy timestamp format lives.
+fn upgrade_to_payments(_table: BackupTable, row: &mut Map<String, Value>) -> AppResult<()> {
+    for field in ["created_at", "updated_at"] {
+        if let Some(value) = row.get_mut(field) {
+            normalize_timestamp(value)?;
+        }
+    }
+    Ok(())
+}
+
+fn upgrade_to_user_active(table: BackupTable, row: &mut Map<String, Value>) -> AppResult<()> {
+    if table == BackupTable::Users {
+        row.entry("active").or_insert(json!(true));
+    }
+    Ok(())
+}
+
+/// Orders got the year their number belongs to; it is the year they were created.
+fn upgrade_to_order_numbering(table: BackupTable, order: &mut Map<String, Value>) -> AppResult<()> {
+    if table != BackupTable::Orders || order.get("order_year").is_some_and(|year| !year.is_null()) {
+        return Ok(());
+    }
+    let created_at = order.get("created_at").and_then(Value::as_str).unwrap_or_default();
+    let year = OffsetDateTime::parse(created_at, &Rfc3339)
+        .map_err(|e| AppError::Failed(format!("Invalid created_at on order: {}", e)))?
+        .year();
+    order.insert("order_year".to_string(), json!(year));
+    Ok(())
+}
+
+/// Discount and IVA became NOT NULL
+fn upgrade_to_money_numeric(table: BackupTable, order: &mut Map<String, Value>) -> AppResult<()> {
+    if table != BackupTable::Orders {
+        return Ok(());
+    }
+    for field in ["discount", "iva"] {
+        if order.get(field).is_none_or(Value::is_null) {
+            order.insert(field.to_string(), json!(0));
+        }
+    }
+    Ok(())
+}
+
+/// Same normalization as migration 0006: unknown statuses become order_received.
+fn upgrade_to_status_history(table: BackupTable, order: &mut Map<String, Value>) -> AppResult<()> {
+    if table != BackupTable::Orders {
+        return Ok(());
+    }
+    let known = order.get("status")
+        .and_then(Value::as_str)
+        .is_some_and(|status| OrderStatus::parse(status).is_some());
+    if !known {
+        order.insert("status".to_string(), json!("order_received"));
+    }
+    Ok(())
+}
+
+/// Same normalization as migration 0007. NUITs the backup holds twice are
+/// cleared by the cross-row step once the rows are in.
+fn upgrade_to_client_nuit_unique(table: BackupTable, client: &mut Map<String, Value>) -> AppResult<()> {
+    if table != BackupTable::Clients {
+        return Ok(());
+    }
+    if let Some(Value::String(nuit)) = client.get_mut("nuit") {
+        *nuit = nuit::normalize(nuit);
+    }
+    Ok(())
+}
+
+/// Same as migration 0008: older orders count as numbered per year, which the
+/// previous unique index already guaranteed.
+fn upgrade_to_order_number_scope(table: BackupTable, order: &mut Map<String, Value>) -> AppResult<()> {
+    if table != BackupTable::Orders || order.get("number_scope").is_some_and(|scope| !scope.is_null()) {
+        return Ok(());
+    }
+    let year = order.get("order_year")
+        .and_then(Value::as_i64)
+        .ok_or_else(|| AppError::Failed("Missing order_year on order".to_string()))?;
+    order.insert("number_scope".to_string(), json!(format!("order:{}", year)));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn upgraded(from: i64, table: BackupTable, mut row: Value) -> Value {
+        upgrade_row(from, table, &mut row).unwrap();
+        row
+    }
+
+    #[test]
+    fn steps_reach_the_current_schema() {
+        assert_eq!(UPGRADE_STEPS.last().map(|(version, _)| *version), Some(current_schema_version()));
+        assert!(UPGRADE_STEPS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn brings_a_first_version_order_up_to_date() {
+        let order = upgraded(1, BackupTable::Orders, json!({
+            "id": "o1",
+            "status": "pending",
+            "discount": null,
+            "created_at": "2024-12-31 23:59:59.5 +00:00:00",
+            "updated_at": "2025-01-02 08:00:00.0 +02:00:00",
+        }));
+        assert_eq!(order["created_at"], "2024-12-31T23:59:59.5Z");
+        assert_eq!(order["updated_at"], "2025-01-02T08:00:00+02:00");
+        assert_eq!(order["order_year"], 2024);
+        assert_eq!(order["number_scope"], "order:2024");
+        assert_eq!(order["discount"], 0);
+        assert_eq!(order["iva"], 0);
+        assert_eq!(order["status"], "order_received");
+    }
+
+    #[test]
+    fn keeps_fields_the_backup_already_has() {
+        let order = upgraded(1, BackupTable::Orders, json!({
+            "status": "delivered",
+            "order_year": 2023,
+            "number_scope": "order",
+            "discount": "5.00",
+            "iva": "16",
+            "created_at": "2025-03-01T10:00:00Z",
+        }));
+        assert_eq!(order["status"], "delivered");
+        assert_eq!(order["order_year"], 2023);
+        assert_eq!(order["number_scope"], "order");
+        assert_eq!(order["discount"], "5.00");
+        assert_eq!(order["iva"], "16");
+    }
+
+    #[test]
+    fn only_runs_steps_newer_than_the_backup() {
+        let from_five = upgraded(5, BackupTable::Orders, json!({ "status": "pending", "order_year": 2025 }));
+        assert_eq!(from_five["status"], "order_received");
+
+        let current = json!({ "status": "pending", "order_year": 2025, "number_scope": "order:2025" });
+        assert_eq!(upgraded(current_schema_version(), BackupTable::Orders, current.clone()), current);
+    }
+
+    #[test]
+    fn upgrades_users_and_clients() {
+        let user = upgraded(2, BackupTable::Users, json!({ "username": "ana" }));
+        assert_eq!(user["active"], true);
+        let inactive = upgraded(2, BackupTable::Users, json!({ "username": "rui", "active": false }));
+        assert_eq!(inactive["active"], false);
+
+        let client = upgraded(6, BackupTable::Clients, json!({ "nuit": " 400 339 910 " }));
+        assert_eq!(client["nuit"], "400339910");
+        let placeholder = upgraded(6, BackupTable::Clients, json!({ "nuit": "N/A" }));
+        assert_eq!(placeholder["nuit"], "");
+    }
+
+    #[test]
+    fn rejects_rows_that_cannot_be_upgraded() {
+        assert!(upgrade_row(1, BackupTable::Orders, &mut json!([1, 2])).is_err());
+        let mut undated = json!({ "status": "delivered" });
+        assert!(upgrade_row(3, BackupTable::Orders, &mut undated).is_err());
+        let mut bad_timestamp = json!({ "created_at": "yesterday" });
+        assert!(upgrade_row(1, BackupTable::Payments, &mut bad_timestamp).is_err());
+    }
+}
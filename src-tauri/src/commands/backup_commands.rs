@@ -1,16 +1,113 @@
+use tauri::{AppHandle, State};
+use crate::auth::session::SessionStore;
 use crate::backup::BackupService;
+use crate::backup::catalog::{BackupInfo, BackupInspection};
+use crate::backup::container::BackupFileOptions;
+use crate::backup::pg_tools::{PgTools, PgToolsInfo};
+use crate::backup::preview::RestorePreview;
+use crate::backup::scheduler::validate_schedule;
+use crate::backup::selection::{BackupTable, RestoreOptions};
+use crate::backup::stream::emit_progress;
+use crate::error::AppResult;
+use crate::config::{load_config, load_file_config, save_config, BackupScheduleConfig, PgToolsConfig};
+use crate::models::Role;
 
+/// Any user can back up to the backup directory; only admins choose another `destination`.
 #[tauri::command]
-pub async fn create_database_backup() -> Result<String, String> {
-    BackupService::create_backup().await
+pub async fn create_database_backup(app: AppHandle, sessions: State<'_, SessionStore>, token: String, destination: Option<String>, options: Option<BackupFileOptions>) -> AppResult<BackupInfo> {
+    sessions.authorize(&token, destination_role(&destination))?;
+    BackupService::create_backup(destination, &options.unwrap_or_default(), &emit_progress(&app)).await
 }
 
+/// Same rule for `destination` as `create_database_backup`.
 #[tauri::command]
-pub async fn restore_database_backup() -> Result<String, String> {
-    BackupService::restore_backup().await
+pub async fn create_pg_dump_backup(sessions: State<'_, SessionStore>, token: String, destination: Option<String>) -> AppResult<BackupInfo> {
+    sessions.authorize(&token, destination_role(&destination))?;
+    BackupService::create_pg_dump_backup(destination).await
+}
+
+/// A custom destination writes the whole database anywhere on disk
+fn destination_role(destination: &Option<String>) -> Role {
+    if destination.is_some() { Role::Admin } else { Role::User }
+}
+
+#[tauri::command]
+pub fn list_backups(sessions: State<'_, SessionStore>, token: String) -> AppResult<Vec<BackupInfo>> {
+    sessions.authorize(&token, Role::User)?;
+    BackupService::list_backups()
 }
 
 #[tauri::command]
-pub fn get_backup_info() -> Result<Option<String>, String> {
-    BackupService::get_backup_info()
-}
\ No newline at end of file
+pub fn get_backup_info(sessions: State<'_, SessionStore>, token: String, id: String) -> AppResult<Option<BackupInspection>> {
+    sessions.authorize(&token, Role::User)?;
+    BackupService::get_backup_info(&id)
+}
+
+#[tauri::command]
+pub fn delete_backup(sessions: State<'_, SessionStore>, token: String, id: String) -> AppResult<bool> {
+    sessions.authorize(&token, Role::Admin)?;
+    BackupService::delete_backup(&id)
+}
+
+#[tauri::command]
+pub async fn preview_database_restore(sessions: State<'_, SessionStore>, token: String, id: String, options: Option<RestoreOptions>, passphrase: Option<String>) -> AppResult<RestorePreview> {
+    sessions.authorize(&token, Role::Admin)?;
+    BackupService::preview_restore(&id, &options.unwrap_or_default(), passphrase.as_deref()).await
+}
+
+#[tauri::command]
+pub async fn restore_database_backup(app: AppHandle, sessions: State<'_, SessionStore>, token: String, id: String, options: Option<RestoreOptions>, passphrase: Option<String>) -> AppResult<String> {
+    sessions.authorize(&token, Role::Admin)?;
+    let options = options.unwrap_or_default();
+    let message = BackupService::restore_backup(&id, &options, passphrase.as_deref(), &emit_progress(&app)).await?;
+
+    // Restoring users (a full restore, which pg_dump ones always are,
+    // includes them) replaces the accounts the current sessions belong to
+    if options.includes(BackupTable::Users) {
+        sessions.revoke_all();
+    }
+    Ok(message)
+}
+
+#[tauri::command]
+pub fn get_backup_schedule(sessions: State<'_, SessionStore>, token: String) -> AppResult<BackupScheduleConfig> {
+    sessions.authorize(&token, Role::User)?;
+    Ok(load_config()?.backup)
+}
+
+#[tauri::command]
+pub fn set_backup_schedule(sessions: State<'_, SessionStore>, token: String, schedule: BackupScheduleConfig) -> AppResult<BackupScheduleConfig> {
+    sessions.authorize(&token, Role::Admin)?;
+    validate_schedule(&schedule.schedule)?;
+
+    let mut config = load_file_config()?;
+    config.backup = schedule;
+    save_config(&config)?;
+    Ok(config.backup)
+}
+
+#[tauri::command]
+pub fn get_pg_tools_config(sessions: State<'_, SessionStore>, token: String) -> AppResult<PgToolsConfig> {
+    sessions.authorize(&token, Role::User)?;
+    Ok(load_config()?.pg_tools)
+}
+
+/// Reports where pg_dump and pg_restore were found and their versions.
+#[tauri::command]
+pub async fn check_pg_tools(sessions: State<'_, SessionStore>, token: String) -> AppResult<PgToolsInfo> {
+    sessions.authorize(&token, Role::User)?;
+    let config = load_config()?;
+    PgTools::locate(&config.pg_tools)?.check().await
+}
+
+/// Only saved once both tools run from the new location.
+#[tauri::command]
+pub async fn set_pg_tools_config(sessions: State<'_, SessionStore>, token: String, pg_tools: PgToolsConfig) -> AppResult<PgToolsInfo> {
+    sessions.authorize(&token, Role::Admin)?;
+    let info = PgTools::locate(&pg_tools)?.check().await?;
+
+    let mut config = load_file_config()?;
+    config.pg_tools = pg_tools;
+    save_config(&config)?;
+    Ok(info)
+}
@@ -1,44 +1,84 @@
-use crate::dto::{CreateClientDto, UpdateClientDto, ClientResponseDto};
+use tauri::State;
+use crate::auth::session::SessionStore;
+use crate::error::AppResult;
+use crate::dto::{CreateClientDto, UpdateClientDto, ClientResponseDto, ClientFilter, ClientSortField, Page, PageRequest};
+use crate::dto::{DuplicateClientGroupDto, MergeClientsDto, MergeClientsResultDto};
+use crate::models::Role;
 use crate::services::ClientService;
 
 #[tauri::command]
-pub async fn create_client(dto: CreateClientDto) -> Result<ClientResponseDto, String> {
+pub async fn create_client(sessions: State<'_, SessionStore>, token: String, dto: CreateClientDto) -> AppResult<ClientResponseDto> {
+    sessions.authorize(&token, Role::User)?;
     let service = ClientService::new();
     service.create_client(dto).await
 }
 
 #[tauri::command]
-pub async fn get_client_by_id(id: String) -> Result<Option<ClientResponseDto>, String> {
+pub async fn get_client_by_id(sessions: State<'_, SessionStore>, token: String, id: String) -> AppResult<Option<ClientResponseDto>> {
+    sessions.authorize(&token, Role::User)?;
     let service = ClientService::new();
     service.get_client_by_id(&id).await
 }
 
 #[tauri::command]
-pub async fn get_clients_by_name(name: String) -> Result<Vec<ClientResponseDto>, String> {
+pub async fn find_client_by_nuit(sessions: State<'_, SessionStore>, token: String, nuit: String) -> AppResult<Option<ClientResponseDto>> {
+    sessions.authorize(&token, Role::User)?;
+    let service = ClientService::new();
+    service.find_client_by_nuit(&nuit).await
+}
+
+#[tauri::command]
+pub async fn get_clients_by_name(sessions: State<'_, SessionStore>, token: String, name: String) -> AppResult<Vec<ClientResponseDto>> {
+    sessions.authorize(&token, Role::User)?;
     let service = ClientService::new();
     service.get_clients_by_name(&name).await
 }
 
 #[tauri::command]
-pub async fn list_clients() -> Result<Vec<ClientResponseDto>, String> {
+pub async fn list_clients_page(sessions: State<'_, SessionStore>, token: String, filter: Option<ClientFilter>, page: Option<PageRequest<ClientSortField>>) -> AppResult<Page<ClientResponseDto>> {
+    sessions.authorize(&token, Role::User)?;
+    let service = ClientService::new();
+    service.list_clients_page(filter.unwrap_or_default(), page.unwrap_or_default()).await
+}
+
+#[tauri::command]
+pub async fn list_clients(sessions: State<'_, SessionStore>, token: String) -> AppResult<Vec<ClientResponseDto>> {
+    sessions.authorize(&token, Role::User)?;
     let service = ClientService::new();
     service.list_clients().await
 }
 
 #[tauri::command]
-pub async fn update_client(id: String, dto: UpdateClientDto) -> Result<Option<ClientResponseDto>, String> {
+pub async fn update_client(sessions: State<'_, SessionStore>, token: String, id: String, dto: UpdateClientDto) -> AppResult<Option<ClientResponseDto>> {
+    sessions.authorize(&token, Role::User)?;
     let service = ClientService::new();
     service.update_client(&id, dto).await
 }
 
 #[tauri::command]
-pub async fn delete_client(id: String) -> Result<bool, String> {
+pub async fn delete_client(sessions: State<'_, SessionStore>, token: String, id: String) -> AppResult<bool> {
+    sessions.authorize(&token, Role::Admin)?;
     let service = ClientService::new();
     service.delete_client(&id).await
 }
 
 #[tauri::command]
-pub async fn update_client_debt(client_id: String) -> Result<bool, String> {
+pub async fn find_duplicate_clients(sessions: State<'_, SessionStore>, token: String) -> AppResult<Vec<DuplicateClientGroupDto>> {
+    sessions.authorize(&token, Role::User)?;
+    let service = ClientService::new();
+    service.find_duplicate_clients().await
+}
+
+#[tauri::command]
+pub async fn merge_clients(sessions: State<'_, SessionStore>, token: String, dto: MergeClientsDto) -> AppResult<MergeClientsResultDto> {
+    sessions.authorize(&token, Role::Admin)?;
+    let service = ClientService::new();
+    service.merge_clients(dto).await
+}
+
+#[tauri::command]
+pub async fn update_client_debt(sessions: State<'_, SessionStore>, token: String, client_id: String) -> AppResult<bool> {
+    sessions.authorize(&token, Role::User)?;
     let service = ClientService::new();
     service.update_client_debt(&client_id).await
 }
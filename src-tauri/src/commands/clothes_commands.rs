@@ -1,62 +1,77 @@
+use tauri::State;
+use crate::auth::session::SessionStore;
+use crate::error::AppResult;
 use crate::dto::{ClothesDto, CreateClothesDto, UpdateClothesDto, CreateClothingServiceDto, UpdateClothingServiceDto, ClothingServiceDto};
+use crate::models::Role;
 use crate::services::ClothesService;
+use rust_decimal::Decimal;
 
 #[tauri::command]
-pub async fn create_clothes(dto: CreateClothesDto) -> Result<ClothesDto, String> {
+pub async fn create_clothes(sessions: State<'_, SessionStore>, token: String, dto: CreateClothesDto) -> AppResult<ClothesDto> {
+    sessions.authorize(&token, Role::User)?;
     let service = ClothesService::new();
     service.create_clothes(dto).await
 }
 
 #[tauri::command]
-pub async fn get_clothes_by_id(id: String) -> Result<Option<ClothesDto>, String> {
+pub async fn get_clothes_by_id(sessions: State<'_, SessionStore>, token: String, id: String) -> AppResult<Option<ClothesDto>> {
+    sessions.authorize(&token, Role::User)?;
     let service = ClothesService::new();
     service.get_clothes_by_id(&id).await
 }
 
 #[tauri::command]
-pub async fn get_clothes_by_order_id(order_id: String) -> Result<Vec<ClothesDto>, String> {
+pub async fn get_clothes_by_order_id(sessions: State<'_, SessionStore>, token: String, order_id: String) -> AppResult<Vec<ClothesDto>> {
+    sessions.authorize(&token, Role::User)?;
     let service = ClothesService::new();
     service.get_clothes_by_order_id(&order_id).await
 }
 
 #[tauri::command]
-pub async fn update_clothes(id: String, dto: UpdateClothesDto) -> Result<Option<ClothesDto>, String> {
+pub async fn update_clothes(sessions: State<'_, SessionStore>, token: String, id: String, dto: UpdateClothesDto) -> AppResult<Option<ClothesDto>> {
+    sessions.authorize(&token, Role::User)?;
     let service = ClothesService::new();
     service.update_clothes(&id, dto).await
 }
 
 #[tauri::command]
-pub async fn delete_clothes(id: String) -> Result<bool, String> {
+pub async fn delete_clothes(sessions: State<'_, SessionStore>, token: String, id: String) -> AppResult<bool> {
+    sessions.authorize(&token, Role::User)?;
     let service = ClothesService::new();
     service.delete_clothes(&id).await
 }
 
 #[tauri::command]
-pub async fn add_service_to_clothes(clothes_id: String, dto: CreateClothingServiceDto) -> Result<ClothingServiceDto, String> {
+pub async fn add_service_to_clothes(sessions: State<'_, SessionStore>, token: String, clothes_id: String, dto: CreateClothingServiceDto) -> AppResult<ClothingServiceDto> {
+    sessions.authorize(&token, Role::User)?;
     let service = ClothesService::new();
     service.add_service_to_clothes(&clothes_id, dto).await
 }
 
 #[tauri::command]
-pub async fn update_clothing_service(service_id: String, dto: UpdateClothingServiceDto) -> Result<Option<ClothingServiceDto>, String> {
+pub async fn update_clothing_service(sessions: State<'_, SessionStore>, token: String, service_id: String, dto: UpdateClothingServiceDto) -> AppResult<Option<ClothingServiceDto>> {
+    sessions.authorize(&token, Role::User)?;
     let service = ClothesService::new();
     service.update_service(&service_id, dto).await
 }
 
 #[tauri::command]
-pub async fn delete_clothing_service(service_id: String) -> Result<bool, String> {
+pub async fn delete_clothing_service(sessions: State<'_, SessionStore>, token: String, service_id: String) -> AppResult<bool> {
+    sessions.authorize(&token, Role::User)?;
     let service = ClothesService::new();
     service.delete_service(&service_id).await
 }
 
 #[tauri::command]
-pub async fn get_services_by_clothes_id(clothes_id: String) -> Result<Vec<ClothingServiceDto>, String> {
+pub async fn get_services_by_clothes_id(sessions: State<'_, SessionStore>, token: String, clothes_id: String) -> AppResult<Vec<ClothingServiceDto>> {
+    sessions.authorize(&token, Role::User)?;
     let service = ClothesService::new();
     service.get_services_by_clothes_id(&clothes_id).await
 }
 
 #[tauri::command]
-pub async fn calculate_order_clothes_total(order_id: String) -> Result<f64, String> {
+pub async fn calculate_order_clothes_total(sessions: State<'_, SessionStore>, token: String, order_id: String) -> AppResult<Decimal> {
+    sessions.authorize(&token, Role::User)?;
     let service = ClothesService::new();
     service.calculate_order_clothes_total(&order_id).await
 }
@@ -0,0 +1,45 @@
+use tauri::State;
+use crate::auth::session::SessionStore;
+use crate::error::AppResult;
+use crate::database::is_connected;
+use crate::dto::{DatabaseSettingsDto, DatabaseStatusDto, DatabaseTestResultDto};
+use crate::models::Role;
+use crate::services::DatabaseService;
+
+/// Nobody can log in while the app is not connected, so until a connection
+/// works the settings are open to whoever runs the app. Once connected they
+/// are admin only.
+fn authorize_settings(sessions: &SessionStore, token: Option<&str>) -> AppResult<()> {
+    if is_connected() {
+        sessions.authorize(token.unwrap_or_default(), Role::Admin)?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_database_status() -> DatabaseStatusDto {
+    DatabaseService::status()
+}
+
+#[tauri::command]
+pub fn get_database_settings(sessions: State<'_, SessionStore>, token: Option<String>) -> AppResult<DatabaseSettingsDto> {
+    authorize_settings(&sessions, token.as_deref())?;
+    DatabaseService::get_settings()
+}
+
+#[tauri::command]
+pub async fn test_database_settings(sessions: State<'_, SessionStore>, token: Option<String>, settings: DatabaseSettingsDto) -> AppResult<DatabaseTestResultDto> {
+    authorize_settings(&sessions, token.as_deref())?;
+    DatabaseService::test_settings(settings).await
+}
+
+/// Reconnects with the new settings without a restart. Switching to another
+/// database ends every session, since its users are not the ones logged in.
+#[tauri::command]
+pub async fn save_database_settings(sessions: State<'_, SessionStore>, token: Option<String>, settings: DatabaseSettingsDto) -> AppResult<DatabaseStatusDto> {
+    authorize_settings(&sessions, token.as_deref())?;
+    if DatabaseService::save_settings(settings).await? {
+        sessions.revoke_all();
+    }
+    Ok(DatabaseService::status())
+}
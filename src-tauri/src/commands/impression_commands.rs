@@ -1,32 +1,41 @@
+use tauri::State;
+use crate::auth::session::SessionStore;
+use crate::error::AppResult;
 use crate::dto::{ImpressionDto, CreateImpressionDto, UpdateImpressionDto};
+use crate::models::Role;
 use crate::services::ImpressionService;
 
 #[tauri::command]
-pub async fn create_impression(dto: CreateImpressionDto) -> Result<ImpressionDto, String> {
+pub async fn create_impression(sessions: State<'_, SessionStore>, token: String, dto: CreateImpressionDto) -> AppResult<ImpressionDto> {
+    sessions.authorize(&token, Role::User)?;
     let service = ImpressionService::new();
     service.create_impression(dto).await
 }
 
 #[tauri::command]
-pub async fn get_impression_by_id(id: String) -> Result<Option<ImpressionDto>, String> {
+pub async fn get_impression_by_id(sessions: State<'_, SessionStore>, token: String, id: String) -> AppResult<Option<ImpressionDto>> {
+    sessions.authorize(&token, Role::User)?;
     let service = ImpressionService::new();
     service.get_impression_by_id(&id).await
 }
 
 #[tauri::command]
-pub async fn get_impressions_by_order_id(order_id: String) -> Result<Vec<ImpressionDto>, String> {
+pub async fn get_impressions_by_order_id(sessions: State<'_, SessionStore>, token: String, order_id: String) -> AppResult<Vec<ImpressionDto>> {
+    sessions.authorize(&token, Role::User)?;
     let service = ImpressionService::new();
     service.get_impressions_by_order_id(&order_id).await
 }
 
 #[tauri::command]
-pub async fn update_impression(id: String, dto: UpdateImpressionDto) -> Result<Option<ImpressionDto>, String> {
+pub async fn update_impression(sessions: State<'_, SessionStore>, token: String, id: String, dto: UpdateImpressionDto) -> AppResult<Option<ImpressionDto>> {
+    sessions.authorize(&token, Role::User)?;
     let service = ImpressionService::new();
     service.update_impression(&id, dto).await
 }
 
 #[tauri::command]
-pub async fn delete_impression(id: String) -> Result<bool, String> {
+pub async fn delete_impression(sessions: State<'_, SessionStore>, token: String, id: String) -> AppResult<bool> {
+    sessions.authorize(&token, Role::User)?;
     let service = ImpressionService::new();
     service.delete_impression(&id).await
-}
\ No newline at end of file
+}
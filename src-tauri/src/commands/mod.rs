@@ -2,14 +2,18 @@ pub mod client_commands;
 pub mod clothes_commands;
 pub mod impression_commands;
 pub mod order_commands;
+pub mod payment_commands;
 pub mod user_commands;
 pub mod window_commands;
 pub mod backup_commands;
+pub mod database_commands;
 
 pub use client_commands::*;
 pub use clothes_commands::*;
 pub use impression_commands::*;
 pub use order_commands::*;
+pub use payment_commands::*;
 pub use user_commands::*;
 pub use window_commands::*;
 pub use backup_commands::*;
+pub use database_commands::*;
@@ -1,51 +1,78 @@
-use crate::dto::{CreateOrderDto, UpdateOrderDto, OrderResponseDto};
+use tauri::State;
+use crate::auth::session::SessionStore;
+use crate::error::AppResult;
+use crate::dto::{CreateOrderDto, UpdateOrderDto, OrderResponseDto, OrderStatusChangeDto, OrderFilter, OrderSortField, Page, PageRequest};
+use crate::models::Role;
 use crate::services::OrderService;
 use time::Date;
+use rust_decimal::Decimal;
 
 #[tauri::command]
-pub async fn create_order(dto: CreateOrderDto) -> Result<OrderResponseDto, String> {
+pub async fn create_order(sessions: State<'_, SessionStore>, token: String, dto: CreateOrderDto) -> AppResult<OrderResponseDto> {
+    let session = sessions.authorize(&token, Role::User)?;
     let service = OrderService::new();
-    service.create_order(dto).await
+    service.create_order(dto, Some(session.user_id)).await
 }
 
 #[tauri::command]
-pub async fn get_order_by_id(id: String) -> Result<Option<OrderResponseDto>, String> {
+pub async fn get_order_by_id(sessions: State<'_, SessionStore>, token: String, id: String) -> AppResult<Option<OrderResponseDto>> {
+    sessions.authorize(&token, Role::User)?;
     let service = OrderService::new();
     service.get_order_by_id(&id).await
 }
 
 #[tauri::command]
-pub async fn get_orders_by_client_id(client_id: String) -> Result<Vec<OrderResponseDto>, String> {
+pub async fn get_orders_by_client_id(sessions: State<'_, SessionStore>, token: String, client_id: String) -> AppResult<Vec<OrderResponseDto>> {
+    sessions.authorize(&token, Role::User)?;
     let service = OrderService::new();
     service.get_orders_by_client_id(&client_id).await
 }
 
 #[tauri::command]
-pub async fn get_orders_by_date_range(start_date: Date, end_date: Date) -> Result<Vec<OrderResponseDto>, String> {
+pub async fn get_orders_by_date_range(sessions: State<'_, SessionStore>, token: String, start_date: Date, end_date: Date) -> AppResult<Vec<OrderResponseDto>> {
+    sessions.authorize(&token, Role::User)?;
     let service = OrderService::new();
     service.get_orders_by_date_range(start_date, end_date).await
 }
 
 #[tauri::command]
-pub async fn list_orders() -> Result<Vec<OrderResponseDto>, String> {
+pub async fn list_orders(sessions: State<'_, SessionStore>, token: String) -> AppResult<Vec<OrderResponseDto>> {
+    sessions.authorize(&token, Role::User)?;
     let service = OrderService::new();
     service.list_orders().await
 }
 
 #[tauri::command]
-pub async fn update_order(id: String, dto: UpdateOrderDto) -> Result<Option<OrderResponseDto>, String> {
+pub async fn list_orders_page(sessions: State<'_, SessionStore>, token: String, filter: Option<OrderFilter>, page: Option<PageRequest<OrderSortField>>) -> AppResult<Page<OrderResponseDto>> {
+    sessions.authorize(&token, Role::User)?;
     let service = OrderService::new();
-    service.update_order(&id, dto).await
+    service.list_orders_page(filter.unwrap_or_default(), page.unwrap_or_default()).await
 }
 
 #[tauri::command]
-pub async fn delete_order(id: String) -> Result<bool, String> {
+pub async fn update_order(sessions: State<'_, SessionStore>, token: String, id: String, dto: UpdateOrderDto) -> AppResult<Option<OrderResponseDto>> {
+    let session = sessions.authorize(&token, Role::User)?;
+    let service = OrderService::new();
+    service.update_order(&id, dto, Some(session.user_id)).await
+}
+
+#[tauri::command]
+pub async fn get_order_status_history(sessions: State<'_, SessionStore>, token: String, order_id: String) -> AppResult<Vec<OrderStatusChangeDto>> {
+    sessions.authorize(&token, Role::User)?;
+    let service = OrderService::new();
+    service.get_order_status_history(&order_id).await
+}
+
+#[tauri::command]
+pub async fn delete_order(sessions: State<'_, SessionStore>, token: String, id: String) -> AppResult<bool> {
+    sessions.authorize(&token, Role::Admin)?;
     let service = OrderService::new();
     service.delete_order(&id).await
 }
 
 #[tauri::command]
-pub async fn pay_order_debt(id: String, payment_amount: f64) -> Result<bool, String> {
+pub async fn pay_order_debt(sessions: State<'_, SessionStore>, token: String, id: String, payment_amount: Decimal) -> AppResult<bool> {
+    let session = sessions.authorize(&token, Role::User)?;
     let service = OrderService::new();
-    service.pay_order_debt(&id, payment_amount).await
+    service.pay_order_debt(&id, payment_amount, Some(session.user_id)).await
 }
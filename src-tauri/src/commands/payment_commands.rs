@@ -0,0 +1,44 @@
+use tauri::State;
+use crate::auth::session::SessionStore;
+use crate::error::AppResult;
+use crate::dto::{CreatePaymentDto, PaymentDto};
+use crate::models::Role;
+use crate::services::PaymentService;
+use rust_decimal::Decimal;
+
+#[tauri::command]
+pub async fn create_payment(sessions: State<'_, SessionStore>, token: String, mut dto: CreatePaymentDto) -> AppResult<PaymentDto> {
+    let session = sessions.authorize(&token, Role::User)?;
+    // The payment is always attributed to whoever is logged in
+    dto.received_by = Some(session.user_id);
+    let service = PaymentService::new();
+    service.create_payment(dto).await
+}
+
+#[tauri::command]
+pub async fn get_payment_by_id(sessions: State<'_, SessionStore>, token: String, id: String) -> AppResult<Option<PaymentDto>> {
+    sessions.authorize(&token, Role::User)?;
+    let service = PaymentService::new();
+    service.get_payment_by_id(&id).await
+}
+
+#[tauri::command]
+pub async fn get_payments_by_order_id(sessions: State<'_, SessionStore>, token: String, order_id: String) -> AppResult<Vec<PaymentDto>> {
+    sessions.authorize(&token, Role::User)?;
+    let service = PaymentService::new();
+    service.get_payments_by_order_id(&order_id).await
+}
+
+#[tauri::command]
+pub async fn get_order_total_paid(sessions: State<'_, SessionStore>, token: String, order_id: String) -> AppResult<Decimal> {
+    sessions.authorize(&token, Role::User)?;
+    let service = PaymentService::new();
+    service.get_total_paid(&order_id).await
+}
+
+#[tauri::command]
+pub async fn delete_payment(sessions: State<'_, SessionStore>, token: String, id: String) -> AppResult<bool> {
+    sessions.authorize(&token, Role::Admin)?;
+    let service = PaymentService::new();
+    service.delete_payment(&id).await
+}
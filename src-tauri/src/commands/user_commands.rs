@@ -1,18 +1,132 @@
-use crate::dto::{LoginDto, CreateUserDto, LoginResponseDto};
-use crate::models::User;
+use tauri::State;
+use crate::auth::session::SessionStore;
+use crate::error::{AppError, AppResult};
+use crate::dto::{LoginDto, CreateUserDto, LoginResponseDto, InitialSetupDto, SessionInfoDto, UpdateUserDto, ChangePasswordDto, UserResponseDto};
+use crate::models::Role;
 use crate::services::UserService;
 
 #[tauri::command]
-pub async fn login(login_dto: LoginDto) -> Result<LoginResponseDto, String> {
-    UserService::login(login_dto).await
+pub async fn login(sessions: State<'_, SessionStore>, login_dto: LoginDto) -> AppResult<LoginResponseDto> {
+    let service = UserService::new();
+    let mut response = service.login(login_dto).await?;
+
+    if response.success {
+        let role = Role::parse(&response.role)
+            .ok_or_else(|| AppError::Database(format!("Invalid role: {}", response.role)))?;
+        response.token = sessions.create(response.id.clone(), response.login.clone(), role, response.must_change_password);
+    }
+
+    Ok(response)
+}
+
+#[tauri::command]
+pub fn logout(sessions: State<'_, SessionStore>, token: String) -> bool {
+    sessions.revoke(&token)
+}
+
+#[tauri::command]
+pub fn get_current_session(sessions: State<'_, SessionStore>, token: String) -> AppResult<SessionInfoDto> {
+    let session = sessions.authorize_password_change(&token)?;
+    Ok(SessionInfoDto {
+        user_id: session.user_id,
+        login: session.login,
+        role: session.role.as_str().to_string(),
+        must_change_password: session.must_change_password,
+    })
+}
+
+#[tauri::command]
+pub async fn create_user(sessions: State<'_, SessionStore>, token: String, create_user_dto: CreateUserDto) -> AppResult<UserResponseDto> {
+    sessions.authorize(&token, Role::Admin)?;
+    let service = UserService::new();
+    service.create_user(create_user_dto).await
+}
+
+#[tauri::command]
+pub async fn list_users(sessions: State<'_, SessionStore>, token: String) -> AppResult<Vec<UserResponseDto>> {
+    sessions.authorize(&token, Role::Admin)?;
+    let service = UserService::new();
+    service.list_users().await
+}
+
+#[tauri::command]
+pub async fn needs_initial_setup() -> AppResult<bool> {
+    let service = UserService::new();
+    service.needs_initial_setup().await
 }
 
 #[tauri::command]
-pub async fn create_user(create_user_dto: CreateUserDto) -> Result<User, String> {
-    UserService::create_user(create_user_dto).await
+pub async fn create_initial_admin(setup_dto: InitialSetupDto) -> AppResult<UserResponseDto> {
+    let service = UserService::new();
+    service.create_initial_admin(setup_dto).await
 }
 
 #[tauri::command]
-pub async fn list_users() -> Result<Vec<User>, String> {
-    UserService::list_users().await
+pub async fn update_user(sessions: State<'_, SessionStore>, token: String, id: String, dto: UpdateUserDto) -> AppResult<Option<UserResponseDto>> {
+    sessions.authorize(&token, Role::Admin)?;
+    let role_or_login_changed = dto.role.is_some() || dto.login.is_some();
+    let service = UserService::new();
+    let user = service.update_user(&id, dto).await?;
+
+    // Sessions keep the role and login they were opened with, so a new one
+    // only takes effect on the next login
+    if role_or_login_changed {
+        sessions.revoke_user(&id);
+    }
+
+    Ok(user)
+}
+
+#[tauri::command]
+pub async fn set_user_active(sessions: State<'_, SessionStore>, token: String, id: String, active: bool) -> AppResult<Option<UserResponseDto>> {
+    sessions.authorize(&token, Role::Admin)?;
+    let service = UserService::new();
+    let user = service.set_user_active(&id, active).await?;
+
+    if !active {
+        sessions.revoke_user(&id);
+    }
+
+    Ok(user)
+}
+
+#[tauri::command]
+pub async fn delete_user(sessions: State<'_, SessionStore>, token: String, id: String) -> AppResult<bool> {
+    let session = sessions.authorize(&token, Role::Admin)?;
+    if session.user_id == id {
+        return Err(AppError::Conflict("You cannot delete your own account".to_string()));
+    }
+
+    let service = UserService::new();
+    let deleted = service.delete_user(&id).await?;
+    sessions.revoke_user(&id);
+
+    Ok(deleted)
+}
+
+#[tauri::command]
+pub async fn change_password(sessions: State<'_, SessionStore>, token: String, dto: ChangePasswordDto) -> AppResult<bool> {
+    // Allowed while a default password still has to be changed
+    let session = sessions.authorize_password_change(&token)?;
+    let service = UserService::new();
+    let changed = service.change_password(&session.user_id, dto).await?;
+
+    if changed {
+        sessions.password_changed(&token);
+    }
+
+    Ok(changed)
+}
+
+#[tauri::command]
+pub async fn reset_user_password(sessions: State<'_, SessionStore>, token: String, id: String, new_password: String) -> AppResult<bool> {
+    let session = sessions.authorize(&token, Role::Admin)?;
+    let service = UserService::new();
+    let reset = service.reset_password(&id, &new_password).await?;
+
+    if session.user_id != id {
+        sessions.revoke_user(&id);
+    }
+
+    Ok(reset)
 }
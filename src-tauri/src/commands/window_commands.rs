@@ -1,13 +1,14 @@
 use crate::resize::resize::resize_window;
+use crate::error::{AppError, AppResult};
 use tauri::{command, Window};
 
 #[command]
-pub fn resize_current_window(window: Window, width: f64, height: f64) -> Result<(), String> {
-    resize_window(window, width, height)
+pub fn resize_current_window(window: Window, width: f64, height: f64) -> AppResult<()> {
+    resize_window(window, width, height).map_err(AppError::Failed)
 }
 
 #[command]
-pub fn get_screen_size(window: Window) -> Result<serde_json::Value, String> {
+pub fn get_screen_size(window: Window) -> AppResult<serde_json::Value> {
     match window.current_monitor() {
         Ok(Some(monitor)) => {
             let size = monitor.size();
@@ -17,7 +18,7 @@ pub fn get_screen_size(window: Window) -> Result<serde_json::Value, String> {
             });
             Ok(json)
         },
-        Ok(None) => Err("No monitor found".to_string()),
-        Err(e) => Err(format!("Failed to get monitor: {}", e))
+        Ok(None) => Err(AppError::Failed("No monitor found".to_string())),
+        Err(e) => Err(AppError::Failed(format!("Failed to get monitor: {}", e)))
     }
 }
@@ -1,191 +1,116 @@
-use sqlx::{Pool, Postgres, PgPool};
-use std::sync::OnceLock;
-use crate::config::{load_config};
+use sqlx::pool::PoolConnection;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::{Connection, PgConnection, Postgres, PgPool, Transaction};
+use std::sync::RwLock;
+use std::time::Duration;
+use crate::config::{load_config, DatabaseConfig};
+use crate::error::{AppError, AppResult};
+use crate::migrations::run_migrations;
 
-static DB_POOL: OnceLock<Pool<Postgres>> = OnceLock::new();
+/// None until a connection succeeds; replaced whole when the settings change
+static DB_POOL: RwLock<Option<PgPool>> = RwLock::new(None);
+/// Why the last connection attempt failed, for the "not connected" screen
+static CONNECTION_ERROR: RwLock<Option<String>> = RwLock::new(None);
 
-pub async fn init_database() -> Result<(), String> {
-    // Load configuration from AppData
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Connects with the saved settings. The app keeps running when this fails,
+/// so the settings can be fixed from the UI.
+pub async fn init_database() -> AppResult<()> {
     let config = load_config()?;
-    let database_url = config.database.to_connection_string();
+    connect(&config.database).await
+}
 
-    let pool = PgPool::connect(&database_url)
-        .await
-        .map_err(|e| format!("Failed to connect to database: {}", e))?;
-
-
-    // Create users table
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS users (
-            id TEXT PRIMARY KEY,
-            login TEXT NOT NULL UNIQUE,
-            password TEXT NOT NULL,
-            role TEXT NOT NULL CHECK (role IN ('admin', 'user')),
-            created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
-            updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
-        )
-        "#,
-    )
-    .execute(&pool)
-    .await
-    .map_err(|e| format!("Failed to create users table: {}", e))?;
-
-    // Create clients table
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS clients (
-            id TEXT PRIMARY KEY,
-            name TEXT NOT NULL,
-            nuit TEXT NOT NULL,
-            contact TEXT NOT NULL,
-            category TEXT NOT NULL,
-            observations TEXT NOT NULL,
-            debt DOUBLE PRECISION NOT NULL DEFAULT 0,
-            created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
-            updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
-        )
-        "#,
-    )
-    .execute(&pool)
-    .await
-    .map_err(|e| format!("Failed to create clients table: {}", e))?;
-
-    // Create orders table
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS orders (
-            id TEXT PRIMARY KEY,
-            name TEXT NOT NULL,
-            client_id TEXT NOT NULL REFERENCES clients(id) ON DELETE CASCADE,
-            order_number INTEGER NOT NULL,
-            client_requisition_number INTEGER NOT NULL,
-            due_date DATE,
-            discount DOUBLE PRECISION DEFAULT 0,
-            iva DOUBLE PRECISION DEFAULT 0,
-            subtotal DOUBLE PRECISION NOT NULL,
-            total DOUBLE PRECISION NOT NULL,
-            status TEXT NOT NULL DEFAULT 'order_received',
-            debt DOUBLE PRECISION NOT NULL DEFAULT 0,
-            created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
-            updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
-        )
-        "#,
-    )
-    .execute(&pool)
-    .await
-    .map_err(|e| format!("Failed to create orders table: {}", e))?;
-
-    // Create impressions table
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS impressions (
-            id TEXT PRIMARY KEY NOT NULL,
-            order_id TEXT NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
-            name TEXT NOT NULL,
-            size TEXT NOT NULL,
-            material TEXT NOT NULL,
-            description TEXT NOT NULL,
-            price DOUBLE PRECISION NOT NULL,
-            created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
-            updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
-        )
-        "#,
-    )
-    .execute(&pool)
-    .await
-    .map_err(|e| format!("Failed to create impressions table: {}", e))?;
-
-    // Create clothes table
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS clothes (
-            id TEXT PRIMARY KEY,
-            order_id TEXT NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
-            clothing_type TEXT NOT NULL,
-            custom_type TEXT,
-            unit_price DOUBLE PRECISION NOT NULL DEFAULT 0,
-            sizes TEXT NOT NULL, -- JSON string: {"S": 2, "M": 5, "L": 3}
-            color TEXT NOT NULL,
-            total_quantity INTEGER NOT NULL DEFAULT 0,
-            created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
-            updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
-        )
-        "#,
-    )
-    .execute(&pool)
-    .await
-    .map_err(|e| format!("Failed to create clothes table: {}", e))?;
-
-    // Create clothing_services table
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS clothing_services (
-            id TEXT PRIMARY KEY,
-            clothes_id TEXT NOT NULL REFERENCES clothes(id) ON DELETE CASCADE,
-            service_type TEXT NOT NULL,
-            location TEXT NOT NULL,
-            description TEXT,
-            unit_price DOUBLE PRECISION NOT NULL DEFAULT 0,
-            created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
-            updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
-        )
-        "#,
-    )
-    .execute(&pool)
-    .await
-    .map_err(|e| format!("Failed to create clothing_services table: {}", e))?;
-
-    // Insert default users
-    let admin_id = uuid::Uuid::new_v4().to_string();
-    let user_id = uuid::Uuid::new_v4().to_string();
-    let now = time::OffsetDateTime::now_utc();
-    
-    // Insert default admin user
-    sqlx::query(
-        r#"
-        INSERT INTO users (id, login, password, role, created_at, updated_at)
-        VALUES ($1, $2, $3, $4, $5, $6)
-        ON CONFLICT (login) DO NOTHING
-        "#,
-    )
-    .bind(&admin_id)
-    .bind("admin")
-    .bind("admin123")
-    .bind("admin")
-    .bind(now)
-    .bind(now)
-    .execute(&pool)
-    .await
-    .map_err(|e| format!("Failed to create admin user: {}", e))?;
-
-    // Insert default user
-    sqlx::query(
-        r#"
-        INSERT INTO users (id, login, password, role, created_at, updated_at)
-        VALUES ($1, $2, $3, $4, $5, $6)
-        ON CONFLICT (login) DO NOTHING
-        "#,
-    )
-    .bind(&user_id)
-    .bind("user")
-    .bind("user123")
-    .bind("user")
-    .bind(now)
-    .bind(now)
-    .execute(&pool)
-    .await
-    .map_err(|e| format!("Failed to create default user: {}", e))?;
+/// Opens a pool with `config`, brings the schema up to date and swaps it in.
+/// The previous pool is closed once its running queries finish.
+pub async fn connect(config: &DatabaseConfig) -> AppResult<()> {
+    let pool = match open_pool(config).await {
+        Ok(pool) => pool,
+        Err(e) => {
+            *CONNECTION_ERROR.write().unwrap_or_else(|e| e.into_inner()) = Some(e.to_string());
+            return Err(e);
+        }
+    };
 
-    DB_POOL
-        .set(pool)
-        .map_err(|_| "Failed to set database pool".to_string())?;
+    let previous = DB_POOL.write().unwrap_or_else(|e| e.into_inner()).replace(pool);
+    *CONNECTION_ERROR.write().unwrap_or_else(|e| e.into_inner()) = None;
+    log::info!("Connected to database {} on {}:{}", config.dbname, config.host, config.port);
 
+    if let Some(previous) = previous {
+        previous.close().await;
+    }
     Ok(())
 }
 
-pub fn get_db_pool() -> Result<&'static Pool<Postgres>, String> {
+async fn open_pool(config: &DatabaseConfig) -> AppResult<PgPool> {
+    let pool = PgPoolOptions::new()
+        .acquire_timeout(CONNECT_TIMEOUT)
+        .connect_with(config.connect_options())
+        .await
+        .map_err(|e| AppError::database("Failed to connect to database", e))?;
+
+    // Bring the schema up to date before anything touches it
+    let status = run_migrations(&pool).await?;
+    log::info!(
+        "Schema migrations: {} applied, {} pending, {} failed",
+        status.applied.len(),
+        status.pending.len(),
+        status.failed.len()
+    );
+
+    Ok(pool)
+}
+
+/// Tries `config` on a single connection, leaving the pool alone. Returns
+/// the server version.
+pub async fn test_connection(config: &DatabaseConfig) -> AppResult<String> {
+    let mut conn = tokio::time::timeout(CONNECT_TIMEOUT, PgConnection::connect_with(&config.connect_options()))
+        .await
+        .map_err(|_| AppError::Database("Timed out connecting to database".to_string()))?
+        .map_err(|e| AppError::database("Failed to connect to database", e))?;
+
+    let version: String = sqlx::query_scalar("SHOW server_version")
+        .fetch_one(&mut conn)
+        .await
+        .map_err(|e| AppError::database("Failed to query server version", e))?;
+
+    let _ = conn.close().await;
+    Ok(version)
+}
+
+pub fn is_connected() -> bool {
+    DB_POOL.read().unwrap_or_else(|e| e.into_inner()).is_some()
+}
+
+pub fn connection_error() -> Option<String> {
+    CONNECTION_ERROR.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+pub fn get_db_pool() -> AppResult<PgPool> {
     DB_POOL
-        .get()
-        .ok_or_else(|| "Database pool not initialized".to_string())
-}
\ No newline at end of file
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .ok_or_else(|| AppError::Database("Not connected to the database".to_string()))
+}
+
+pub async fn acquire_connection() -> AppResult<PoolConnection<Postgres>> {
+    get_db_pool()?
+        .acquire()
+        .await
+        .map_err(|e| AppError::database("Failed to acquire database connection", e))
+}
+
+/// Starts a transaction. Dropping it without calling `commit_transaction` rolls back.
+pub async fn begin_transaction() -> AppResult<Transaction<'static, Postgres>> {
+    get_db_pool()?
+        .begin()
+        .await
+        .map_err(|e| AppError::database("Failed to start transaction", e))
+}
+
+pub async fn commit_transaction(tx: Transaction<'static, Postgres>) -> AppResult<()> {
+    tx.commit()
+        .await
+        .map_err(|e| AppError::database("Failed to commit transaction", e))
+}
@@ -1,9 +1,13 @@
 use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
+use rust_decimal::Decimal;
+use crate::validation::{Validate, Validator};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateClientDto {
     pub name: String,
+    /// Empty or omitted for clients without a NUIT
+    #[serde(default)]
     pub nuit: String,
     pub contact: String,
     pub category: String,
@@ -13,6 +17,7 @@ pub struct CreateClientDto {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UpdateClientDto {
     pub name: Option<String>,
+    /// `Some("")` removes the client's NUIT
     pub nuit: Option<String>,
     pub contact: Option<String>,
     pub category: Option<String>,
@@ -27,13 +32,58 @@ pub struct ClientResponseDto {
     pub contact: String,
     pub category: String,
     pub observations: String,
-    pub debt: f64,
+    pub debt: Decimal,
     #[serde(with = "time::serde::rfc3339")]
     pub created_at: OffsetDateTime,
     #[serde(with = "time::serde::rfc3339")]
     pub updated_at: OffsetDateTime,
 }
 
+/// How the clients of a `DuplicateClientGroupDto` look alike
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DuplicateMatch {
+    /// Same name once case, accents, punctuation and the legal form (Lda, SA) are ignored
+    #[serde(rename = "name")]
+    Name,
+    #[serde(rename = "nuit")]
+    Nuit,
+    /// Same phone number or email address
+    #[serde(rename = "contact")]
+    Contact,
+}
+
+/// Clients that are probably the same, oldest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateClientGroupDto {
+    pub matched_by: DuplicateMatch,
+    /// The normalized value they share
+    pub key: String,
+    pub clients: Vec<ClientResponseDto>,
+}
+
+/// Moves everything of `source_ids` to `target_id` and deletes the sources
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeClientsDto {
+    pub target_id: String,
+    pub source_ids: Vec<String>,
+}
+
+/// A moved order whose requisition number was already taken by the target client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenumberedOrderDto {
+    pub order_id: String,
+    pub order_number: i32,
+    pub previous_requisition_number: i32,
+    pub requisition_number: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeClientsResultDto {
+    pub client: ClientResponseDto,
+    pub moved_orders: usize,
+    pub renumbered_orders: Vec<RenumberedOrderDto>,
+}
+
 impl From<crate::models::Client> for ClientResponseDto {
     fn from(client: crate::models::Client) -> Self {
         Self {
@@ -49,3 +99,33 @@ impl From<crate::models::Client> for ClientResponseDto {
         }
     }
 }
+
+impl Validate for CreateClientDto {
+    fn rules(&self, validator: Validator) -> Validator {
+        validator
+            .required("name", &self.name)
+            .nuit("nuit", &self.nuit)
+    }
+}
+
+/// `nuit` is left to the service: NUITs saved before they were validated
+/// must not block other edits, so only a changed NUIT is checked.
+impl Validate for UpdateClientDto {
+    fn rules(&self, validator: Validator) -> Validator {
+        validator
+            .optional(self.name.as_deref(), |v, name| v.required("name", name))
+    }
+}
+
+impl Validate for MergeClientsDto {
+    fn rules(&self, validator: Validator) -> Validator {
+        let mut unique_ids: Vec<&str> = self.source_ids.iter().map(String::as_str).collect();
+        unique_ids.sort_unstable();
+        unique_ids.dedup();
+        validator
+            .required("target_id", &self.target_id)
+            .check(!self.source_ids.is_empty(), "source_ids", "required", "source_ids is required")
+            .check(unique_ids.len() == self.source_ids.len(), "source_ids", "duplicate", "source_ids cannot repeat a client")
+            .check(!self.source_ids.contains(&self.target_id), "source_ids", "contains_target", "source_ids cannot contain target_id")
+    }
+}
@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
 use crate::models::{Clothes, ClothingService, ClothingType, ServiceType, ServiceLocation, SizesMap};
+use rust_decimal::Decimal;
+use crate::error::{AppError, AppResult};
+use crate::validation::{Validate, Validator};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClothesDto {
@@ -8,7 +11,7 @@ pub struct ClothesDto {
     pub order_id: String,
     pub clothing_type: ClothingType,
     pub custom_type: Option<String>,
-    pub unit_price: f64,
+    pub unit_price: Decimal,
     pub sizes: SizesMap,
     pub color: String,
     pub total_quantity: i32,
@@ -24,7 +27,7 @@ pub struct ClothingServiceDto {
     pub service_type: ServiceType,
     pub location: ServiceLocation,
     pub description: Option<String>,
-    pub unit_price: f64,
+    pub unit_price: Decimal,
     pub created_at: OffsetDateTime,
     pub updated_at: OffsetDateTime,
 }
@@ -34,7 +37,7 @@ pub struct CreateClothesDto {
     pub order_id: String,
     pub clothing_type: ClothingType,
     pub custom_type: Option<String>,
-    pub unit_price: f64,
+    pub unit_price: Decimal,
     pub sizes: SizesMap,
     pub color: String,
     pub services: Vec<CreateClothingServiceDto>,
@@ -45,14 +48,14 @@ pub struct CreateClothingServiceDto {
     pub service_type: ServiceType,
     pub location: ServiceLocation,
     pub description: Option<String>,
-    pub unit_price: f64,
+    pub unit_price: Decimal,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UpdateClothesDto {
     pub clothing_type: Option<ClothingType>,
     pub custom_type: Option<Option<String>>,
-    pub unit_price: Option<f64>,
+    pub unit_price: Option<Decimal>,
     pub sizes: Option<SizesMap>,
     pub color: Option<String>,
 }
@@ -62,11 +65,11 @@ pub struct UpdateClothingServiceDto {
     pub service_type: Option<ServiceType>,
     pub location: Option<ServiceLocation>,
     pub description: Option<Option<String>>,
-    pub unit_price: Option<f64>,
+    pub unit_price: Option<Decimal>,
 }
 
 impl ClothesDto {
-    pub fn from_model(clothes: Clothes, services: Vec<ClothingService>) -> Result<Self, String> {
+    pub fn from_model(clothes: Clothes, services: Vec<ClothingService>) -> AppResult<Self> {
         let clothing_type = match clothes.clothing_type.as_str() {
             "collared_tshirts" => ClothingType::CollaredTshirts,
             "tshirts_without_collar" => ClothingType::TshirtsWithoutCollar,
@@ -83,13 +86,13 @@ impl ClothesDto {
             "sheets" => ClothingType::Sheets,
             "aprons_kitchen" => ClothingType::ApronsKitchen,
             "other" => ClothingType::Other,
-            _ => return Err(format!("Invalid clothing type: {}", clothes.clothing_type)),
+            _ => return Err(AppError::invalid("clothing_type", "invalid", format!("Invalid clothing type: {}", clothes.clothing_type))),
         };
 
         let sizes: SizesMap = serde_json::from_str(&clothes.sizes)
-            .map_err(|e| format!("Failed to parse sizes JSON: {}", e))?;
+            .map_err(|e| AppError::Database(format!("Failed to parse sizes JSON: {}", e)))?;
 
-        let service_dtos: Result<Vec<ClothingServiceDto>, String> = services
+        let service_dtos: AppResult<Vec<ClothingServiceDto>> = services
             .into_iter()
             .map(ClothingServiceDto::from_model)
             .collect();
@@ -109,20 +112,20 @@ impl ClothesDto {
         })
     }
 
-    pub fn calculate_total_price(&self) -> f64 {
-        let services_total: f64 = self.services.iter().map(|s| s.unit_price).sum();
-        (self.unit_price + services_total) * self.total_quantity as f64
+    pub fn calculate_total_price(&self) -> Decimal {
+        let services_total: Decimal = self.services.iter().map(|s| s.unit_price).sum();
+        (self.unit_price + services_total) * Decimal::from(self.total_quantity)
     }
 }
 
 impl ClothingServiceDto {
-    pub fn from_model(service: ClothingService) -> Result<Self, String> {
+    pub fn from_model(service: ClothingService) -> AppResult<Self> {
         let service_type = match service.service_type.as_str() {
             "embroidery" => ServiceType::Embroidery,
             "stamping" => ServiceType::Stamping,
             "dtf" => ServiceType::Dtf,
             "transfer" => ServiceType::Transfer,
-            _ => return Err(format!("Invalid service type: {}", service.service_type)),
+            _ => return Err(AppError::invalid("service_type", "invalid", format!("Invalid service type: {}", service.service_type))),
         };
 
         let location = match service.location.as_str() {
@@ -138,7 +141,7 @@ impl ClothingServiceDto {
             "top" => ServiceLocation::Top,
             "bottom" => ServiceLocation::Bottom,
             "custom" => ServiceLocation::Custom,
-            _ => return Err(format!("Invalid service location: {}", service.location)),
+            _ => return Err(AppError::invalid("location", "invalid", format!("Invalid service location: {}", service.location))),
         };
 
         Ok(ClothingServiceDto {
@@ -179,9 +182,9 @@ impl CreateClothesDto {
         }
     }
 
-    pub fn sizes_to_json(&self) -> Result<String, String> {
+    pub fn sizes_to_json(&self) -> AppResult<String> {
         serde_json::to_string(&self.sizes)
-            .map_err(|e| format!("Failed to serialize sizes: {}", e))
+            .map_err(|e| AppError::Failed(format!("Failed to serialize sizes: {}", e)))
     }
 }
 
@@ -234,10 +237,10 @@ impl UpdateClothesDto {
         })
     }
 
-    pub fn sizes_to_json(&self) -> Result<Option<String>, String> {
+    pub fn sizes_to_json(&self) -> AppResult<Option<String>> {
         match &self.sizes {
             Some(sizes) => Ok(Some(serde_json::to_string(sizes)
-                .map_err(|e| format!("Failed to serialize sizes: {}", e))?)),
+                .map_err(|e| AppError::Failed(format!("Failed to serialize sizes: {}", e)))?)),
             None => Ok(None),
         }
     }
@@ -274,3 +277,72 @@ impl UpdateClothingServiceDto {
         })
     }
 }
+
+fn sizes_rules(validator: Validator, sizes: &SizesMap) -> Validator {
+    let total: u32 = sizes.values().sum();
+    validator.check(total > 0, "sizes", "empty", "sizes must have at least one piece")
+}
+
+fn custom_type_rules(validator: Validator, custom_type: Option<&str>) -> Validator {
+    let present = custom_type.is_some_and(|custom_type| !custom_type.trim().is_empty());
+    validator.check(present, "custom_type", "required", "custom_type is required for other clothing")
+}
+
+impl Validate for CreateClothesDto {
+    fn rules(&self, validator: Validator) -> Validator {
+        let mut validator = validator
+            .required("order_id", &self.order_id)
+            .non_negative("unit_price", self.unit_price)
+            .required("color", &self.color);
+        validator = sizes_rules(validator, &self.sizes);
+        if matches!(self.clothing_type, ClothingType::Other) {
+            validator = custom_type_rules(validator, self.custom_type.as_deref());
+        }
+        for (index, service) in self.services.iter().enumerate() {
+            validator = validator.nested(&format!("services[{}]", index), service);
+        }
+        validator
+    }
+}
+
+impl Validate for CreateClothingServiceDto {
+    fn rules(&self, validator: Validator) -> Validator {
+        let validator = validator.non_negative("unit_price", self.unit_price);
+        if matches!(self.location, ServiceLocation::Custom) {
+            let described = self.description.as_deref().is_some_and(|description| !description.trim().is_empty());
+            validator.check(described, "description", "required", "description is required for a custom location")
+        } else {
+            validator
+        }
+    }
+}
+
+impl Validate for UpdateClothesDto {
+    fn rules(&self, validator: Validator) -> Validator {
+        let mut validator = validator
+            .optional(self.unit_price.as_ref(), |v, unit_price| v.non_negative("unit_price", *unit_price))
+            .optional(self.color.as_deref(), |v, color| v.required("color", color))
+            .optional(self.sizes.as_ref(), sizes_rules);
+        // A custom type that is not sent stays as it is
+        if matches!(self.clothing_type, Some(ClothingType::Other)) {
+            if let Some(custom_type) = &self.custom_type {
+                validator = custom_type_rules(validator, custom_type.as_deref());
+            }
+        }
+        validator
+    }
+}
+
+impl Validate for UpdateClothingServiceDto {
+    fn rules(&self, validator: Validator) -> Validator {
+        let validator = validator
+            .optional(self.unit_price.as_ref(), |v, unit_price| v.non_negative("unit_price", *unit_price));
+        match (&self.location, &self.description) {
+            (Some(ServiceLocation::Custom), Some(description)) => {
+                let described = description.as_deref().is_some_and(|description| !description.trim().is_empty());
+                validator.check(described, "description", "required", "description is required for a custom location")
+            }
+            _ => validator,
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+use crate::config::SslMode;
+use crate::validation::{Validate, Validator};
+
+/// Connection settings as the settings screen edits them. The saved
+/// password is never sent back; saving with `password: None` keeps it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseSettingsDto {
+    pub host: String,
+    pub port: u16,
+    pub user: String,
+    #[serde(default)]
+    pub password: Option<String>,
+    pub dbname: String,
+    #[serde(default)]
+    pub ssl_mode: SslMode,
+    /// Whether a password is saved; ignored on input
+    #[serde(default)]
+    pub has_password: bool,
+    /// Set when the settings come from `--database-url` or
+    /// `EDITART_DATABASE_URL` and cannot be saved; ignored on input
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseStatusDto {
+    pub connected: bool,
+    /// Why the last connection attempt failed
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseTestResultDto {
+    pub server_version: String,
+}
+
+impl Validate for DatabaseSettingsDto {
+    fn rules(&self, validator: Validator) -> Validator {
+        validator
+            .required("host", &self.host)
+            .check(self.port != 0, "port", "required", "port is required")
+            .required("user", &self.user)
+            .required("dbname", &self.dbname)
+    }
+}
@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
 use crate::models::Impression;
+use rust_decimal::Decimal;
+use crate::error::AppResult;
+use crate::validation::{Validate, Validator};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImpressionDto {
@@ -9,7 +12,7 @@ pub struct ImpressionDto {
     pub size: String,
     pub material: String,
     pub description: String,
-    pub price: f64,
+    pub price: Decimal,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,7 +22,7 @@ pub struct CreateImpressionDto {
     pub size: String,
     pub material: String,
     pub description: String,
-    pub price: f64,
+    pub price: Decimal,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,11 +31,11 @@ pub struct UpdateImpressionDto {
     pub size: String,
     pub material: String,
     pub description: String,
-    pub price: f64,
+    pub price: Decimal,
 }
 
 impl ImpressionDto {
-    pub fn from_model(model: Impression) -> Result<Self, String> {
+    pub fn from_model(model: Impression) -> AppResult<Self> {
         Ok(Self {
             id: model.id,
             order_id: model.order_id,
@@ -43,4 +46,21 @@ impl ImpressionDto {
             price: model.price,
         })
     }
-}
\ No newline at end of file
+}
+
+impl Validate for CreateImpressionDto {
+    fn rules(&self, validator: Validator) -> Validator {
+        validator
+            .required("order_id", &self.order_id)
+            .required("name", &self.name)
+            .non_negative("price", self.price)
+    }
+}
+
+impl Validate for UpdateImpressionDto {
+    fn rules(&self, validator: Validator) -> Validator {
+        validator
+            .required("name", &self.name)
+            .non_negative("price", self.price)
+    }
+}
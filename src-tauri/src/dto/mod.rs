@@ -1,11 +1,17 @@
 pub mod client_dto;
 pub mod clothes_dto;
+pub mod database_dto;
 pub mod impression_dto;
 pub mod order_dto;
+pub mod page_dto;
+pub mod payment_dto;
 pub mod user_dto;
 
-pub use client_dto::{CreateClientDto, UpdateClientDto, ClientResponseDto};
+pub use client_dto::{CreateClientDto, UpdateClientDto, ClientResponseDto, DuplicateMatch, DuplicateClientGroupDto, MergeClientsDto, MergeClientsResultDto, RenumberedOrderDto};
 pub use clothes_dto::{ClothesDto, ClothingServiceDto, CreateClothesDto, CreateClothingServiceDto, UpdateClothesDto, UpdateClothingServiceDto};
+pub use database_dto::{DatabaseSettingsDto, DatabaseStatusDto, DatabaseTestResultDto};
 pub use impression_dto::{ImpressionDto, CreateImpressionDto, UpdateImpressionDto};
-pub use order_dto::{CreateOrderDto, UpdateOrderDto, OrderResponseDto};
-pub use user_dto::{LoginDto, CreateUserDto, LoginResponseDto};
+pub use order_dto::{CreateOrderDto, UpdateOrderDto, OrderResponseDto, OrderStatusChangeDto};
+pub use page_dto::{Page, PageRequest, ClientFilter, ClientSortField, OrderFilter, OrderSortField};
+pub use payment_dto::{CreatePaymentDto, PaymentDto};
+pub use user_dto::{LoginDto, CreateUserDto, LoginResponseDto, InitialSetupDto, SessionInfoDto, UpdateUserDto, ChangePasswordDto, UserResponseDto};
@@ -1,14 +1,18 @@
 use serde::{Deserialize, Serialize};
 use time::{Date, OffsetDateTime};
-use crate::models::OrderStatus;
+use crate::config::NumberingConfig;
+use crate::models::{OrderStatus, OrderStatusChange};
+use rust_decimal::Decimal;
+use crate::error::{AppError, AppResult};
+use crate::validation::{Validate, Validator};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateOrderDto {
     pub name: String,
     pub client_id: String,
     pub due_date: Date,
-    pub iva: f64,
-    pub discount: Option<f64>,
+    pub iva: Decimal,
+    pub discount: Option<Decimal>,
     pub status: Option<OrderStatus>,
 }
 
@@ -17,11 +21,11 @@ pub struct UpdateOrderDto {
     pub name: Option<String>,
     pub client_id: Option<String>,
     pub due_date: Option<Date>,
-    pub discount: Option<f64>,
-    pub iva: Option<f64>,
-    pub subtotal: Option<f64>,
-    pub total: Option<f64>,
+    pub discount: Option<Decimal>,
+    pub iva: Option<Decimal>,
     pub status: Option<OrderStatus>,
+    /// Stored in the status history when `status` changes
+    pub status_note: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,36 +36,44 @@ pub struct OrderResponseDto {
     pub client_name: String,
     pub client_contact: String,
     pub order_number: i32,
+    pub order_year: i32,
+    /// Number as shown to customers, formatted per `NumberingConfig`
+    pub order_code: String,
     pub client_requisition_number: i32,
     pub due_date: Date,
-    pub discount: f64,
-    pub iva: f64,
-    pub subtotal: f64,
-    pub total: f64,
+    pub discount: Decimal,
+    pub iva: Decimal,
+    pub subtotal: Decimal,
+    pub total: Decimal,
     pub status: OrderStatus,
-    pub debt: f64,
+    pub debt: Decimal,
     #[serde(with = "time::serde::rfc3339")]
     pub created_at: OffsetDateTime,
     #[serde(with = "time::serde::rfc3339")]
     pub updated_at: OffsetDateTime,
 }
 
-impl From<(crate::models::Order, String, String)> for OrderResponseDto {
-    fn from((order, client_name, client_contact): (crate::models::Order, String, String)) -> Self {
-        let status = match order.status.as_str() {
-            "in_production" => OrderStatus::InProduction,
-            "ready_for_delivery" => OrderStatus::ReadyForDelivery,
-            "delivered" => OrderStatus::Delivered,
-            _ => OrderStatus::OrderReceived,
-        };
-        
-        Self {
+/// A status as stored, which may only hold the values `OrderStatus` knows
+fn parse_status(status: &str) -> AppResult<OrderStatus> {
+    OrderStatus::parse(status)
+        .ok_or_else(|| AppError::invalid("status", "invalid", format!("Invalid order status: {}", status)))
+}
+
+impl TryFrom<(crate::models::Order, String, String)> for OrderResponseDto {
+    type Error = AppError;
+
+    fn try_from((order, client_name, client_contact): (crate::models::Order, String, String)) -> AppResult<Self> {
+        let status = parse_status(&order.status)?;
+
+        Ok(Self {
             id: order.id,
             name: order.name,
             client_id: order.client_id,
             client_name,
             client_contact,
             order_number: order.order_number,
+            order_year: order.order_year,
+            order_code: NumberingConfig::current().format_order_code(order.order_year, order.order_number),
             client_requisition_number: order.client_requisition_number,
             due_date: order.due_date,
             discount: order.discount,
@@ -72,6 +84,130 @@ impl From<(crate::models::Order, String, String)> for OrderResponseDto {
             debt: order.debt,
             created_at: order.created_at,
             updated_at: order.updated_at,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderStatusChangeDto {
+    pub id: String,
+    pub order_id: String,
+    pub from_status: Option<OrderStatus>,
+    pub to_status: OrderStatus,
+    pub changed_by: Option<String>,
+    pub changed_by_login: Option<String>,
+    pub note: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub changed_at: OffsetDateTime,
+    /// Time spent in `to_status`, until the next change or until now
+    pub duration_seconds: i64,
+}
+
+impl OrderStatusChangeDto {
+    /// Builds the DTOs for one order's history, which must be in chronological order.
+    pub fn from_history(history: Vec<(OrderStatusChange, Option<String>)>) -> AppResult<Vec<Self>> {
+        let now = OffsetDateTime::now_utc();
+        let next_changes: Vec<OffsetDateTime> = history.iter()
+            .skip(1)
+            .map(|(change, _)| change.changed_at)
+            .chain(std::iter::once(now))
+            .collect();
+
+        history.into_iter()
+            .zip(next_changes)
+            .map(|((change, changed_by_login), until)| Ok(Self {
+                id: change.id,
+                order_id: change.order_id,
+                from_status: change.from_status.as_deref().map(parse_status).transpose()?,
+                to_status: parse_status(&change.to_status)?,
+                changed_by: change.changed_by,
+                changed_by_login,
+                note: change.note,
+                changed_at: change.changed_at,
+                duration_seconds: (until - change.changed_at).whole_seconds().max(0),
+            }))
+            .collect()
+    }
+}
+
+impl Validate for CreateOrderDto {
+    fn rules(&self, validator: Validator) -> Validator {
+        validator
+            .required("name", &self.name)
+            .required("client_id", &self.client_id)
+            .not_in_past("due_date", self.due_date)
+            .percentage("iva", self.iva)
+            .optional(self.discount.as_ref(), |v, discount| v.non_negative("discount", *discount))
+            .optional(self.status.as_ref(), |v, status| {
+                v.check(status.is_initial(), "status", "invalid_initial_status", "A new order must start as order_received or on_hold")
+            })
+    }
+}
+
+/// `due_date` is left to the service: an order already past its due date
+/// must stay editable, so only a changed date is checked.
+impl Validate for UpdateOrderDto {
+    fn rules(&self, validator: Validator) -> Validator {
+        validator
+            .optional(self.name.as_deref(), |v, name| v.required("name", name))
+            .optional(self.client_id.as_deref(), |v, client_id| v.required("client_id", client_id))
+            .optional(self.iva.as_ref(), |v, iva| v.percentage("iva", *iva))
+            .optional(self.discount.as_ref(), |v, discount| v.non_negative("discount", *discount))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Order;
+
+    fn order(status: &str) -> Order {
+        let now = OffsetDateTime::now_utc();
+        Order {
+            id: "order-1".to_string(),
+            name: "Banners".to_string(),
+            client_id: "client-1".to_string(),
+            order_number: 1,
+            order_year: 2025,
+            client_requisition_number: 1,
+            due_date: now.date(),
+            discount: Decimal::ZERO,
+            iva: Decimal::ZERO,
+            subtotal: Decimal::ZERO,
+            total: Decimal::ZERO,
+            status: status.to_string(),
+            debt: Decimal::ZERO,
+            created_at: now,
+            updated_at: now,
         }
     }
+
+    fn change(from_status: Option<&str>, to_status: &str) -> (OrderStatusChange, Option<String>) {
+        let change = OrderStatusChange {
+            id: "change-1".to_string(),
+            order_id: "order-1".to_string(),
+            from_status: from_status.map(str::to_string),
+            to_status: to_status.to_string(),
+            changed_by: None,
+            note: None,
+            changed_at: OffsetDateTime::now_utc(),
+        };
+        (change, None)
+    }
+
+    #[test]
+    fn unknown_stored_statuses_are_errors() {
+        let error = OrderResponseDto::try_from((order("shipped"), String::new(), String::new())).unwrap_err();
+        assert_eq!(error.code(), "validation");
+
+        assert!(OrderStatusChangeDto::from_history(vec![change(None, "shipped")]).is_err());
+        assert!(OrderStatusChangeDto::from_history(vec![change(Some("shipped"), "delivered")]).is_err());
+    }
+
+    #[test]
+    fn history_keeps_known_statuses() {
+        let history = OrderStatusChangeDto::from_history(vec![change(Some("in_production"), "delivered")]).unwrap();
+        assert_eq!(history[0].from_status, Some(OrderStatus::InProduction));
+        assert_eq!(history[0].to_status, OrderStatus::Delivered);
+    }
 }
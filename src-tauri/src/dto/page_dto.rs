@@ -0,0 +1,145 @@
+use serde::{Deserialize, Serialize};
+use time::Date;
+use crate::models::OrderStatus;
+
+pub const DEFAULT_PAGE_LIMIT: i64 = 25;
+pub const MAX_PAGE_LIMIT: i64 = 200;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum SortDirection {
+    #[serde(rename = "asc")]
+    Asc,
+    #[default]
+    #[serde(rename = "desc")]
+    Desc,
+}
+
+impl SortDirection {
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            SortDirection::Asc => "ASC",
+            SortDirection::Desc => "DESC",
+        }
+    }
+}
+
+/// Offset/limit page with an optional sort. `S` is the list's sort field
+/// enum, so only whitelisted columns ever reach the ORDER BY.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageRequest<S> {
+    #[serde(default)]
+    pub offset: i64,
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    pub sort_by: Option<S>,
+    #[serde(default)]
+    pub direction: SortDirection,
+}
+
+fn default_limit() -> i64 {
+    DEFAULT_PAGE_LIMIT
+}
+
+impl<S> PageRequest<S> {
+    pub fn offset(&self) -> i64 {
+        self.offset.max(0)
+    }
+
+    pub fn limit(&self) -> i64 {
+        self.limit.clamp(1, MAX_PAGE_LIMIT)
+    }
+}
+
+impl<S> Default for PageRequest<S> {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            limit: DEFAULT_PAGE_LIMIT,
+            sort_by: None,
+            direction: SortDirection::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Rows matching the filter, ignoring offset and limit
+    pub total: i64,
+    pub offset: i64,
+    pub limit: i64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum ClientSortField {
+    #[serde(rename = "name")]
+    Name,
+    #[serde(rename = "created_at")]
+    CreatedAt,
+    #[serde(rename = "debt")]
+    Debt,
+}
+
+impl ClientSortField {
+    pub fn column(&self) -> &'static str {
+        match self {
+            ClientSortField::Name => "name",
+            ClientSortField::CreatedAt => "created_at",
+            ClientSortField::Debt => "debt",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClientFilter {
+    /// Matches name, NUIT or contact
+    pub search: Option<String>,
+    pub category: Option<String>,
+    pub has_debt: Option<bool>,
+    /// `Some(false)` lists the clients registered without a NUIT
+    pub has_nuit: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum OrderSortField {
+    #[serde(rename = "created_at")]
+    CreatedAt,
+    #[serde(rename = "due_date")]
+    DueDate,
+    #[serde(rename = "order_number")]
+    OrderNumber,
+    #[serde(rename = "client_name")]
+    ClientName,
+    #[serde(rename = "status")]
+    Status,
+    #[serde(rename = "total")]
+    Total,
+    #[serde(rename = "debt")]
+    Debt,
+}
+
+impl OrderSortField {
+    /// Numbers can restart every year, so they sort by year first
+    pub fn columns(&self) -> &'static [&'static str] {
+        match self {
+            OrderSortField::CreatedAt => &["o.created_at"],
+            OrderSortField::DueDate => &["o.due_date"],
+            OrderSortField::OrderNumber => &["o.order_year", "o.order_number"],
+            OrderSortField::ClientName => &["c.name"],
+            OrderSortField::Status => &["o.status"],
+            OrderSortField::Total => &["o.total"],
+            OrderSortField::Debt => &["o.debt"],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OrderFilter {
+    pub status: Option<OrderStatus>,
+    pub client_id: Option<String>,
+    pub due_from: Option<Date>,
+    pub due_to: Option<Date>,
+    pub has_debt: Option<bool>,
+    /// Matches the order name, the client name or the order number
+    pub search: Option<String>,
+}
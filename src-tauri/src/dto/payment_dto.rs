@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+use time::{Date, OffsetDateTime};
+use crate::models::{Payment, PaymentMethod};
+use rust_decimal::Decimal;
+use crate::error::{AppError, AppResult};
+use crate::validation::{Validate, Validator};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreatePaymentDto {
+    pub order_id: String,
+    pub amount: Decimal,
+    pub payment_date: Option<Date>,
+    #[serde(default)]
+    pub method: PaymentMethod,
+    pub reference: Option<String>,
+    pub received_by: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentDto {
+    pub id: String,
+    pub order_id: String,
+    pub amount: Decimal,
+    pub payment_date: Date,
+    pub method: PaymentMethod,
+    pub reference: Option<String>,
+    pub received_by: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub updated_at: OffsetDateTime,
+}
+
+impl PaymentDto {
+    pub fn from_model(payment: Payment) -> AppResult<Self> {
+        let method = match payment.method.as_str() {
+            "cash" => PaymentMethod::Cash,
+            "bank_transfer" => PaymentMethod::BankTransfer,
+            "mpesa" => PaymentMethod::Mpesa,
+            "emola" => PaymentMethod::Emola,
+            "card" => PaymentMethod::Card,
+            "cheque" => PaymentMethod::Cheque,
+            "other" => PaymentMethod::Other,
+            _ => return Err(AppError::invalid("method", "invalid", format!("Invalid payment method: {}", payment.method))),
+        };
+
+        Ok(PaymentDto {
+            id: payment.id,
+            order_id: payment.order_id,
+            amount: payment.amount,
+            payment_date: payment.payment_date,
+            method,
+            reference: payment.reference,
+            received_by: payment.received_by,
+            created_at: payment.created_at,
+            updated_at: payment.updated_at,
+        })
+    }
+}
+
+impl CreatePaymentDto {
+    pub fn to_method_string(&self) -> String {
+        match self.method {
+            PaymentMethod::Cash => "cash".to_string(),
+            PaymentMethod::BankTransfer => "bank_transfer".to_string(),
+            PaymentMethod::Mpesa => "mpesa".to_string(),
+            PaymentMethod::Emola => "emola".to_string(),
+            PaymentMethod::Card => "card".to_string(),
+            PaymentMethod::Cheque => "cheque".to_string(),
+            PaymentMethod::Other => "other".to_string(),
+        }
+    }
+}
+
+impl Validate for CreatePaymentDto {
+    fn rules(&self, validator: Validator) -> Validator {
+        validator
+            .required("order_id", &self.order_id)
+            .positive("amount", self.amount)
+    }
+}
@@ -1,4 +1,8 @@
 use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use crate::models::User;
+use crate::auth::password::MIN_PASSWORD_LENGTH;
+use crate::validation::{Validate, Validator};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LoginDto {
@@ -20,4 +24,97 @@ pub struct LoginResponseDto {
     pub role: String,
     pub success: bool,
     pub message: String,
+    /// Session token to pass to every other command; empty when login fails
+    pub token: String,
+    /// The account still has a shipped default password, which must be
+    /// changed before anything else is allowed
+    pub must_change_password: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InitialSetupDto {
+    pub login: String,
+    pub password: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionInfoDto {
+    pub user_id: String,
+    pub login: String,
+    pub role: String,
+    pub must_change_password: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateUserDto {
+    pub login: Option<String>,
+    pub role: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChangePasswordDto {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+/// User as returned to the frontend; never carries the password hash
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserResponseDto {
+    pub id: String,
+    pub login: String,
+    pub role: String,
+    pub active: bool,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub updated_at: OffsetDateTime,
+}
+
+impl From<User> for UserResponseDto {
+    fn from(user: User) -> Self {
+        Self {
+            id: user.id,
+            login: user.login,
+            role: user.role,
+            active: user.active,
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+        }
+    }
+}
+
+/// Same values as `Role`
+const ROLES: [&str; 2] = ["admin", "user"];
+
+impl Validate for CreateUserDto {
+    fn rules(&self, validator: Validator) -> Validator {
+        validator
+            .required("login", &self.login)
+            .min_chars("password", &self.password, MIN_PASSWORD_LENGTH)
+            .one_of("role", &self.role, &ROLES)
+    }
+}
+
+impl Validate for InitialSetupDto {
+    fn rules(&self, validator: Validator) -> Validator {
+        validator
+            .required("login", &self.login)
+            .min_chars("password", &self.password, MIN_PASSWORD_LENGTH)
+    }
+}
+
+impl Validate for UpdateUserDto {
+    fn rules(&self, validator: Validator) -> Validator {
+        validator
+            .optional(self.login.as_deref(), |v, login| v.required("login", login))
+            .optional(self.role.as_deref(), |v, role| v.one_of("role", role, &ROLES))
+    }
+}
+
+impl Validate for ChangePasswordDto {
+    fn rules(&self, validator: Validator) -> Validator {
+        validator
+            .required("current_password", &self.current_password)
+            .min_chars("new_password", &self.new_password, MIN_PASSWORD_LENGTH)
+    }
 }
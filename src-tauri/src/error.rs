@@ -0,0 +1,163 @@
+use crate::auth::session::AuthError;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use serde_json::{json, Value};
+use std::fmt;
+
+/// One invalid field of a request. `code` is stable (e.g. `"required"`,
+/// `"negative"`) so the frontend can translate it; `message` is English.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub code: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, code: &str, message: impl Into<String>) -> Self {
+        FieldError {
+            field: field.to_string(),
+            code: code.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Error returned by repositories, services and commands. Serialized as
+/// `{ "code": ..., "message": ..., "details": ... }`, where `code` never
+/// changes and `details` depends on it:
+/// - `not_found`: `{ "entity", "id" }`
+/// - `validation`: `{ "fields": [FieldError] }`
+/// - `unauthorized`: `{ "reason" }`, `"session_expired"` or `"invalid_session"`
+/// - anything else: `null`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AppError {
+    NotFound { entity: &'static str, id: String },
+    Validation(Vec<FieldError>),
+    /// The request clashes with existing data (duplicates, rows still in use)
+    Conflict(String),
+    /// No session, or it expired. `reason` tells the two apart.
+    Unauthorized { reason: &'static str, message: String },
+    /// Logged in, but the role does not allow it
+    Forbidden(String),
+    Database(String),
+    Io(String),
+    Config(String),
+    /// Anything else, e.g. a backup that cannot be read
+    Failed(String),
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+impl AppError {
+    pub fn not_found(entity: &'static str, id: &str) -> Self {
+        AppError::NotFound { entity, id: id.to_string() }
+    }
+
+    /// A single invalid field
+    pub fn invalid(field: &str, code: &str, message: impl Into<String>) -> Self {
+        AppError::Validation(vec![FieldError::new(field, code, message)])
+    }
+
+    /// A failed query, with what was being done. Unique and foreign key
+    /// violations become `Conflict`, since they come from what was asked.
+    pub fn database(context: &str, error: sqlx::Error) -> Self {
+        if let sqlx::Error::Database(db_error) = &error {
+            if db_error.is_unique_violation() || db_error.is_foreign_key_violation() {
+                return AppError::Conflict(format!("{}: {}", context, db_error.message()));
+            }
+        }
+        AppError::Database(format!("{}: {}", context, error))
+    }
+
+    pub fn io(context: &str, error: std::io::Error) -> Self {
+        AppError::Io(format!("{}: {}", context, error))
+    }
+
+    /// The same error with its message rewritten, e.g. to add what happened
+    /// to the data. `NotFound` and `Validation` are left as they are.
+    pub fn map_message(self, f: impl FnOnce(&str) -> String) -> Self {
+        match self {
+            AppError::Conflict(message) => AppError::Conflict(f(&message)),
+            AppError::Unauthorized { reason, message } => AppError::Unauthorized { reason, message: f(&message) },
+            AppError::Forbidden(message) => AppError::Forbidden(f(&message)),
+            AppError::Database(message) => AppError::Database(f(&message)),
+            AppError::Io(message) => AppError::Io(f(&message)),
+            AppError::Config(message) => AppError::Config(f(&message)),
+            AppError::Failed(message) => AppError::Failed(f(&message)),
+            error => error,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NotFound { .. } => "not_found",
+            AppError::Validation(_) => "validation",
+            AppError::Conflict(_) => "conflict",
+            AppError::Unauthorized { .. } => "unauthorized",
+            AppError::Forbidden(_) => "forbidden",
+            AppError::Database(_) => "database",
+            AppError::Io(_) => "io",
+            AppError::Config(_) => "config",
+            AppError::Failed(_) => "failed",
+        }
+    }
+
+    fn details(&self) -> Value {
+        match self {
+            AppError::NotFound { entity, id } => json!({ "entity": entity, "id": id }),
+            AppError::Validation(fields) => json!({ "fields": fields }),
+            AppError::Unauthorized { reason, .. } => json!({ "reason": reason }),
+            _ => Value::Null,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::NotFound { entity, id } => write!(f, "{} {} not found", capitalize(entity), id),
+            AppError::Validation(fields) => {
+                let messages: Vec<&str> = fields.iter().map(|field| field.message.as_str()).collect();
+                write!(f, "{}", messages.join("; "))
+            }
+            AppError::Conflict(message)
+            | AppError::Unauthorized { message, .. }
+            | AppError::Forbidden(message)
+            | AppError::Database(message)
+            | AppError::Io(message)
+            | AppError::Config(message)
+            | AppError::Failed(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut error = serializer.serialize_struct("AppError", 3)?;
+        error.serialize_field("code", self.code())?;
+        error.serialize_field("message", &self.to_string())?;
+        error.serialize_field("details", &self.details())?;
+        error.end()
+    }
+}
+
+impl From<AuthError> for AppError {
+    fn from(error: AuthError) -> Self {
+        let message = error.to_string();
+        match error {
+            AuthError::InvalidSession => AppError::Unauthorized { reason: "invalid_session", message },
+            AuthError::SessionExpired => AppError::Unauthorized { reason: "session_expired", message },
+            AuthError::Forbidden { .. } | AuthError::PasswordChangeRequired => AppError::Forbidden(message),
+        }
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
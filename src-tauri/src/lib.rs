@@ -1,48 +1,82 @@
 mod config;
 mod database;
+mod error;
+mod migrations;
+mod money;
 mod models;
+mod nuit;
+mod local_time;
 mod dto;
 mod repositories;
 mod services;
 mod commands;
 mod resize;
 mod backup; 
+mod auth;
+mod secrets;
+mod validation;
+#[cfg(test)]
+mod test_support;
 
 use commands::*;
+use auth::session::SessionStore;
+use config::ConfigOverrides;
 use database::init_database;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // Before the logger, so RUST_LOG can come from .env
+    let override_errors = ConfigOverrides::init();
+
     // Inicializar logger
     env_logger::init();
+    // Before Tauri spawns any thread
+    local_time::init();
+    for error in override_errors {
+        log::error!("Ignoring command-line option: {}", error);
+    }
+    if let Ok(config_path) = config::get_config_path() {
+        log::info!("Using config file {:?}", config_path);
+    }
     
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
-        .setup(|_app| {
-            tauri::async_runtime::block_on(async {
-                if let Err(e) = init_database().await {
-                    eprintln!("Failed to initialize database: {}", e);
-                    std::process::exit(1);
-                }
-            });
+        .manage(SessionStore::default())
+        .setup(|app| {
+            // Without a database the app still opens, on the connection settings
+            if let Err(e) = tauri::async_runtime::block_on(init_database()) {
+                log::error!("Starting without a database connection: {}", e);
+            }
+            backup::scheduler::start(app.handle().clone());
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             create_client,
             get_client_by_id,
+            find_client_by_nuit,
             get_clients_by_name,
             list_clients,
+            list_clients_page,
             update_client,
             delete_client,
+            find_duplicate_clients,
+            merge_clients,
             update_client_debt,
             create_order,
             get_order_by_id,
             get_orders_by_client_id,
             get_orders_by_date_range,
             list_orders,
+            list_orders_page,
             update_order,
+            get_order_status_history,
             delete_order,
             pay_order_debt,
+            create_payment,
+            get_payment_by_id,
+            get_payments_by_order_id,
+            get_order_total_paid,
+            delete_payment,
             create_clothes,
             get_clothes_by_id,
             get_clothes_by_order_id,
@@ -59,14 +93,41 @@ pub fn run() {
             update_impression,
             delete_impression,
             login,
+            logout,
+            get_current_session,
             create_user,
             list_users,
+            update_user,
+            set_user_active,
+            delete_user,
+            change_password,
+            reset_user_password,
+            needs_initial_setup,
+            create_initial_admin,
             resize_current_window,
             get_screen_size,
             create_database_backup,
             restore_database_backup,
-            get_backup_info
+            preview_database_restore,
+            get_backup_info,
+            list_backups,
+            delete_backup,
+            get_backup_schedule,
+            set_backup_schedule,
+            create_pg_dump_backup,
+            get_pg_tools_config,
+            check_pg_tools,
+            set_pg_tools_config,
+            get_database_status,
+            get_database_settings,
+            test_database_settings,
+            save_database_settings
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                tauri::async_runtime::block_on(backup::scheduler::run_on_close(app));
+            }
+        });
 }
\ No newline at end of file
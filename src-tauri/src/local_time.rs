@@ -0,0 +1,29 @@
+//! The machine's UTC offset, read once at startup. On Unix `time` refuses to
+//! read it once the process has more than one thread, so it has to be
+//! captured before Tauri starts its runtime.
+
+use std::sync::OnceLock;
+use time::{Date, OffsetDateTime, UtcOffset};
+
+static LOCAL_OFFSET: OnceLock<UtcOffset> = OnceLock::new();
+
+/// Call from `run` while the process is still single-threaded.
+pub fn init() {
+    let offset = UtcOffset::current_local_offset().unwrap_or_else(|e| {
+        log::warn!("Could not read the local UTC offset ({}), using UTC for dates and backup schedules", e);
+        UtcOffset::UTC
+    });
+    let _ = LOCAL_OFFSET.set(offset);
+}
+
+/// The offset captured by `init`, or UTC if it never ran
+pub fn local_offset() -> UtcOffset {
+    *LOCAL_OFFSET.get_or_init(|| {
+        log::warn!("Local UTC offset was not captured at startup, using UTC");
+        UtcOffset::UTC
+    })
+}
+
+pub fn today() -> Date {
+    OffsetDateTime::now_utc().to_offset(local_offset()).date()
+}
@@ -0,0 +1,408 @@
+use crate::error::{AppError, AppResult};
+use sha2::{Digest, Sha256};
+use sqlx::{PgConnection, PgPool, Row};
+use time::OffsetDateTime;
+
+/// Arbitrary key for the advisory lock that keeps two workstations from
+/// migrating the same database at the same time.
+const MIGRATION_LOCK_KEY: i64 = 0x6564_6974_6172_7401;
+
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+/// Every up-migration known to this binary, in order. Never edit an entry
+/// that has shipped; add a new one instead. Statements other code also runs
+/// live in `migrations/shared` and are spliced back in with `concat!`, so the
+/// SQL (and its checksum) stays byte for byte what shipped.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        sql: include_str!("../migrations/0001_initial_schema.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "payments",
+        sql: concat!(
+            include_str!("../migrations/0002_payments.sql"),
+            include_str!("../migrations/shared/opening_balances.sql"),
+        ),
+    },
+    Migration {
+        version: 3,
+        name: "user_active",
+        sql: include_str!("../migrations/0003_user_active.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "order_numbering",
+        sql: concat!(
+            include_str!("../migrations/0004_order_numbering.sql"),
+            include_str!("../migrations/shared/renumber_duplicate_requisitions.sql"),
+            include_str!("../migrations/parts/0004_indexes.sql"),
+        ),
+    },
+    Migration {
+        version: 5,
+        name: "money_numeric",
+        sql: include_str!("../migrations/0005_money_numeric.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "order_status_history",
+        sql: include_str!("../migrations/0006_order_status_history.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "client_nuit_unique",
+        sql: concat!(
+            include_str!("../migrations/0007_client_nuit_unique.sql"),
+            include_str!("../migrations/shared/clear_duplicate_nuits.sql"),
+            include_str!("../migrations/parts/0007_index.sql"),
+        ),
+    },
+    Migration {
+        version: 8,
+        name: "order_number_scope",
+        sql: include_str!("../migrations/0008_order_number_scope.sql"),
+    },
+];
+
+/// Turns what was paid before the payments ledger existed into one opening
+/// payment per order. Part of migration 0002 and also run by
+/// `PaymentRepository::backfill_opening_balances`.
+pub const OPENING_BALANCES_SQL: &str = include_str!("../migrations/shared/opening_balances.sql");
+
+/// Gives orders that share a client's requisition number the next free ones.
+/// Part of migration 0004; also run when restoring older backups.
+pub const RENUMBER_DUPLICATE_REQUISITIONS_SQL: &str = include_str!("../migrations/shared/renumber_duplicate_requisitions.sql");
+
+/// Clears a NUIT on every client but the oldest one holding it. Part of
+/// migration 0007; also run when restoring older backups.
+pub const CLEAR_DUPLICATE_NUITS_SQL: &str = include_str!("../migrations/shared/clear_duplicate_nuits.sql");
+
+impl Migration {
+    fn checksum(&self) -> String {
+        format!("{:x}", Sha256::digest(self.sql.as_bytes()))
+    }
+}
+
+#[derive(Debug)]
+struct AppliedMigration {
+    version: i64,
+    name: String,
+    checksum: String,
+    success: bool,
+    error: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct MigrationStatus {
+    pub applied: Vec<i64>,
+    pub pending: Vec<i64>,
+    pub failed: Vec<(i64, String)>,
+}
+
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+async fn ensure_migrations_table(conn: &mut PgConnection) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_migrations (
+            version BIGINT PRIMARY KEY,
+            name TEXT NOT NULL,
+            checksum TEXT NOT NULL,
+            success BOOLEAN NOT NULL,
+            error TEXT,
+            applied_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )
+        "#,
+    )
+    .execute(conn)
+    .await
+    .map_err(|e| AppError::database("Failed to create schema_migrations table", e))?;
+
+    Ok(())
+}
+
+async fn fetch_applied(conn: &mut PgConnection) -> AppResult<Vec<AppliedMigration>> {
+    let rows = sqlx::query("SELECT version, name, checksum, success, error FROM schema_migrations ORDER BY version")
+        .fetch_all(conn)
+        .await
+        .map_err(|e| AppError::database("Failed to read schema_migrations", e))?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| AppliedMigration {
+            version: row.get("version"),
+            name: row.get("name"),
+            checksum: row.get("checksum"),
+            success: row.get("success"),
+            error: row.get("error"),
+        })
+        .collect())
+}
+
+fn build_status(applied: &[AppliedMigration]) -> MigrationStatus {
+    let succeeded = |version: i64| applied.iter().any(|a| a.version == version && a.success);
+
+    MigrationStatus {
+        applied: applied.iter().filter(|a| a.success).map(|a| a.version).collect(),
+        pending: MIGRATIONS.iter().filter(|m| !succeeded(m.version)).map(|m| m.version).collect(),
+        failed: applied
+            .iter()
+            .filter(|a| !a.success)
+            .map(|a| (a.version, a.error.clone().unwrap_or_default()))
+            .collect(),
+    }
+}
+
+/// Refuses databases written by a newer binary and databases whose applied
+/// migrations no longer match the SQL shipped with this one.
+fn verify_applied(applied: &[AppliedMigration]) -> AppResult<()> {
+    let latest = latest_version();
+
+    if let Some(newer) = applied.iter().filter(|a| a.success).map(|a| a.version).find(|v| *v > latest) {
+        return Err(AppError::Database(format!(
+            "Database schema version {} is newer than this application supports ({}). Please update the application.",
+            newer, latest
+        )));
+    }
+
+    for entry in applied.iter().filter(|a| a.success) {
+        if let Some(migration) = MIGRATIONS.iter().find(|m| m.version == entry.version) {
+            if migration.checksum() != entry.checksum {
+                return Err(AppError::Database(format!(
+                    "Migration {:04}_{} was modified after being applied (checksum mismatch)",
+                    entry.version, entry.name
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn apply(conn: &mut PgConnection, migration: &Migration) -> AppResult<()> {
+    let mut tx = sqlx::Connection::begin(&mut *conn)
+        .await
+        .map_err(|e| AppError::database("Failed to start migration transaction", e))?;
+
+    // Without bind arguments this is a simple query, so a migration can hold
+    // several statements. Unlike `raw_sql` its future is Send, which lets
+    // commands run migrations.
+    let result = sqlx::Executor::execute(&mut *tx, migration.sql).await;
+
+    match result {
+        Ok(_) => {
+            sqlx::query(
+                r#"
+                INSERT INTO schema_migrations (version, name, checksum, success, error, applied_at)
+                VALUES ($1, $2, $3, TRUE, NULL, $4)
+                ON CONFLICT (version) DO UPDATE
+                SET name = EXCLUDED.name, checksum = EXCLUDED.checksum, success = TRUE, error = NULL, applied_at = EXCLUDED.applied_at
+                "#,
+            )
+            .bind(migration.version)
+            .bind(migration.name)
+            .bind(migration.checksum())
+            .bind(OffsetDateTime::now_utc())
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::database(&format!("Failed to record migration {}", migration.version), e))?;
+
+            tx.commit()
+                .await
+                .map_err(|e| AppError::database(&format!("Failed to commit migration {}", migration.version), e))?;
+
+            Ok(())
+        }
+        Err(e) => {
+            let _ = tx.rollback().await;
+            let error = e.to_string();
+
+            // Record the failure outside the rolled back transaction so the next start can report it
+            sqlx::query(
+                r#"
+                INSERT INTO schema_migrations (version, name, checksum, success, error, applied_at)
+                VALUES ($1, $2, $3, FALSE, $4, $5)
+                ON CONFLICT (version) DO UPDATE
+                SET checksum = EXCLUDED.checksum, success = FALSE, error = EXCLUDED.error, applied_at = EXCLUDED.applied_at
+                "#,
+            )
+            .bind(migration.version)
+            .bind(migration.name)
+            .bind(migration.checksum())
+            .bind(&error)
+            .bind(OffsetDateTime::now_utc())
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| AppError::database(&format!("Failed to record failed migration {}", migration.version), e))?;
+
+            Err(AppError::Database(format!("Migration {:04}_{} failed: {}", migration.version, migration.name, error)))
+        }
+    }
+}
+
+async fn run_locked(conn: &mut PgConnection) -> AppResult<MigrationStatus> {
+    ensure_migrations_table(conn).await?;
+
+    let applied = fetch_applied(conn).await?;
+    verify_applied(&applied)?;
+
+    let status = build_status(&applied);
+    for (version, error) in &status.failed {
+        log::warn!("Migration {} failed previously ({}), retrying", version, error);
+    }
+
+    if status.pending.is_empty() {
+        log::info!("Database schema is up to date (version {})", latest_version());
+        return Ok(status);
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| status.pending.contains(&m.version)) {
+        log::info!("Applying migration {:04}_{}...", migration.version, migration.name);
+        apply(conn, migration).await?;
+    }
+
+    let applied = fetch_applied(conn).await?;
+    log::info!("Database schema migrated to version {}", latest_version());
+    Ok(build_status(&applied))
+}
+
+/// Applies every pending migration, each inside its own transaction.
+pub async fn run_migrations(pool: &PgPool) -> AppResult<MigrationStatus> {
+    let mut conn = pool
+        .acquire()
+        .await
+        .map_err(|e| AppError::database("Failed to acquire connection for migrations", e))?;
+
+    sqlx::query("SELECT pg_advisory_lock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| AppError::database("Failed to acquire migration lock", e))?;
+
+    let result = run_locked(&mut conn).await;
+
+    sqlx::query("SELECT pg_advisory_unlock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| AppError::database("Failed to release migration lock", e))?;
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::with_empty_database;
+
+    /// As shipped. A migration that has been applied somewhere must never change.
+    const SHIPPED_CHECKSUMS: &[(i64, &str)] = &[
+        (1, "8265c23f1d1738ca216a85e71827b691a095247f03cb00bd4b350185a9920ede"),
+        (2, "4ef7ae10c1663f816f0fbf56c4f72d478886a557196ad601411b044f55a2bd9a"),
+        (3, "af5da6510b3ddd984fa033d04f8d00e2ea27731cc4476b77838e8e7b6243ee7f"),
+        (4, "925fb837c831f94b801283e77ab051cfdea9d1996145897f70bd835514bf3d5b"),
+        (5, "dfbb9227e5c8f043441139831f2981387f9e1646c88c82d4a2bdd1d0e8280a68"),
+        (6, "3d083e673f6406b8104131d8800fc29431b7459f47888c4c590364b8112095ba"),
+        (7, "2f813bb0a533809bceb521c2b919ae2f6ad608abdc63d7f70718d0cbf7d5523a"),
+        (8, "0d9db599a1f7ccff16123ab867350efeaee9a7a095502c527f2786b4f3d10f4d"),
+    ];
+
+    fn all_versions() -> Vec<i64> {
+        MIGRATIONS.iter().map(|migration| migration.version).collect()
+    }
+
+    async fn checksum_of(pool: &PgPool, version: i64) -> Option<(String, bool)> {
+        sqlx::query_as("SELECT checksum, success FROM schema_migrations WHERE version = $1")
+            .bind(version)
+            .fetch_optional(pool)
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn shipped_migrations_are_unchanged() {
+        let versions: Vec<i64> = SHIPPED_CHECKSUMS.iter().map(|(version, _)| *version).collect();
+        assert_eq!(all_versions(), versions, "add the checksum of a new migration here");
+        for (migration, (_, checksum)) in MIGRATIONS.iter().zip(SHIPPED_CHECKSUMS) {
+            assert_eq!(&migration.checksum(), checksum, "migration {} was edited", migration.version);
+        }
+    }
+
+    #[test]
+    fn migrates_an_empty_database_once() {
+        with_empty_database(|pool| async move {
+            let status = run_migrations(&pool).await.unwrap();
+            assert_eq!(status.applied, all_versions());
+            assert!(status.pending.is_empty() && status.failed.is_empty());
+            for migration in MIGRATIONS {
+                let recorded = checksum_of(&pool, migration.version).await;
+                assert_eq!(recorded, Some((migration.checksum(), true)));
+            }
+
+            let again = run_migrations(&pool).await.unwrap();
+            assert_eq!(again.applied, all_versions());
+            assert!(again.pending.is_empty());
+        });
+    }
+
+    #[test]
+    fn refuses_a_database_whose_migrations_changed() {
+        with_empty_database(|pool| async move {
+            run_migrations(&pool).await.unwrap();
+            sqlx::query("UPDATE schema_migrations SET checksum = 'edited' WHERE version = 2")
+                .execute(&pool)
+                .await
+                .unwrap();
+
+            match run_migrations(&pool).await {
+                Err(AppError::Database(message)) => assert!(message.contains("checksum mismatch"), "{}", message),
+                other => panic!("expected a checksum mismatch, got {:?}", other),
+            }
+        });
+    }
+
+    #[test]
+    fn refuses_a_database_from_a_newer_version() {
+        with_empty_database(|pool| async move {
+            run_migrations(&pool).await.unwrap();
+            sqlx::query("INSERT INTO schema_migrations (version, name, checksum, success) VALUES ($1, 'future', '', TRUE)")
+                .bind(latest_version() + 1)
+                .execute(&pool)
+                .await
+                .unwrap();
+
+            match run_migrations(&pool).await {
+                Err(AppError::Database(message)) => assert!(message.contains("newer"), "{}", message),
+                other => panic!("expected a newer schema error, got {:?}", other),
+            }
+        });
+    }
+
+    #[test]
+    fn records_a_failed_migration_and_retries_it() {
+        with_empty_database(|pool| async move {
+            // Migration 2 keeps an existing payments table and then fails on its columns
+            sqlx::query("CREATE TABLE payments (id INTEGER)").execute(&pool).await.unwrap();
+
+            let error = run_migrations(&pool).await.unwrap_err();
+            assert!(error.to_string().contains("0002_payments failed"), "{}", error);
+            assert_eq!(checksum_of(&pool, 1).await.map(|(_, success)| success), Some(true));
+            assert_eq!(checksum_of(&pool, 2).await.map(|(_, success)| success), Some(false));
+            assert_eq!(checksum_of(&pool, 3).await, None);
+
+            sqlx::query("DROP TABLE payments").execute(&pool).await.unwrap();
+            let status = run_migrations(&pool).await.unwrap();
+            assert_eq!(status.applied, all_versions());
+            assert!(status.failed.is_empty());
+        });
+    }
+}
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use time::OffsetDateTime;
+use rust_decimal::Decimal;
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Client {
@@ -10,7 +11,7 @@ pub struct Client {
     pub contact: String,
     pub category: String,
     pub observations: String,
-    pub debt: f64,
+    pub debt: Decimal,
     pub created_at: OffsetDateTime,
     pub updated_at: OffsetDateTime,
 }
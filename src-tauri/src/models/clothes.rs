@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use time::OffsetDateTime;
 use std::collections::HashMap;
+use rust_decimal::Decimal;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ClothingType {
@@ -108,7 +109,7 @@ pub struct Clothes {
     pub order_id: String,
     pub clothing_type: String, // Will be converted to/from ClothingType in DTOs
     pub custom_type: Option<String>,
-    pub unit_price: f64,
+    pub unit_price: Decimal,
     pub sizes: String, // JSON string, will be parsed to/from SizesMap in DTOs
     pub color: String,
     pub total_quantity: i32,
@@ -123,7 +124,7 @@ pub struct ClothingService {
     pub service_type: String, // Will be converted to/from ServiceType in DTOs
     pub location: String, // Will be converted to/from ServiceLocation in DTOs
     pub description: Option<String>, // New description field
-    pub unit_price: f64,
+    pub unit_price: Decimal,
     pub created_at: OffsetDateTime,
     pub updated_at: OffsetDateTime,
 }
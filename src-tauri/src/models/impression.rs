@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use time::OffsetDateTime;
+use rust_decimal::Decimal;
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Impression {
@@ -10,7 +11,7 @@ pub struct Impression {
     pub size: String,
     pub material: String,
     pub description: String,
-    pub price: f64,
+    pub price: Decimal,
     pub created_at: OffsetDateTime,
     pub updated_at: OffsetDateTime,
 }
\ No newline at end of file
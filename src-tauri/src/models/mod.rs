@@ -2,10 +2,12 @@ pub mod client;
 pub mod clothes;
 pub mod impression;
 pub mod order;
+pub mod payment;
 pub mod user;
 
 pub use client::Client;
 pub use clothes::{Clothes, ClothingService, ClothingType, ServiceType, ServiceLocation, SizesMap};
 pub use impression::Impression;
-pub use order::{Order, OrderStatus};
-pub use user::User;
+pub use order::{Order, OrderStatus, OrderStatusChange};
+pub use payment::{Payment, PaymentMethod};
+pub use user::{Role, User};
@@ -1,8 +1,9 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use time::{Date, OffsetDateTime};
+use rust_decimal::Decimal;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum OrderStatus {
     #[serde(rename = "order_received")]
     OrderReceived,
@@ -12,6 +13,10 @@ pub enum OrderStatus {
     ReadyForDelivery,
     #[serde(rename = "delivered")]
     Delivered,
+    #[serde(rename = "on_hold")]
+    OnHold,
+    #[serde(rename = "cancelled")]
+    Cancelled,
 }
 
 impl Default for OrderStatus {
@@ -20,20 +25,132 @@ impl Default for OrderStatus {
     }
 }
 
+impl OrderStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderStatus::OrderReceived => "order_received",
+            OrderStatus::InProduction => "in_production",
+            OrderStatus::ReadyForDelivery => "ready_for_delivery",
+            OrderStatus::Delivered => "delivered",
+            OrderStatus::OnHold => "on_hold",
+            OrderStatus::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn parse(status: &str) -> Option<Self> {
+        match status {
+            "order_received" => Some(OrderStatus::OrderReceived),
+            "in_production" => Some(OrderStatus::InProduction),
+            "ready_for_delivery" => Some(OrderStatus::ReadyForDelivery),
+            "delivered" => Some(OrderStatus::Delivered),
+            "on_hold" => Some(OrderStatus::OnHold),
+            "cancelled" => Some(OrderStatus::Cancelled),
+            _ => None,
+        }
+    }
+
+    /// Statuses a new order may start in
+    pub fn is_initial(&self) -> bool {
+        matches!(self, OrderStatus::OrderReceived | OrderStatus::OnHold)
+    }
+
+    /// Delivered and cancelled orders are closed; everything else moves
+    /// forward one step, back to production for rework, or onto hold.
+    pub fn can_transition_to(&self, next: OrderStatus) -> bool {
+        use OrderStatus::*;
+        matches!(
+            (self, next),
+            (OrderReceived, InProduction | OnHold | Cancelled)
+                | (InProduction, ReadyForDelivery | OnHold | Cancelled)
+                | (ReadyForDelivery, Delivered | InProduction | OnHold | Cancelled)
+                | (OnHold, OrderReceived | InProduction | ReadyForDelivery | Cancelled)
+        )
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Order {
     pub id: String,
     pub name: String,
     pub client_id: String,
     pub order_number: i32,
+    pub order_year: i32,
     pub client_requisition_number: i32,
     pub due_date: Date,
-    pub discount: f64,
-    pub iva: f64,
-    pub subtotal: f64,
-    pub total: f64,
+    pub discount: Decimal,
+    pub iva: Decimal,
+    pub subtotal: Decimal,
+    pub total: Decimal,
     pub status: String, // Will be converted to/from OrderStatus in DTOs
-    pub debt: f64,
+    pub debt: Decimal,
     pub created_at: OffsetDateTime,
     pub updated_at: OffsetDateTime,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct OrderStatusChange {
+    pub id: String,
+    pub order_id: String,
+    pub from_status: Option<String>, // None for the status the order was created with
+    pub to_status: String,
+    pub changed_by: Option<String>,
+    pub note: Option<String>,
+    pub changed_at: OffsetDateTime,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OrderStatus::{self, *};
+
+    const ALL: [OrderStatus; 6] = [OrderReceived, InProduction, ReadyForDelivery, Delivered, OnHold, Cancelled];
+
+    #[test]
+    fn closed_orders_cannot_move() {
+        for next in ALL {
+            assert!(!Delivered.can_transition_to(next), "delivered -> {:?}", next);
+            assert!(!Cancelled.can_transition_to(next), "cancelled -> {:?}", next);
+        }
+    }
+
+    #[test]
+    fn no_status_transitions_to_itself() {
+        for status in ALL {
+            assert!(!status.can_transition_to(status), "{:?} -> itself", status);
+        }
+    }
+
+    #[test]
+    fn open_orders_move_forward_and_can_be_held_or_cancelled() {
+        assert!(OrderReceived.can_transition_to(InProduction));
+        assert!(InProduction.can_transition_to(ReadyForDelivery));
+        assert!(ReadyForDelivery.can_transition_to(Delivered));
+        for status in [OrderReceived, InProduction, ReadyForDelivery] {
+            assert!(status.can_transition_to(OnHold), "{:?} -> on hold", status);
+            assert!(status.can_transition_to(Cancelled), "{:?} -> cancelled", status);
+        }
+    }
+
+    #[test]
+    fn skipping_steps_is_rejected() {
+        assert!(!OrderReceived.can_transition_to(ReadyForDelivery));
+        assert!(!OrderReceived.can_transition_to(Delivered));
+        assert!(!InProduction.can_transition_to(Delivered));
+        assert!(!OnHold.can_transition_to(Delivered));
+    }
+
+    #[test]
+    fn rework_and_resume_are_allowed() {
+        assert!(ReadyForDelivery.can_transition_to(InProduction));
+        assert!(OnHold.can_transition_to(OrderReceived));
+        assert!(OnHold.can_transition_to(InProduction));
+        assert!(OnHold.can_transition_to(ReadyForDelivery));
+    }
+
+    #[test]
+    fn parse_round_trips_as_str() {
+        for status in ALL {
+            assert_eq!(OrderStatus::parse(status.as_str()), Some(status));
+        }
+        assert_eq!(OrderStatus::parse("shipped"), None);
+    }
+}
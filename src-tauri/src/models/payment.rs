@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use time::{Date, OffsetDateTime};
+use rust_decimal::Decimal;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub enum PaymentMethod {
+    #[default]
+    #[serde(rename = "cash")]
+    Cash,
+    #[serde(rename = "bank_transfer")]
+    BankTransfer,
+    #[serde(rename = "mpesa")]
+    Mpesa,
+    #[serde(rename = "emola")]
+    Emola,
+    #[serde(rename = "card")]
+    Card,
+    #[serde(rename = "cheque")]
+    Cheque,
+    #[serde(rename = "other")]
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Payment {
+    pub id: String,
+    pub order_id: String,
+    pub amount: Decimal,
+    pub payment_date: Date,
+    pub method: String, // Will be converted to/from PaymentMethod in DTOs
+    pub reference: Option<String>,
+    pub received_by: Option<String>, // id of the user who took the payment
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+}
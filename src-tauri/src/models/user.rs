@@ -8,6 +8,7 @@ pub struct User {
     pub login: String,
     pub password: String,
     pub role: String,
+    pub active: bool,
     #[serde(with = "time::serde::rfc3339")]
     pub created_at: OffsetDateTime,
     #[serde(with = "time::serde::rfc3339")]
@@ -22,8 +23,39 @@ impl User {
             login,
             password,
             role,
+            active: true,
             created_at: now,
             updated_at: now,
         }
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Role {
+    #[serde(rename = "admin")]
+    Admin,
+    #[serde(rename = "user")]
+    User,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::User => "user",
+        }
+    }
+
+    pub fn parse(role: &str) -> Option<Self> {
+        match role {
+            "admin" => Some(Role::Admin),
+            "user" => Some(Role::User),
+            _ => None,
+        }
+    }
+
+    /// Admins can do everything a regular user can
+    pub fn satisfies(&self, required: Role) -> bool {
+        matches!((self, required), (Role::Admin, _) | (Role::User, Role::User))
+    }
+}
@@ -0,0 +1,71 @@
+//! Money amounts as `Decimal`, stored as NUMERIC(14,2).
+//!
+//! Rounding rules (always to 2 places, half away from zero):
+//! - prices, discounts and payments are rounded when they come in;
+//! - line totals (price × quantity) and the subtotal are exact sums;
+//! - IVA is computed on the subtotal and rounded once;
+//! - total = subtotal + IVA - discount, with no further rounding.
+//!
+//! The frontend gets the amounts as JSON numbers. Backups write them as
+//! strings so no digit is lost, and read numbers from older ones.
+
+use rust_decimal::{Decimal, RoundingStrategy};
+use crate::error::{AppError, AppResult};
+
+pub const MONEY_SCALE: u32 = 2;
+
+/// Rounds to centavos.
+pub fn round_money(value: Decimal) -> Decimal {
+    value.round_dp_with_strategy(MONEY_SCALE, RoundingStrategy::MidpointAwayFromZero)
+}
+
+/// IVA on `subtotal` at `rate` (a percentage, e.g. 16).
+pub fn iva_amount(subtotal: Decimal, rate: Decimal) -> Decimal {
+    round_money(subtotal * rate / Decimal::ONE_HUNDRED)
+}
+
+/// Order total from the subtotal, IVA rate and fixed discount.
+pub fn order_total(subtotal: Decimal, iva_rate: Decimal, discount: Decimal) -> Decimal {
+    subtotal + iva_amount(subtotal, iva_rate) - discount
+}
+
+/// Checks an amount entered by the user and rounds it to centavos.
+pub fn parse_amount(value: Decimal, field: &str) -> AppResult<Decimal> {
+    if value.is_sign_negative() {
+        return Err(AppError::invalid(field, "negative", format!("{} cannot be negative", field)));
+    }
+    Ok(round_money(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn d(value: &str) -> Decimal {
+        Decimal::from_str(value).unwrap()
+    }
+
+    #[test]
+    fn iva_is_rounded_half_away_from_zero() {
+        assert_eq!(iva_amount(d("100.00"), d("16")), d("16.00"));
+        // 0.125 → 0.13, 0.03125 → 0.03, 1.6048 → 1.60
+        assert_eq!(iva_amount(d("1.25"), d("10")), d("0.13"));
+        assert_eq!(iva_amount(d("0.25"), d("12.5")), d("0.03"));
+        assert_eq!(iva_amount(d("10.03"), d("16")), d("1.60"));
+    }
+
+    #[test]
+    fn total_adds_iva_and_subtracts_the_discount() {
+        assert_eq!(order_total(d("100.00"), d("16"), d("0")), d("116.00"));
+        assert_eq!(order_total(d("100.00"), d("16"), d("6.00")), d("110.00"));
+        assert_eq!(order_total(d("10.03"), d("16"), d("0.50")), d("11.13"));
+        assert_eq!(order_total(d("250.00"), d("0"), d("0")), d("250.00"));
+    }
+
+    #[test]
+    fn amounts_are_rounded_and_negatives_rejected() {
+        assert_eq!(parse_amount(d("12.345"), "price").unwrap(), d("12.35"));
+        assert!(matches!(parse_amount(d("-1"), "price"), Err(AppError::Validation(_))));
+    }
+}
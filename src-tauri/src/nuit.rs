@@ -0,0 +1,81 @@
+//! Mozambican NUIT (Número Único de Identificação Tributária): 9 digits,
+//! the last one a check digit over the first 8.
+//!
+//! Clients without a NUIT are stored with an empty one. Only non-empty NUITs
+//! have to be unique (migration 0007).
+
+pub const NUIT_LENGTH: usize = 9;
+
+const CHECK_WEIGHTS: [u32; NUIT_LENGTH - 1] = [8, 9, 4, 5, 6, 7, 8, 9];
+
+/// What people typed when the client has no NUIT, after `normalize` removes
+/// whitespace and upper-cases it. Keep in sync with migration 0007.
+const PLACEHOLDERS: &[&str] = &["-", "N/A", "NA", "S/N", "SN", "SEMNUIT"];
+
+/// Strips whitespace, so "400 339 910" and "400339910" are the same NUIT.
+/// Placeholders and all-zero NUITs become empty.
+pub fn normalize(raw: &str) -> String {
+    let nuit: String = raw.chars().filter(|c| !c.is_whitespace()).collect();
+    let upper = nuit.to_uppercase();
+    if PLACEHOLDERS.contains(&upper.as_str()) || nuit.chars().all(|c| c == '0') {
+        return String::new();
+    }
+    nuit
+}
+
+/// Nine digits, whatever the check digit
+pub fn is_well_formed(nuit: &str) -> bool {
+    nuit.len() == NUIT_LENGTH && nuit.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// The weighted sum of the first 8 digits modulo 11 gives the last digit,
+/// with 10 written as 1. Expects a well-formed NUIT.
+///
+/// Worked example, 400339910: 4×8 + 0×9 + 0×4 + 3×5 + 3×6 + 9×7 + 9×8 + 1×9
+/// = 209, and 209 mod 11 = 0, the last digit.
+pub fn has_valid_check_digit(nuit: &str) -> bool {
+    let digits: Vec<u32> = nuit.bytes().map(|b| u32::from(b - b'0')).collect();
+    let sum: u32 = CHECK_WEIGHTS.iter().zip(&digits).map(|(weight, digit)| weight * digit).sum();
+    let expected = match sum % 11 {
+        10 => 1,
+        check => check,
+    };
+    digits.last() == Some(&expected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_nuits_with_a_valid_check_digit() {
+        for nuit in ["400339910", "123456785", "111111111", "400000001"] {
+            assert!(has_valid_check_digit(nuit), "{} should be valid", nuit);
+        }
+    }
+
+    #[test]
+    fn rejects_wrong_or_transposed_digits() {
+        // Wrong check digit, last digits swapped, first digits swapped
+        for nuit in ["400339911", "123456875", "213456785", "400000000"] {
+            assert!(!has_valid_check_digit(nuit), "{} should be invalid", nuit);
+        }
+    }
+
+    #[test]
+    fn writes_a_remainder_of_ten_as_one() {
+        // 4×8 = 32, and 32 mod 11 = 10
+        assert!(has_valid_check_digit("400000001"));
+        assert!(!has_valid_check_digit("400000000"));
+    }
+
+    #[test]
+    fn normalizes_whitespace_and_placeholders() {
+        assert_eq!(normalize(" 400 339 910 "), "400339910");
+        assert_eq!(normalize("n/a"), "");
+        assert_eq!(normalize("000000000"), "");
+        assert!(is_well_formed("400339910"));
+        assert!(!is_well_formed("40033991"));
+        assert!(!is_well_formed("40033991A"));
+    }
+}
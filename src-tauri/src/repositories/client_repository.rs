@@ -1,13 +1,15 @@
-use crate::database::get_db_pool;
+use crate::dto::{ClientFilter, ClientSortField, PageRequest};
 use crate::models::Client;
+use sqlx::{PgConnection, Postgres, QueryBuilder};
 use time::OffsetDateTime;
 use uuid::Uuid;
+use rust_decimal::Decimal;
+use crate::error::{AppError, AppResult};
 
 pub struct ClientRepository;
 
 impl ClientRepository {
-    pub async fn create(&self, name: String, nuit: String, contact: String, category: String, observations: String) -> Result<Client, String> {
-        let pool = get_db_pool()?;
+    pub async fn create(&self, conn: &mut PgConnection, name: String, nuit: String, contact: String, category: String, observations: String) -> AppResult<Client> {
         let id = Uuid::new_v4().to_string();
         let now = OffsetDateTime::now_utc();
 
@@ -24,63 +26,132 @@ impl ClientRepository {
         .bind(&contact)
         .bind(&category)
         .bind(&observations)
-        .bind(0.0) // debt starts at 0
+        .bind(Decimal::ZERO) // debt starts at 0
         .bind(now)
         .bind(now)
-        .fetch_one(pool)
+        .fetch_one(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to create client: {}", e))?;
+        .map_err(|e| AppError::database("Failed to create client", e))?;
 
         Ok(client)
     }
 
-    pub async fn get_by_id(&self, id: &str) -> Result<Option<Client>, String> {
-        let pool = get_db_pool()?;
-
+    pub async fn get_by_id(&self, conn: &mut PgConnection, id: &str) -> AppResult<Option<Client>> {
         let client = sqlx::query_as::<_, Client>(
             "SELECT * FROM clients WHERE id = $1"
         )
         .bind(id)
-        .fetch_optional(pool)
+        .fetch_optional(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to get client by id: {}", e))?;
+        .map_err(|e| AppError::database("Failed to get client by id", e))?;
 
         Ok(client)
     }
 
-    pub async fn get_by_name(&self, name: &str) -> Result<Vec<Client>, String> {
-        let pool = get_db_pool()?;
+    /// Locks the client row until the surrounding transaction ends
+    pub async fn lock(&self, conn: &mut PgConnection, id: &str) -> AppResult<bool> {
+        let locked = sqlx::query_scalar::<_, String>(
+            "SELECT id FROM clients WHERE id = $1 FOR UPDATE"
+        )
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(|e| AppError::database("Failed to lock client", e))?;
+
+        Ok(locked.is_some())
+    }
+
+    /// The client with this NUIT, which must already be normalized and non-empty
+    pub async fn get_by_nuit(&self, conn: &mut PgConnection, nuit: &str) -> AppResult<Option<Client>> {
+        let client = sqlx::query_as::<_, Client>(
+            "SELECT * FROM clients WHERE nuit = $1"
+        )
+        .bind(nuit)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(|e| AppError::database("Failed to get client by NUIT", e))?;
+
+        Ok(client)
+    }
 
+    pub async fn get_by_name(&self, conn: &mut PgConnection, name: &str) -> AppResult<Vec<Client>> {
         let clients = sqlx::query_as::<_, Client>(
             "SELECT * FROM clients WHERE name ILIKE $1 ORDER BY name"
         )
         .bind(format!("%{}%", name))
-        .fetch_all(pool)
+        .fetch_all(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to get clients by name: {}", e))?;
+        .map_err(|e| AppError::database("Failed to get clients by name", e))?;
 
         Ok(clients)
     }
 
-    pub async fn list(&self) -> Result<Vec<Client>, String> {
-        let pool = get_db_pool()?;
-
+    pub async fn list(&self, conn: &mut PgConnection) -> AppResult<Vec<Client>> {
         let clients = sqlx::query_as::<_, Client>(
             "SELECT * FROM clients ORDER BY created_at DESC"
         )
-        .fetch_all(pool)
+        .fetch_all(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to list clients: {}", e))?;
+        .map_err(|e| AppError::database("Failed to list clients", e))?;
 
         Ok(clients)
     }
 
-    pub async fn update(&self, id: &str, name: Option<String>, nuit: Option<String>, contact: Option<String>, category: Option<String>, observations: Option<String>) -> Result<Option<Client>, String> {
-        let pool = get_db_pool()?;
+    /// One page of clients plus the number of clients matching the filter.
+    pub async fn list_page(&self, conn: &mut PgConnection, filter: &ClientFilter, page: &PageRequest<ClientSortField>) -> AppResult<(Vec<Client>, i64)> {
+        let mut count_query = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM clients WHERE TRUE");
+        Self::push_filter(&mut count_query, filter);
+        let total: i64 = count_query
+            .build_query_scalar()
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(|e| AppError::database("Failed to count clients", e))?;
+
+        let mut query = QueryBuilder::<Postgres>::new("SELECT * FROM clients WHERE TRUE");
+        Self::push_filter(&mut query, filter);
+        let sort = page.sort_by.unwrap_or(ClientSortField::CreatedAt);
+        query.push(format!(" ORDER BY {} {}, id", sort.column(), page.direction.as_sql()));
+        query.push(" LIMIT ").push_bind(page.limit());
+        query.push(" OFFSET ").push_bind(page.offset());
+
+        let clients = query
+            .build_query_as::<Client>()
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(|e| AppError::database("Failed to list clients", e))?;
+
+        Ok((clients, total))
+    }
+
+    fn push_filter(query: &mut QueryBuilder<'_, Postgres>, filter: &ClientFilter) {
+        if let Some(search) = filter.search.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+            let pattern = format!("%{}%", search);
+            query.push(" AND (name ILIKE ").push_bind(pattern.clone());
+            query.push(" OR nuit ILIKE ").push_bind(pattern.clone());
+            query.push(" OR contact ILIKE ").push_bind(pattern);
+            query.push(")");
+        }
+        if let Some(category) = &filter.category {
+            query.push(" AND category = ").push_bind(category.clone());
+        }
+        match filter.has_debt {
+            Some(true) => { query.push(" AND debt > 0"); }
+            Some(false) => { query.push(" AND debt <= 0"); }
+            None => {}
+        }
+        match filter.has_nuit {
+            Some(true) => { query.push(" AND nuit <> ''"); }
+            Some(false) => { query.push(" AND nuit = ''"); }
+            None => {}
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn update(&self, conn: &mut PgConnection, id: &str, name: Option<String>, nuit: Option<String>, contact: Option<String>, category: Option<String>, observations: Option<String>) -> AppResult<Option<Client>> {
         let now = OffsetDateTime::now_utc();
 
         // First, get the current client
-        let current_client = self.get_by_id(id).await?;
+        let current_client = self.get_by_id(&mut *conn, id).await?;
         let current_client = match current_client {
             Some(client) => client,
             None => return Ok(None),
@@ -108,37 +179,34 @@ impl ClientRepository {
         .bind(updated_category)
         .bind(updated_observations)
         .bind(now)
-        .fetch_optional(pool)
+        .fetch_optional(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to update client: {}", e))?;
+        .map_err(|e| AppError::database("Failed to update client", e))?;
 
         Ok(client)
     }
 
-    pub async fn delete(&self, id: &str) -> Result<bool, String> {
-        let pool = get_db_pool()?;
-
+    pub async fn delete(&self, conn: &mut PgConnection, id: &str) -> AppResult<bool> {
         let result = sqlx::query("DELETE FROM clients WHERE id = $1")
             .bind(id)
-            .execute(pool)
+            .execute(&mut *conn)
             .await
-            .map_err(|e| format!("Failed to delete client: {}", e))?;
+            .map_err(|e| AppError::database("Failed to delete client", e))?;
 
         Ok(result.rows_affected() > 0)
     }
 
-    pub async fn update_client_debt(&self, client_id: &str) -> Result<bool, String> {
-        let pool = get_db_pool()?;
+    pub async fn update_client_debt(&self, conn: &mut PgConnection, client_id: &str) -> AppResult<bool> {
         let now = OffsetDateTime::now_utc();
 
         // Calculate total debt from all orders for this client
-        let total_debt: f64 = sqlx::query_scalar::<_, f64>(
+        let total_debt: Decimal = sqlx::query_scalar::<_, Decimal>(
             "SELECT COALESCE(SUM(debt), 0.0) FROM orders WHERE client_id = $1"
         )
         .bind(client_id)
-        .fetch_one(pool)
+        .fetch_one(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to calculate client debt: {}", e))?;
+        .map_err(|e| AppError::database("Failed to calculate client debt", e))?;
 
         // Update the client's debt field
         let result = sqlx::query(
@@ -147,9 +215,9 @@ impl ClientRepository {
         .bind(client_id)
         .bind(total_debt)
         .bind(now)
-        .execute(pool)
+        .execute(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to update client debt: {}", e))?;
+        .map_err(|e| AppError::database("Failed to update client debt", e))?;
 
         Ok(result.rows_affected() > 0)
     }
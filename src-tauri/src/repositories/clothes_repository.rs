@@ -1,21 +1,23 @@
-use crate::database::get_db_pool;
 use crate::models::{Clothes, ClothingService};
+use sqlx::PgConnection;
 use time::OffsetDateTime;
 use uuid::Uuid;
+use rust_decimal::Decimal;
+use crate::error::{AppError, AppResult};
 
 pub struct ClothesRepository;
 
 impl ClothesRepository {
     pub async fn create(&self, 
+        conn: &mut PgConnection,
         order_id: String, 
         clothing_type: String, 
         custom_type: Option<String>,
-        unit_price: f64,
+        unit_price: Decimal,
         sizes: String, // JSON string
         color: String,
         total_quantity: i32
-    ) -> Result<Clothes, String> {
-        let pool = get_db_pool()?;
+    ) -> AppResult<Clothes> {
         let id = Uuid::new_v4().to_string();
         let now = OffsetDateTime::now_utc();
 
@@ -24,12 +26,12 @@ impl ClothesRepository {
             "SELECT EXISTS(SELECT 1 FROM orders WHERE id = $1)"
         )
         .bind(&order_id)
-        .fetch_one(pool)
+        .fetch_one(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to validate order: {}", e))?;
+        .map_err(|e| AppError::database("Failed to validate order", e))?;
 
         if !order_exists {
-            return Err("Order not found".to_string());
+            return Err(AppError::not_found("order", &order_id));
         }
 
         let clothes = sqlx::query_as::<_, Clothes>(
@@ -49,55 +51,51 @@ impl ClothesRepository {
         .bind(total_quantity)
         .bind(now)
         .bind(now)
-        .fetch_one(pool)
+        .fetch_one(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to create clothes: {}", e))?;
+        .map_err(|e| AppError::database("Failed to create clothes", e))?;
 
         Ok(clothes)
     }
 
-    pub async fn get_by_id(&self, id: &str) -> Result<Option<Clothes>, String> {
-        let pool = get_db_pool()?;
-
+    pub async fn get_by_id(&self, conn: &mut PgConnection, id: &str) -> AppResult<Option<Clothes>> {
         let clothes = sqlx::query_as::<_, Clothes>(
             "SELECT * FROM clothes WHERE id = $1"
         )
         .bind(id)
-        .fetch_optional(pool)
+        .fetch_optional(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to get clothes by id: {}", e))?;
+        .map_err(|e| AppError::database("Failed to get clothes by id", e))?;
 
         Ok(clothes)
     }
 
-    pub async fn get_by_order_id(&self, order_id: &str) -> Result<Vec<Clothes>, String> {
-        let pool = get_db_pool()?;
-
+    pub async fn get_by_order_id(&self, conn: &mut PgConnection, order_id: &str) -> AppResult<Vec<Clothes>> {
         let clothes = sqlx::query_as::<_, Clothes>(
             "SELECT * FROM clothes WHERE order_id = $1 ORDER BY created_at ASC"
         )
         .bind(order_id)
-        .fetch_all(pool)
+        .fetch_all(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to get clothes by order_id: {}", e))?;
+        .map_err(|e| AppError::database("Failed to get clothes by order_id", e))?;
 
         Ok(clothes)
     }
 
     pub async fn update(&self, 
+        conn: &mut PgConnection,
         id: &str, 
         clothing_type: Option<String>,
         custom_type: Option<Option<String>>,
-        unit_price: Option<f64>,
+        unit_price: Option<Decimal>,
         sizes: Option<String>,
         color: Option<String>,
         total_quantity: Option<i32>
-    ) -> Result<Option<Clothes>, String> {
-        let pool = get_db_pool()?;
+    ) -> AppResult<Option<Clothes>> {
         let now = OffsetDateTime::now_utc();
 
         // Get current clothes data
-        let current = self.get_by_id(id).await?;
+        let current = self.get_by_id(&mut *conn, id).await?;
         let current = match current {
             Some(clothes) => clothes,
             None => return Ok(None),
@@ -125,21 +123,19 @@ impl ClothesRepository {
         .bind(color.unwrap_or(current.color))
         .bind(total_quantity.unwrap_or(current.total_quantity))
         .bind(now)
-        .fetch_one(pool)
+        .fetch_one(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to update clothes: {}", e))?;
+        .map_err(|e| AppError::database("Failed to update clothes", e))?;
 
         Ok(Some(updated_clothes))
     }
 
-    pub async fn delete(&self, id: &str) -> Result<bool, String> {
-        let pool = get_db_pool()?;
-
+    pub async fn delete(&self, conn: &mut PgConnection, id: &str) -> AppResult<bool> {
         let result = sqlx::query("DELETE FROM clothes WHERE id = $1")
             .bind(id)
-            .execute(pool)
+            .execute(&mut *conn)
             .await
-            .map_err(|e| format!("Failed to delete clothes: {}", e))?;
+            .map_err(|e| AppError::database("Failed to delete clothes", e))?;
 
         Ok(result.rows_affected() > 0)
     }
@@ -149,13 +145,13 @@ pub struct ClothingServiceRepository;
 
 impl ClothingServiceRepository {
     pub async fn create(&self, 
+        conn: &mut PgConnection,
         clothes_id: String, 
         service_type: String, 
         location: String,
         description: Option<String>,
-        unit_price: f64
-    ) -> Result<ClothingService, String> {
-        let pool = get_db_pool()?;
+        unit_price: Decimal
+    ) -> AppResult<ClothingService> {
         let id = Uuid::new_v4().to_string();
         let now = OffsetDateTime::now_utc();
 
@@ -164,12 +160,12 @@ impl ClothingServiceRepository {
             "SELECT EXISTS(SELECT 1 FROM clothes WHERE id = $1)"
         )
         .bind(&clothes_id)
-        .fetch_one(pool)
+        .fetch_one(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to validate clothes: {}", e))?;
+        .map_err(|e| AppError::database("Failed to validate clothes", e))?;
 
         if !clothes_exists {
-            return Err("Clothes not found".to_string());
+            return Err(AppError::not_found("clothes", &clothes_id));
         }
 
         let service = sqlx::query_as::<_, ClothingService>(
@@ -187,53 +183,49 @@ impl ClothingServiceRepository {
         .bind(unit_price)
         .bind(now)
         .bind(now)
-        .fetch_one(pool)
+        .fetch_one(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to create clothing service: {}", e))?;
+        .map_err(|e| AppError::database("Failed to create clothing service", e))?;
 
         Ok(service)
     }
 
-    pub async fn get_by_id(&self, id: &str) -> Result<Option<ClothingService>, String> {
-        let pool = get_db_pool()?;
-
+    pub async fn get_by_id(&self, conn: &mut PgConnection, id: &str) -> AppResult<Option<ClothingService>> {
         let service = sqlx::query_as::<_, ClothingService>(
             "SELECT * FROM clothing_services WHERE id = $1"
         )
         .bind(id)
-        .fetch_optional(pool)
+        .fetch_optional(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to get clothing service by id: {}", e))?;
+        .map_err(|e| AppError::database("Failed to get clothing service by id", e))?;
 
         Ok(service)
     }
 
-    pub async fn get_by_clothes_id(&self, clothes_id: &str) -> Result<Vec<ClothingService>, String> {
-        let pool = get_db_pool()?;
-
+    pub async fn get_by_clothes_id(&self, conn: &mut PgConnection, clothes_id: &str) -> AppResult<Vec<ClothingService>> {
         let services = sqlx::query_as::<_, ClothingService>(
             "SELECT * FROM clothing_services WHERE clothes_id = $1 ORDER BY created_at ASC"
         )
         .bind(clothes_id)
-        .fetch_all(pool)
+        .fetch_all(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to get clothing services by clothes_id: {}", e))?;
+        .map_err(|e| AppError::database("Failed to get clothing services by clothes_id", e))?;
 
         Ok(services)
     }
 
     pub async fn update(&self, 
+        conn: &mut PgConnection,
         id: &str, 
         service_type: Option<String>,
         location: Option<String>,
         description: Option<Option<String>>,
-        unit_price: Option<f64>
-    ) -> Result<Option<ClothingService>, String> {
-        let pool = get_db_pool()?;
+        unit_price: Option<Decimal>
+    ) -> AppResult<Option<ClothingService>> {
         let now = OffsetDateTime::now_utc();
 
         // Get current service data
-        let current = self.get_by_id(id).await?;
+        let current = self.get_by_id(&mut *conn, id).await?;
         let current = match current {
             Some(service) => service,
             None => return Ok(None),
@@ -257,33 +249,29 @@ impl ClothingServiceRepository {
         .bind(description.unwrap_or(current.description))
         .bind(unit_price.unwrap_or(current.unit_price))
         .bind(now)
-        .fetch_one(pool)
+        .fetch_one(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to update clothing service: {}", e))?;
+        .map_err(|e| AppError::database("Failed to update clothing service", e))?;
 
         Ok(Some(updated_service))
     }
 
-    pub async fn delete(&self, id: &str) -> Result<bool, String> {
-        let pool = get_db_pool()?;
-
+    pub async fn delete(&self, conn: &mut PgConnection, id: &str) -> AppResult<bool> {
         let result = sqlx::query("DELETE FROM clothing_services WHERE id = $1")
             .bind(id)
-            .execute(pool)
+            .execute(&mut *conn)
             .await
-            .map_err(|e| format!("Failed to delete clothing service: {}", e))?;
+            .map_err(|e| AppError::database("Failed to delete clothing service", e))?;
 
         Ok(result.rows_affected() > 0)
     }
 
-    pub async fn delete_by_clothes_id(&self, clothes_id: &str) -> Result<u64, String> {
-        let pool = get_db_pool()?;
-
+    pub async fn delete_by_clothes_id(&self, conn: &mut PgConnection, clothes_id: &str) -> AppResult<u64> {
         let result = sqlx::query("DELETE FROM clothing_services WHERE clothes_id = $1")
             .bind(clothes_id)
-            .execute(pool)
+            .execute(&mut *conn)
             .await
-            .map_err(|e| format!("Failed to delete clothing services by clothes_id: {}", e))?;
+            .map_err(|e| AppError::database("Failed to delete clothing services by clothes_id", e))?;
 
         Ok(result.rows_affected())
     }
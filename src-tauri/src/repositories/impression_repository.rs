@@ -1,34 +1,37 @@
 
-use crate::database::get_db_pool;
 use crate::models::Impression;
+use sqlx::PgConnection;
 use time::OffsetDateTime;
 use uuid::Uuid;
+use rust_decimal::Decimal;
+use crate::error::{AppError, AppResult};
 
 pub struct ImpressionRepository;
 
 impl ImpressionRepository {
+    #[allow(clippy::too_many_arguments)]
     pub async fn create(
         &self,
+        conn: &mut PgConnection,
         order_id: String,
         name: String,
         size: String,
         material: String,
         description: String,
-        price: f64,
-    ) -> Result<Impression, String> {
-        let pool = get_db_pool()?;
+        price: Decimal,
+    ) -> AppResult<Impression> {
         
         // Validate that order exists
         let order_exists = sqlx::query_scalar::<_, bool>(
             "SELECT EXISTS(SELECT 1 FROM orders WHERE id = $1)"
         )
         .bind(&order_id)
-        .fetch_one(pool)
+        .fetch_one(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to validate order: {}", e))?;
+        .map_err(|e| AppError::database("Failed to validate order", e))?;
 
         if !order_exists {
-            return Err("Order not found".to_string());
+            return Err(AppError::not_found("order", &order_id));
         }
         
         let id = Uuid::new_v4().to_string();
@@ -50,51 +53,50 @@ impl ImpressionRepository {
         .bind(price)
         .bind(now)
         .bind(now)
-        .fetch_one(pool)
+        .fetch_one(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to create impression: {}", e))?;
+        .map_err(|e| AppError::database("Failed to create impression", e))?;
 
         Ok(impression)
     }
 
-    pub async fn get_by_id(&self, id: &str) -> Result<Option<Impression>, String> {
-        let pool = get_db_pool()?;
+    pub async fn get_by_id(&self, conn: &mut PgConnection, id: &str) -> AppResult<Option<Impression>> {
         
         let impression = sqlx::query_as::<_, Impression>(
             "SELECT * FROM impressions WHERE id = $1"
         )
         .bind(id)
-        .fetch_optional(pool)
+        .fetch_optional(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to get impression by id: {}", e))?;
+        .map_err(|e| AppError::database("Failed to get impression by id", e))?;
 
         Ok(impression)
     }
 
-    pub async fn get_by_order_id(&self, order_id: &str) -> Result<Vec<Impression>, String> {
-        let pool = get_db_pool()?;
+    pub async fn get_by_order_id(&self, conn: &mut PgConnection, order_id: &str) -> AppResult<Vec<Impression>> {
         
         let impressions = sqlx::query_as::<_, Impression>(
             "SELECT * FROM impressions WHERE order_id = $1 ORDER BY created_at ASC"
         )
         .bind(order_id)
-        .fetch_all(pool)
+        .fetch_all(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to get impressions by order_id: {}", e))?;
+        .map_err(|e| AppError::database("Failed to get impressions by order_id", e))?;
 
         Ok(impressions)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn update(
         &self,
+        conn: &mut PgConnection,
         id: &str,
         name: String,
         size: String,
         material: String,
         description: String,
-        price: f64,
-    ) -> Result<Option<Impression>, String> {
-        let pool = get_db_pool()?;
+        price: Decimal,
+    ) -> AppResult<Option<Impression>> {
         let now = OffsetDateTime::now_utc();
 
         let impression = sqlx::query_as::<_, Impression>(
@@ -112,21 +114,20 @@ impl ImpressionRepository {
         .bind(&description)
         .bind(price)
         .bind(now)
-        .fetch_optional(pool)
+        .fetch_optional(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to update impression: {}", e))?;
+        .map_err(|e| AppError::database("Failed to update impression", e))?;
 
         Ok(impression)
     }
 
-    pub async fn delete(&self, id: &str) -> Result<bool, String> {
-        let pool = get_db_pool()?;
+    pub async fn delete(&self, conn: &mut PgConnection, id: &str) -> AppResult<bool> {
         
         let result = sqlx::query("DELETE FROM impressions WHERE id = $1")
             .bind(id)
-            .execute(pool)
+            .execute(&mut *conn)
             .await
-            .map_err(|e| format!("Failed to delete impression: {}", e))?;
+            .map_err(|e| AppError::database("Failed to delete impression", e))?;
 
         Ok(result.rows_affected() > 0)
     }
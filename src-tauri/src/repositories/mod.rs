@@ -2,9 +2,16 @@ pub mod client_repository;
 pub mod clothes_repository;
 pub mod impression_repository;
 pub mod order_repository;
+pub mod order_status_history_repository;
+pub mod payment_repository;
+pub mod sequence_repository;
 pub mod user_repository;
 
 pub use client_repository::ClientRepository;
 pub use clothes_repository::{ClothesRepository, ClothingServiceRepository};
 pub use impression_repository::ImpressionRepository;
 pub use order_repository::OrderRepository;
+pub use order_status_history_repository::OrderStatusHistoryRepository;
+pub use payment_repository::PaymentRepository;
+pub use sequence_repository::SequenceRepository;
+pub use user_repository::UserRepository;
@@ -1,51 +1,64 @@
-use crate::database::get_db_pool;
+use crate::dto::{OrderFilter, OrderSortField, PageRequest};
 use crate::models::Order;
+use sqlx::{FromRow, PgConnection, Postgres, QueryBuilder};
 use time::{Date, OffsetDateTime};
 use uuid::Uuid;
+use rust_decimal::Decimal;
+use crate::error::{AppError, AppResult};
+
+const ORDER_WITH_CLIENT_SELECT: &str =
+    "SELECT o.*, c.name AS client_name, c.contact AS client_contact FROM orders o JOIN clients c ON c.id = o.client_id";
+
+#[derive(FromRow)]
+struct OrderWithClientRow {
+    #[sqlx(flatten)]
+    order: Order,
+    client_name: String,
+    client_contact: String,
+}
+
+impl OrderWithClientRow {
+    fn into_tuple(self) -> (Order, String, String) {
+        (self.order, self.client_name, self.client_contact)
+    }
+}
 
 pub struct OrderRepository;
 
 impl OrderRepository {
-    pub async fn create(&self, name: String, client_id: String, due_date: Date, iva: f64, discount: Option<f64>, status: String) -> Result<Order, String> {
-        let pool = get_db_pool()?;
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create(&self, conn: &mut PgConnection, name: String, client_id: String, due_date: Date, iva: Decimal, discount: Option<Decimal>, status: String, yearly_reset: bool) -> AppResult<Order> {
         let id = Uuid::new_v4().to_string();
         let now = OffsetDateTime::now_utc();
-        let discount_value = discount.unwrap_or(0.0);
+        let discount_value = discount.unwrap_or(Decimal::ZERO);
 
         // Validate that client exists
         let client_exists = sqlx::query_scalar::<_, bool>(
             "SELECT EXISTS(SELECT 1 FROM clients WHERE id = $1)"
         )
         .bind(&client_id)
-        .fetch_one(pool)
+        .fetch_one(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to validate client: {}", e))?;
+        .map_err(|e| AppError::database("Failed to validate client", e))?;
 
         if !client_exists {
-            return Err("Client not found".to_string());
+            return Err(AppError::not_found("client", &client_id));
         }
 
-        // Get the next order number (global sequence)
-        let order_number: i32 = sqlx::query_scalar::<_, i32>(
-            "SELECT COALESCE(MAX(order_number), 0) + 1 FROM orders"
-        )
-        .fetch_one(pool)
-        .await
-        .map_err(|e| format!("Failed to get next order number: {}", e))?;
-
-        // Get the next client requisition number for this specific client
-        let client_requisition_number: i32 = sqlx::query_scalar::<_, i32>(
-            "SELECT COALESCE(MAX(client_requisition_number), 0) + 1 FROM orders WHERE client_id = $1"
-        )
-        .bind(&client_id)
-        .fetch_one(pool)
-        .await
-        .map_err(|e| format!("Failed to get next client requisition number: {}", e))?;
+        // Numbers come from locked counters, so concurrent creates never collide
+        let order_year = now.year();
+        let sequences = crate::repositories::SequenceRepository;
+        let (number_scope, order_number) = sequences
+            .next_order_number(&mut *conn, yearly_reset.then_some(order_year))
+            .await?;
+        let client_requisition_number = sequences
+            .next_requisition_number(&mut *conn, &client_id)
+            .await?;
 
         let order = sqlx::query_as::<_, Order>(
             r#"
-            INSERT INTO orders (id, name, client_id, order_number, client_requisition_number, due_date, discount, iva, subtotal, total, status, debt, created_at, updated_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+            INSERT INTO orders (id, name, client_id, order_number, order_year, client_requisition_number, due_date, discount, iva, subtotal, total, status, debt, created_at, updated_at, number_scope)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
             RETURNING *
             "#,
         )
@@ -53,102 +66,102 @@ impl OrderRepository {
         .bind(&name)
         .bind(&client_id)
         .bind(order_number)
+        .bind(order_year)
         .bind(client_requisition_number)
         .bind(due_date)
         .bind(discount_value)
         .bind(iva)
-        .bind(0.0) // subtotal starts at 0
-        .bind(0.0) // total starts at 0
+        .bind(Decimal::ZERO) // subtotal starts at 0
+        .bind(Decimal::ZERO) // total starts at 0
         .bind(&status)
-        .bind(0.0) // debt starts at 0
+        .bind(Decimal::ZERO) // debt starts at 0
         .bind(now)
         .bind(now)
-        .fetch_one(pool)
+        .bind(&number_scope)
+        .fetch_one(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to create order: {}", e))?;
+        .map_err(|e| AppError::database("Failed to create order", e))?;
 
         // Update the client's total debt after creating the order
         let client_repo = crate::repositories::ClientRepository;
-        client_repo.update_client_debt(&client_id).await?;
+        client_repo.update_client_debt(&mut *conn, &client_id).await?;
 
         Ok(order)
     }
 
-    pub async fn get_by_id(&self, id: &str) -> Result<Option<Order>, String> {
-        let pool = get_db_pool()?;
-
+    pub async fn get_by_id(&self, conn: &mut PgConnection, id: &str) -> AppResult<Option<Order>> {
         let order = sqlx::query_as::<_, Order>(
             "SELECT * FROM orders WHERE id = $1"
         )
         .bind(id)
-        .fetch_optional(pool)
+        .fetch_optional(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to get order by id: {}", e))?;
+        .map_err(|e| AppError::database("Failed to get order by id", e))?;
 
         Ok(order)
     }
 
-    pub async fn get_by_client_id(&self, client_id: &str) -> Result<Vec<Order>, String> {
-        let pool = get_db_pool()?;
-
+    /// A client's orders, oldest first
+    pub async fn get_by_client_id(&self, conn: &mut PgConnection, client_id: &str) -> AppResult<Vec<Order>> {
         let orders = sqlx::query_as::<_, Order>(
-            "SELECT * FROM orders WHERE client_id = $1 ORDER BY created_at DESC"
+            "SELECT * FROM orders WHERE client_id = $1 ORDER BY created_at, id"
         )
         .bind(client_id)
-        .fetch_all(pool)
+        .fetch_all(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to get orders by client_id: {}", e))?;
+        .map_err(|e| AppError::database("Failed to get orders by client", e))?;
 
         Ok(orders)
     }
 
-    pub async fn get_by_date_range(&self, start_date: Date, end_date: Date) -> Result<Vec<Order>, String> {
-        let pool = get_db_pool()?;
-
-        let orders = sqlx::query_as::<_, Order>(
-            "SELECT * FROM orders WHERE due_date BETWEEN $1 AND $2 ORDER BY due_date"
+    /// Hands the order over to another client under `client_requisition_number`.
+    /// Client debts are left to the caller.
+    pub async fn move_to_client(&self, conn: &mut PgConnection, id: &str, client_id: &str, client_requisition_number: i32) -> AppResult<bool> {
+        let result = sqlx::query(
+            "UPDATE orders SET client_id = $2, client_requisition_number = $3, updated_at = $4 WHERE id = $1"
         )
-        .bind(start_date)
-        .bind(end_date)
-        .fetch_all(pool)
+        .bind(id)
+        .bind(client_id)
+        .bind(client_requisition_number)
+        .bind(OffsetDateTime::now_utc())
+        .execute(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to get orders by date range: {}", e))?;
+        .map_err(|e| AppError::database("Failed to move order", e))?;
 
-        Ok(orders)
+        Ok(result.rows_affected() > 0)
     }
 
-    pub async fn list(&self) -> Result<Vec<Order>, String> {
-        let pool = get_db_pool()?;
-
-        let orders = sqlx::query_as::<_, Order>(
-            "SELECT * FROM orders ORDER BY created_at DESC"
+    /// Locks the order row until the surrounding transaction ends, so concurrent
+    /// edits of the same order (garments, payments, totals) run one at a time.
+    pub async fn lock(&self, conn: &mut PgConnection, id: &str) -> AppResult<bool> {
+        let locked = sqlx::query_scalar::<_, String>(
+            "SELECT id FROM orders WHERE id = $1 FOR UPDATE"
         )
-        .fetch_all(pool)
+        .bind(id)
+        .fetch_optional(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to list orders: {}", e))?;
+        .map_err(|e| AppError::database("Failed to lock order", e))?;
 
-        Ok(orders)
+        Ok(locked.is_some())
     }
 
-    pub async fn update(&self, id: &str, name: Option<String>, client_id: Option<String>, due_date: Option<Date>, discount: Option<f64>, iva: Option<f64>, subtotal: Option<f64>, total: Option<f64>, status: Option<String>) -> Result<Option<Order>, String> {
-        let pool = get_db_pool()?;
+    pub async fn update(&self, conn: &mut PgConnection, id: &str, name: Option<String>, client_id: Option<String>, due_date: Option<Date>, discount: Option<Decimal>, iva: Option<Decimal>, status: Option<String>) -> AppResult<Option<Order>> {
         let now = OffsetDateTime::now_utc();
 
         // First, get the current order
-        let current_order = self.get_by_id(id).await?;
+        let current_order = self.get_by_id(&mut *conn, id).await?;
         let current_order = match current_order {
             Some(order) => order,
             None => return Ok(None),
         };
 
         // Use provided values or keep current values
+        let previous_client_id = current_order.client_id.clone();
         let updated_name = name.unwrap_or(current_order.name);
         let updated_client_id = client_id.clone().unwrap_or(current_order.client_id);
         let updated_due_date = due_date.unwrap_or(current_order.due_date);
         let updated_discount = discount.unwrap_or(current_order.discount);
         let updated_iva = iva.unwrap_or(current_order.iva);
-        let updated_subtotal = subtotal.unwrap_or(current_order.subtotal);
-        let updated_total = total.unwrap_or(current_order.total);
         let updated_status = status.unwrap_or(current_order.status);
 
         // Validate that client exists if client_id is being updated
@@ -157,19 +170,31 @@ impl OrderRepository {
                 "SELECT EXISTS(SELECT 1 FROM clients WHERE id = $1)"
             )
             .bind(&updated_client_id)
-            .fetch_one(pool)
+            .fetch_one(&mut *conn)
             .await
-            .map_err(|e| format!("Failed to validate client: {}", e))?;
+            .map_err(|e| AppError::database("Failed to validate client", e))?;
 
             if !client_exists {
-                return Err("Client not found".to_string());
+                return Err(AppError::not_found("client", &updated_client_id));
             }
         }
 
+        // Requisition numbers are per client, so a moved order takes the next one of its new client
+        let client_requisition_number = if updated_client_id != previous_client_id {
+            crate::repositories::SequenceRepository
+                .next_requisition_number(&mut *conn, &updated_client_id)
+                .await?
+        } else {
+            current_order.client_requisition_number
+        };
+
         let order = sqlx::query_as::<_, Order>(
             r#"
             UPDATE orders 
-            SET name = $2, client_id = $3, due_date = $4, discount = $5, iva = $6, subtotal = $7, total = $8, status = $9, updated_at = $10
+            SET name = $2, client_id = $3, due_date = $4, discount = $5, iva = $6, status = $7, updated_at = $8,
+                client_requisition_number = $9,
+                debt = CASE WHEN $7 = 'cancelled' THEN 0.0
+                            ELSE GREATEST(total - (SELECT COALESCE(SUM(amount), 0.0) FROM payments WHERE order_id = $1), 0.0) END
             WHERE id = $1
             RETURNING *
             "#,
@@ -180,63 +205,64 @@ impl OrderRepository {
         .bind(updated_due_date)
         .bind(updated_discount)
         .bind(updated_iva)
-        .bind(updated_subtotal)
-        .bind(updated_total)
         .bind(updated_status)
         .bind(now)
-        .fetch_optional(pool)
+        .bind(client_requisition_number)
+        .fetch_optional(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to update order: {}", e))?;
+        .map_err(|e| AppError::database("Failed to update order", e))?;
 
         // Update the client's total debt after updating the order
         if order.is_some() {
             let client_repo = crate::repositories::ClientRepository;
-            client_repo.update_client_debt(&updated_client_id).await?;
+            client_repo.update_client_debt(&mut *conn, &updated_client_id).await?;
+            if previous_client_id != updated_client_id {
+                client_repo.update_client_debt(&mut *conn, &previous_client_id).await?;
+            }
         }
 
         Ok(order)
     }
 
-    pub async fn delete(&self, id: &str) -> Result<bool, String> {
-        let pool = get_db_pool()?;
-
+    pub async fn delete(&self, conn: &mut PgConnection, id: &str) -> AppResult<bool> {
         // First, get the current order to get client_id before deleting
-        let current_order = self.get_by_id(id).await?;
+        let current_order = self.get_by_id(&mut *conn, id).await?;
         let client_id = match current_order {
             Some(order) => order.client_id,
-            None => return Err("Order not found".to_string()),
+            None => return Err(AppError::not_found("order", id)),
         };
 
         let result = sqlx::query("DELETE FROM orders WHERE id = $1")
             .bind(id)
-            .execute(pool)
+            .execute(&mut *conn)
             .await
-            .map_err(|e| format!("Failed to delete order: {}", e))?;
+            .map_err(|e| AppError::database("Failed to delete order", e))?;
 
         // Update the client's total debt after deleting the order
         if result.rows_affected() > 0 {
             let client_repo = crate::repositories::ClientRepository;
-            client_repo.update_client_debt(&client_id).await?;
+            client_repo.update_client_debt(&mut *conn, &client_id).await?;
         }
 
         Ok(result.rows_affected() > 0)
     }
 
-    pub async fn update_financial_values(&self, id: &str, subtotal: f64, total: f64) -> Result<bool, String> {
-        let pool     = get_db_pool()?;
+    pub async fn update_financial_values(&self, conn: &mut PgConnection, id: &str, subtotal: Decimal, total: Decimal) -> AppResult<bool> {
         let now = OffsetDateTime::now_utc();
 
         // First, get the current order to get client_id
-        let current_order = self.get_by_id(id).await?;
+        let current_order = self.get_by_id(&mut *conn, id).await?;
         let current_order = match current_order {
             Some(order) => order,
-            None => return Err("Order not found".to_string()),
+            None => return Err(AppError::not_found("order", id)),
         };
 
         let result = sqlx::query(
             r#"
             UPDATE orders 
-            SET subtotal = $2, total = $3, debt = $3, updated_at = $4
+            SET subtotal = $2, total = $3, updated_at = $4,
+                debt = CASE WHEN status = 'cancelled' THEN 0.0
+                            ELSE GREATEST($3 - (SELECT COALESCE(SUM(amount), 0.0) FROM payments WHERE order_id = $1), 0.0) END
             WHERE id = $1
             "#,
         )
@@ -244,135 +270,158 @@ impl OrderRepository {
         .bind(subtotal)
         .bind(total)
         .bind(now)
-        .execute(pool)
+        .execute(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to update order financial values: {}", e))?;
+        .map_err(|e| AppError::database("Failed to update order financial values", e))?;
 
         // Update the client's total debt
         if result.rows_affected() > 0 {
             let client_repo = crate::repositories::ClientRepository;
-            client_repo.update_client_debt(&current_order.client_id).await?;
+            client_repo.update_client_debt(&mut *conn, &current_order.client_id).await?;
         }
 
         Ok(result.rows_affected() > 0)
     }
 
-    pub async fn get_with_client_info(&self, id: &str) -> Result<Option<(Order, String, String)>, String> {
-        let pool = get_db_pool()?;
-
-        // First get the order
-        let order = self.get_by_id(id).await?;
-        
-        match order {
-            Some(order) => {
-                // Then get client info
-                let client_info = sqlx::query_as::<_, (String, String)>(
-                    "SELECT name, contact FROM clients WHERE id = $1"
-                )
-                .bind(&order.client_id)
-                .fetch_optional(pool)
-                .await
-                .map_err(|e| format!("Failed to get client info: {}", e))?;
-
-                match client_info {
-                    Some((name, contact)) => Ok(Some((order, name, contact))),
-                    None => Err("Client not found for order".to_string()),
-                }
-            }
-            None => Ok(None),
-        }
+    pub async fn get_with_client_info(&self, conn: &mut PgConnection, id: &str) -> AppResult<Option<(Order, String, String)>> {
+        let row = sqlx::query_as::<_, OrderWithClientRow>(
+            &format!("{} WHERE o.id = $1", ORDER_WITH_CLIENT_SELECT)
+        )
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(|e| AppError::database("Failed to get order with client info", e))?;
+
+        Ok(row.map(OrderWithClientRow::into_tuple))
     }
 
-    pub async fn list_with_client_info(&self) -> Result<Vec<(Order, String, String)>, String> {
-        let pool = get_db_pool()?;
+    pub async fn list_with_client_info(&self, conn: &mut PgConnection) -> AppResult<Vec<(Order, String, String)>> {
+        let rows = sqlx::query_as::<_, OrderWithClientRow>(
+            &format!("{} ORDER BY o.created_at DESC", ORDER_WITH_CLIENT_SELECT)
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| AppError::database("Failed to list orders with client info", e))?;
 
-        // First get all orders
-        let orders = self.list().await?;
-        let mut results = Vec::new();
+        Ok(rows.into_iter().map(OrderWithClientRow::into_tuple).collect())
+    }
 
-        // Then get client info for each order
-        for order in orders {
-            let client_info = sqlx::query_as::<_, (String, String)>(
-                "SELECT name, contact FROM clients WHERE id = $1"
-            )
-            .bind(&order.client_id)
-            .fetch_optional(pool)
-            .await
-            .map_err(|e| format!("Failed to get client info: {}", e))?;
+    pub async fn get_by_client_id_with_client_info(&self, conn: &mut PgConnection, client_id: &str) -> AppResult<Vec<(Order, String, String)>> {
+        let rows = sqlx::query_as::<_, OrderWithClientRow>(
+            &format!("{} WHERE o.client_id = $1 ORDER BY o.created_at DESC", ORDER_WITH_CLIENT_SELECT)
+        )
+        .bind(client_id)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| AppError::database("Failed to get orders by client_id", e))?;
 
-            match client_info {
-                Some((name, contact)) => results.push((order, name, contact)),
-                None => return Err(format!("Client not found for order {}", order.id)),
-            }
-        }
+        Ok(rows.into_iter().map(OrderWithClientRow::into_tuple).collect())
+    }
 
-        Ok(results)
+    pub async fn get_by_date_range_with_client_info(&self, conn: &mut PgConnection, start_date: Date, end_date: Date) -> AppResult<Vec<(Order, String, String)>> {
+        let rows = sqlx::query_as::<_, OrderWithClientRow>(
+            &format!("{} WHERE o.due_date BETWEEN $1 AND $2 ORDER BY o.due_date", ORDER_WITH_CLIENT_SELECT)
+        )
+        .bind(start_date)
+        .bind(end_date)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| AppError::database("Failed to get orders by date range", e))?;
+
+        Ok(rows.into_iter().map(OrderWithClientRow::into_tuple).collect())
     }
 
-    pub async fn get_by_client_id_with_client_info(&self, client_id: &str) -> Result<Vec<(Order, String, String)>, String> {
-        let pool = get_db_pool()?;
+    /// One page of orders with client info plus the number of orders matching the filter.
+    pub async fn list_page_with_client_info(&self, conn: &mut PgConnection, filter: &OrderFilter, page: &PageRequest<OrderSortField>) -> AppResult<(Vec<(Order, String, String)>, i64)> {
+        let mut count_query = QueryBuilder::<Postgres>::new(
+            "SELECT COUNT(*) FROM orders o JOIN clients c ON c.id = o.client_id WHERE TRUE"
+        );
+        Self::push_filter(&mut count_query, filter);
+        let total: i64 = count_query
+            .build_query_scalar()
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(|e| AppError::database("Failed to count orders", e))?;
+
+        let mut query = QueryBuilder::<Postgres>::new(ORDER_WITH_CLIENT_SELECT);
+        query.push(" WHERE TRUE");
+        Self::push_filter(&mut query, filter);
+        let sort = page.sort_by.unwrap_or(OrderSortField::CreatedAt);
+        let direction = page.direction.as_sql();
+        let order_by: Vec<String> = sort.columns().iter()
+            .map(|column| format!("{} {}", column, direction))
+            .collect();
+        query.push(format!(" ORDER BY {}, o.id", order_by.join(", ")));
+        query.push(" LIMIT ").push_bind(page.limit());
+        query.push(" OFFSET ").push_bind(page.offset());
+
+        let rows = query
+            .build_query_as::<OrderWithClientRow>()
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(|e| AppError::database("Failed to list orders", e))?;
 
-        // First get orders for the client
-        let orders = self.get_by_client_id(client_id).await?;
-        
-        if orders.is_empty() {
-            return Ok(vec![]);
-        }
+        Ok((rows.into_iter().map(OrderWithClientRow::into_tuple).collect(), total))
+    }
 
-        // Get client info once
-        let client_info = sqlx::query_as::<_, (String, String)>(
-            "SELECT name, contact FROM clients WHERE id = $1"
-        )
-        .bind(client_id)
-        .fetch_optional(pool)
-        .await
-        .map_err(|e| format!("Failed to get client info: {}", e))?;
-
-        match client_info {
-            Some((name, contact)) => {
-                let results = orders.into_iter()
-                    .map(|order| (order, name.clone(), contact.clone()))
-                    .collect();
-                Ok(results)
-            }
-            None => Err("Client not found".to_string()),
+    fn push_filter(query: &mut QueryBuilder<'_, Postgres>, filter: &OrderFilter) {
+        if let Some(status) = filter.status {
+            query.push(" AND o.status = ").push_bind(status.as_str());
+        }
+        if let Some(client_id) = &filter.client_id {
+            query.push(" AND o.client_id = ").push_bind(client_id.clone());
+        }
+        if let Some(due_from) = filter.due_from {
+            query.push(" AND o.due_date >= ").push_bind(due_from);
+        }
+        if let Some(due_to) = filter.due_to {
+            query.push(" AND o.due_date <= ").push_bind(due_to);
+        }
+        match filter.has_debt {
+            Some(true) => { query.push(" AND o.debt > 0"); }
+            Some(false) => { query.push(" AND o.debt <= 0"); }
+            None => {}
+        }
+        if let Some(search) = filter.search.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+            let pattern = format!("%{}%", search);
+            query.push(" AND (o.name ILIKE ").push_bind(pattern.clone());
+            query.push(" OR c.name ILIKE ").push_bind(pattern);
+            query.push(" OR o.order_number::text = ").push_bind(search.to_string());
+            query.push(")");
         }
     }
 
-    pub async fn pay_debt(&self, id: &str, payment_amount: f64) -> Result<bool, String> {
-        let pool = get_db_pool()?;
+    /// Recomputes the order debt as total minus the sum of its payments.
+    /// Payments above the total (e.g. a deposit taken before the garments were
+    /// entered) leave the debt at 0 rather than negative. Cancelled orders
+    /// owe nothing.
+    pub async fn refresh_debt(&self, conn: &mut PgConnection, id: &str) -> AppResult<bool> {
         let now = OffsetDateTime::now_utc();
 
-        // First, get the current debt
-        let current_order = self.get_by_id(id).await?;
-        let current_order = match current_order {
-            Some(order) => order,
-            None => return Err("Order not found".to_string()),
-        };
-
-        // Calculate new debt (cannot be less than 0)
-        let new_debt = (current_order.debt - payment_amount).max(0.0);
-
-        let result = sqlx::query(
+        let client_id = sqlx::query_scalar::<_, String>(
             r#"
             UPDATE orders 
-            SET debt = $2, updated_at = $3
+            SET debt = CASE WHEN status = 'cancelled' THEN 0.0
+                            ELSE GREATEST(total - (SELECT COALESCE(SUM(amount), 0.0) FROM payments WHERE order_id = $1), 0.0) END,
+                updated_at = $2
             WHERE id = $1
+            RETURNING client_id
             "#,
         )
         .bind(id)
-        .bind(new_debt)
         .bind(now)
-        .execute(pool)
+        .fetch_optional(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to update order debt: {}", e))?;
+        .map_err(|e| AppError::database("Failed to update order debt", e))?;
 
         // Update the client's total debt after updating the order
-        if result.rows_affected() > 0 {
-            let client_repo = crate::repositories::ClientRepository;
-            client_repo.update_client_debt(&current_order.client_id).await?;
+        match client_id {
+            Some(client_id) => {
+                let client_repo = crate::repositories::ClientRepository;
+                client_repo.update_client_debt(&mut *conn, &client_id).await?;
+                Ok(true)
+            }
+            None => Ok(false),
         }
-
-        Ok(result.rows_affected() > 0)
     }
 }
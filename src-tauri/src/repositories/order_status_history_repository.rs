@@ -0,0 +1,86 @@
+use crate::models::OrderStatusChange;
+use sqlx::{FromRow, PgConnection};
+use time::OffsetDateTime;
+use uuid::Uuid;
+use crate::error::{AppError, AppResult};
+
+#[derive(FromRow)]
+struct StatusChangeRow {
+    #[sqlx(flatten)]
+    change: OrderStatusChange,
+    changed_by_login: Option<String>,
+}
+
+pub struct OrderStatusHistoryRepository;
+
+impl OrderStatusHistoryRepository {
+    pub async fn record(&self,
+        conn: &mut PgConnection,
+        order_id: &str,
+        from_status: Option<&str>,
+        to_status: &str,
+        changed_by: Option<String>,
+        note: Option<String>
+    ) -> AppResult<OrderStatusChange> {
+        let id = Uuid::new_v4().to_string();
+        let now = OffsetDateTime::now_utc();
+
+        let change = sqlx::query_as::<_, OrderStatusChange>(
+            r#"
+            INSERT INTO order_status_history (id, order_id, from_status, to_status, changed_by, note, changed_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+            "#,
+        )
+        .bind(&id)
+        .bind(order_id)
+        .bind(from_status)
+        .bind(to_status)
+        .bind(&changed_by)
+        .bind(&note)
+        .bind(now)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| AppError::database("Failed to record order status change", e))?;
+
+        Ok(change)
+    }
+
+    /// Changes in the order they happened, with the login of whoever made them.
+    pub async fn get_by_order_id(&self, conn: &mut PgConnection, order_id: &str) -> AppResult<Vec<(OrderStatusChange, Option<String>)>> {
+        let rows = sqlx::query_as::<_, StatusChangeRow>(
+            r#"
+            SELECT h.*, u.login AS changed_by_login
+            FROM order_status_history h
+            LEFT JOIN users u ON u.id = h.changed_by
+            WHERE h.order_id = $1
+            ORDER BY h.changed_at, h.id
+            "#,
+        )
+        .bind(order_id)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| AppError::database("Failed to get order status history", e))?;
+
+        Ok(rows.into_iter().map(|row| (row.change, row.changed_by_login)).collect())
+    }
+
+    /// Gives every order without history an entry for its current status.
+    /// Same statement as migration 0006; used after restoring older backups.
+    pub async fn backfill_initial_statuses(&self, conn: &mut PgConnection) -> AppResult<u64> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO order_status_history (id, order_id, from_status, to_status, changed_by, note, changed_at)
+            SELECT gen_random_uuid()::text, o.id, NULL, o.status, NULL,
+                   'Estado anterior ao registo de histórico', o.updated_at
+            FROM orders o
+            WHERE NOT EXISTS (SELECT 1 FROM order_status_history h WHERE h.order_id = o.id)
+            "#,
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| AppError::database("Failed to backfill order status history", e))?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,107 @@
+use crate::models::Payment;
+use sqlx::PgConnection;
+use time::{Date, OffsetDateTime};
+use uuid::Uuid;
+use rust_decimal::Decimal;
+use crate::error::{AppError, AppResult};
+use crate::migrations::OPENING_BALANCES_SQL;
+
+pub struct PaymentRepository;
+
+impl PaymentRepository {
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create(&self,
+        conn: &mut PgConnection,
+        order_id: String,
+        amount: Decimal,
+        payment_date: Date,
+        method: String,
+        reference: Option<String>,
+        received_by: Option<String>
+    ) -> AppResult<Payment> {
+        let id = Uuid::new_v4().to_string();
+        let now = OffsetDateTime::now_utc();
+
+        let payment = sqlx::query_as::<_, Payment>(
+            r#"
+            INSERT INTO payments (id, order_id, amount, payment_date, method, reference, received_by, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING *
+            "#,
+        )
+        .bind(&id)
+        .bind(&order_id)
+        .bind(amount)
+        .bind(payment_date)
+        .bind(&method)
+        .bind(&reference)
+        .bind(&received_by)
+        .bind(now)
+        .bind(now)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| AppError::database("Failed to create payment", e))?;
+
+        Ok(payment)
+    }
+
+    pub async fn get_by_id(&self, conn: &mut PgConnection, id: &str) -> AppResult<Option<Payment>> {
+        let payment = sqlx::query_as::<_, Payment>(
+            "SELECT * FROM payments WHERE id = $1"
+        )
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(|e| AppError::database("Failed to get payment by id", e))?;
+
+        Ok(payment)
+    }
+
+    pub async fn get_by_order_id(&self, conn: &mut PgConnection, order_id: &str) -> AppResult<Vec<Payment>> {
+        let payments = sqlx::query_as::<_, Payment>(
+            "SELECT * FROM payments WHERE order_id = $1 ORDER BY payment_date ASC, created_at ASC"
+        )
+        .bind(order_id)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| AppError::database("Failed to get payments by order_id", e))?;
+
+        Ok(payments)
+    }
+
+    pub async fn get_total_paid(&self, conn: &mut PgConnection, order_id: &str) -> AppResult<Decimal> {
+        let total_paid = sqlx::query_scalar::<_, Decimal>(
+            "SELECT COALESCE(SUM(amount), 0.0) FROM payments WHERE order_id = $1"
+        )
+        .bind(order_id)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| AppError::database("Failed to calculate total paid", e))?;
+
+        Ok(total_paid)
+    }
+
+    pub async fn delete(&self, conn: &mut PgConnection, id: &str) -> AppResult<bool> {
+        let result = sqlx::query("DELETE FROM payments WHERE id = $1")
+            .bind(id)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| AppError::database("Failed to delete payment", e))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Records whatever was already paid on orders created before the ledger
+    /// existed (`total - debt`) as a single opening payment, so that deriving
+    /// debt from payments does not reset those orders to fully unpaid.
+    /// Orders that already have payments are left untouched. Runs the same
+    /// statement as migration 0002.
+    pub async fn backfill_opening_balances(&self, conn: &mut PgConnection) -> AppResult<u64> {
+        let result = sqlx::query(OPENING_BALANCES_SQL)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| AppError::database("Failed to backfill opening balances", e))?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,198 @@
+use sqlx::PgConnection;
+use crate::error::{AppError, AppResult};
+
+/// Hands out order and requisition numbers from the `number_sequences` table.
+/// The counter row stays locked until the caller's transaction ends, so two
+/// workstations can never get the same number and a rolled back order does
+/// not leave a gap.
+pub struct SequenceRepository;
+
+impl SequenceRepository {
+    /// Next order number, either global or restarting every `year`, with
+    /// the scope it was allocated in (stored as `orders.number_scope`).
+    pub async fn next_order_number(&self, conn: &mut PgConnection, year: Option<i32>) -> AppResult<(String, i32)> {
+        let (scope, current_max) = match year {
+            Some(year) => {
+                let current_max = sqlx::query_scalar::<_, i32>(
+                    "SELECT COALESCE(MAX(order_number), 0) FROM orders WHERE order_year = $1"
+                )
+                .bind(year)
+                .fetch_one(&mut *conn)
+                .await
+                .map_err(|e| AppError::database("Failed to get current order number", e))?;
+
+                (format!("order:{}", year), current_max)
+            }
+            None => {
+                let current_max = sqlx::query_scalar::<_, i32>(
+                    "SELECT COALESCE(MAX(order_number), 0) FROM orders"
+                )
+                .fetch_one(&mut *conn)
+                .await
+                .map_err(|e| AppError::database("Failed to get current order number", e))?;
+
+                ("order".to_string(), current_max)
+            }
+        };
+
+        let number = self.allocate(conn, &scope, current_max).await?;
+        Ok((scope, number))
+    }
+
+    pub async fn next_requisition_number(&self, conn: &mut PgConnection, client_id: &str) -> AppResult<i32> {
+        let current_max = sqlx::query_scalar::<_, i32>(
+            "SELECT COALESCE(MAX(client_requisition_number), 0) FROM orders WHERE client_id = $1"
+        )
+        .bind(client_id)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| AppError::database("Failed to get current requisition number", e))?;
+
+        self.allocate(conn, &format!("requisition:{}", client_id), current_max).await
+    }
+
+    /// Raises a client's requisition counter to the numbers its orders use,
+    /// locking it until the transaction ends. Called before and after orders
+    /// are moved in with their own numbers, so a concurrent order waits and
+    /// then counts past them.
+    pub async fn sync_requisition_counter(&self, conn: &mut PgConnection, client_id: &str) -> AppResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO number_sequences (scope, last_value, updated_at)
+            SELECT $1, COALESCE(MAX(client_requisition_number), 0), NOW()
+            FROM orders WHERE client_id = $2
+            ON CONFLICT (scope) DO UPDATE
+            SET last_value = GREATEST(number_sequences.last_value, EXCLUDED.last_value),
+                updated_at = NOW()
+            "#,
+        )
+        .bind(format!("requisition:{}", client_id))
+        .bind(client_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| AppError::database("Failed to update requisition counter", e))?;
+
+        Ok(())
+    }
+
+    /// Drops the requisition counter of a client that no longer exists
+    pub async fn delete_requisition_counter(&self, conn: &mut PgConnection, client_id: &str) -> AppResult<()> {
+        sqlx::query("DELETE FROM number_sequences WHERE scope = $1")
+            .bind(format!("requisition:{}", client_id))
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| AppError::database("Failed to delete requisition counter", e))?;
+
+        Ok(())
+    }
+
+    // The counter never goes below the numbers already in use, which keeps it
+    // correct after a restore or a change of numbering mode.
+    async fn allocate(&self, conn: &mut PgConnection, scope: &str, current_max: i32) -> AppResult<i32> {
+        sqlx::query_scalar::<_, i32>(
+            r#"
+            INSERT INTO number_sequences (scope, last_value, updated_at)
+            VALUES ($1, $2 + 1, NOW())
+            ON CONFLICT (scope) DO UPDATE
+            SET last_value = GREATEST(number_sequences.last_value, $2) + 1,
+                updated_at = NOW()
+            RETURNING last_value
+            "#,
+        )
+        .bind(scope)
+        .bind(current_max)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| AppError::database(&format!("Failed to allocate number for {}", scope), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migrations::run_migrations;
+    use crate::test_support::with_empty_database;
+    use sqlx::{Acquire, PgPool};
+    use std::time::Duration;
+
+    async fn migrated(pool: &PgPool) -> sqlx::pool::PoolConnection<sqlx::Postgres> {
+        run_migrations(pool).await.unwrap();
+        pool.acquire().await.unwrap()
+    }
+
+    #[test]
+    fn order_numbers_are_consecutive_per_scope() {
+        with_empty_database(|pool| async move {
+            let mut conn = migrated(&pool).await;
+            let repository = SequenceRepository;
+
+            assert_eq!(repository.next_order_number(&mut conn, Some(2025)).await.unwrap(), ("order:2025".to_string(), 1));
+            assert_eq!(repository.next_order_number(&mut conn, Some(2025)).await.unwrap(), ("order:2025".to_string(), 2));
+            assert_eq!(repository.next_order_number(&mut conn, Some(2026)).await.unwrap(), ("order:2026".to_string(), 1));
+            assert_eq!(repository.next_order_number(&mut conn, None).await.unwrap(), ("order".to_string(), 1));
+        });
+    }
+
+    #[test]
+    fn requisition_numbers_are_counted_per_client() {
+        with_empty_database(|pool| async move {
+            let mut conn = migrated(&pool).await;
+            let repository = SequenceRepository;
+
+            assert_eq!(repository.next_requisition_number(&mut conn, "client-a").await.unwrap(), 1);
+            assert_eq!(repository.next_requisition_number(&mut conn, "client-a").await.unwrap(), 2);
+            assert_eq!(repository.next_requisition_number(&mut conn, "client-b").await.unwrap(), 1);
+
+            repository.delete_requisition_counter(&mut conn, "client-a").await.unwrap();
+            assert_eq!(repository.next_requisition_number(&mut conn, "client-a").await.unwrap(), 1);
+        });
+    }
+
+    #[test]
+    fn counters_never_go_below_the_numbers_in_use() {
+        with_empty_database(|pool| async move {
+            let mut conn = migrated(&pool).await;
+            let repository = SequenceRepository;
+
+            assert_eq!(repository.allocate(&mut conn, "order", 5).await.unwrap(), 6);
+            assert_eq!(repository.allocate(&mut conn, "order", 0).await.unwrap(), 7);
+            assert_eq!(repository.allocate(&mut conn, "order", 10).await.unwrap(), 11);
+        });
+    }
+
+    #[test]
+    fn a_rolled_back_number_is_handed_out_again() {
+        with_empty_database(|pool| async move {
+            let mut conn = migrated(&pool).await;
+            let repository = SequenceRepository;
+
+            let mut tx = conn.begin().await.unwrap();
+            assert_eq!(repository.next_order_number(&mut tx, None).await.unwrap().1, 1);
+            tx.rollback().await.unwrap();
+
+            assert_eq!(repository.next_order_number(&mut conn, None).await.unwrap().1, 1);
+        });
+    }
+
+    #[test]
+    fn concurrent_transactions_wait_for_the_counter() {
+        with_empty_database(|pool| async move {
+            let mut first = migrated(&pool).await;
+            let mut tx = first.begin().await.unwrap();
+            assert_eq!(SequenceRepository.next_order_number(&mut tx, None).await.unwrap().1, 1);
+
+            let second = tokio::spawn({
+                let pool = pool.clone();
+                async move {
+                    let mut conn = pool.acquire().await.unwrap();
+                    SequenceRepository.next_order_number(&mut conn, None).await.unwrap().1
+                }
+            });
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            assert!(!second.is_finished(), "the counter row should still be locked");
+
+            tx.commit().await.unwrap();
+            assert_eq!(second.await.unwrap(), 2);
+        });
+    }
+}
@@ -1,91 +1,185 @@
 use crate::models::User;
-use crate::database::get_db_pool;
-use sqlx::Row;
+use sqlx::PgConnection;
+use time::OffsetDateTime;
+use crate::error::{AppError, AppResult};
 
 pub struct UserRepository;
 
 impl UserRepository {
-    pub async fn create_user(user: &User) -> Result<User, String> {
-        let pool = get_db_pool()?;
-        
-        let result = sqlx::query(
+    pub async fn create(&self, conn: &mut PgConnection, user: &User) -> AppResult<User> {
+        sqlx::query_as::<_, User>(
             r#"
-            INSERT INTO users (id, login, password, role, created_at, updated_at)
-            VALUES ($1, $2, $3, $4, $5, $6)
-            RETURNING *
+            INSERT INTO users (id, login, password, role, active, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id, login, password, role, active, created_at, updated_at
             "#,
         )
         .bind(&user.id)
         .bind(&user.login)
         .bind(&user.password)
         .bind(&user.role)
+        .bind(user.active)
         .bind(user.created_at)
         .bind(user.updated_at)
-        .fetch_one(pool)
+        .fetch_one(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to create user: {}", e))?;
-
-        Ok(User {
-            id: result.get("id"),
-            login: result.get("login"),
-            password: result.get("password"),
-            role: result.get("role"),
-            created_at: result.get("created_at"),
-            updated_at: result.get("updated_at"),
-        })
+        .map_err(|e| AppError::database("Failed to create user", e))
     }
 
-    pub async fn find_by_login_and_password(login: &str, password: &str) -> Result<Option<User>, String> {
-        let pool = get_db_pool()?;
-        
-        let result = sqlx::query_as::<_, User>(
+    /// Inserts the user only while the table is still empty. Run it after
+    /// `lock`, so two windows racing through first-run setup cannot both succeed.
+    pub async fn create_first(&self, conn: &mut PgConnection, user: &User) -> AppResult<Option<User>> {
+        sqlx::query_as::<_, User>(
             r#"
-            SELECT id, login, password, role, created_at, updated_at
-            FROM users 
-            WHERE login = $1 AND password = $2
+            INSERT INTO users (id, login, password, role, created_at, updated_at)
+            SELECT $1, $2, $3, $4, $5, $6
+            WHERE NOT EXISTS (SELECT 1 FROM users)
+            RETURNING id, login, password, role, active, created_at, updated_at
             "#,
         )
-        .bind(login)
-        .bind(password)
-        .fetch_optional(pool)
+        .bind(&user.id)
+        .bind(&user.login)
+        .bind(&user.password)
+        .bind(&user.role)
+        .bind(user.created_at)
+        .bind(user.updated_at)
+        .fetch_optional(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to find user: {}", e))?;
-
-        Ok(result)
+        .map_err(|e| AppError::database("Failed to create first user", e))
     }
 
-    pub async fn find_by_login(login: &str) -> Result<Option<User>, String> {
-        let pool = get_db_pool()?;
-        
-        let result = sqlx::query_as::<_, User>(
+    pub async fn get_by_login(&self, conn: &mut PgConnection, login: &str) -> AppResult<Option<User>> {
+        sqlx::query_as::<_, User>(
             r#"
-            SELECT id, login, password, role, created_at, updated_at
-            FROM users 
+            SELECT id, login, password, role, active, created_at, updated_at
+            FROM users
             WHERE login = $1
             "#,
         )
         .bind(login)
-        .fetch_optional(pool)
+        .fetch_optional(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to find user by login: {}", e))?;
+        .map_err(|e| AppError::database("Failed to find user by login", e))
+    }
 
-        Ok(result)
+    pub async fn get_by_id(&self, conn: &mut PgConnection, id: &str) -> AppResult<Option<User>> {
+        sqlx::query_as::<_, User>(
+            r#"
+            SELECT id, login, password, role, active, created_at, updated_at
+            FROM users
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(|e| AppError::database("Failed to find user by id", e))
     }
 
-    pub async fn list_users() -> Result<Vec<User>, String> {
-        let pool = get_db_pool()?;
-        
-        let users = sqlx::query_as::<_, User>(
+    pub async fn list(&self, conn: &mut PgConnection) -> AppResult<Vec<User>> {
+        sqlx::query_as::<_, User>(
             r#"
-            SELECT id, login, password, role, created_at, updated_at
-            FROM users 
+            SELECT id, login, password, role, active, created_at, updated_at
+            FROM users
             ORDER BY created_at DESC
             "#,
         )
-        .fetch_all(pool)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| AppError::database("Failed to list users", e))
+    }
+
+    pub async fn count(&self, conn: &mut PgConnection) -> AppResult<i64> {
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM users")
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(|e| AppError::database("Failed to count users", e))
+    }
+
+    /// Serializes changes to the set of users (first-run setup, admin role,
+    /// active flag, deletion) until the surrounding transaction ends.
+    pub async fn lock(&self, conn: &mut PgConnection) -> AppResult<()> {
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext('editart_users'))")
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| AppError::database("Failed to lock users table", e))?;
+
+        Ok(())
+    }
+
+    /// Fails when the change would leave no active admin. Must run after `lock`.
+    pub async fn ensure_other_active_admin(&self, conn: &mut PgConnection, id: &str) -> AppResult<()> {
+        let other_admins = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM users WHERE role = 'admin' AND active AND id <> $1"
+        )
+        .bind(id)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| AppError::database("Failed to count admins", e))?;
+
+        if other_admins == 0 {
+            return Err(AppError::Conflict("At least one active admin must remain".to_string()));
+        }
+
+        Ok(())
+    }
+
+    pub async fn update(&self, conn: &mut PgConnection, id: &str, login: &str, role: &str) -> AppResult<Option<User>> {
+        sqlx::query_as::<_, User>(
+            r#"
+            UPDATE users
+            SET login = $2, role = $3, updated_at = $4
+            WHERE id = $1
+            RETURNING id, login, password, role, active, created_at, updated_at
+            "#,
+        )
+        .bind(id)
+        .bind(login)
+        .bind(role)
+        .bind(OffsetDateTime::now_utc())
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(|e| AppError::database("Failed to update user", e))
+    }
+
+    pub async fn set_active(&self, conn: &mut PgConnection, id: &str, active: bool) -> AppResult<Option<User>> {
+        sqlx::query_as::<_, User>(
+            r#"
+            UPDATE users
+            SET active = $2, updated_at = $3
+            WHERE id = $1
+            RETURNING id, login, password, role, active, created_at, updated_at
+            "#,
+        )
+        .bind(id)
+        .bind(active)
+        .bind(OffsetDateTime::now_utc())
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(|e| AppError::database("Failed to update user status", e))
+    }
+
+    pub async fn update_password(&self, conn: &mut PgConnection, id: &str, password_hash: &str) -> AppResult<bool> {
+        let result = sqlx::query(
+            "UPDATE users SET password = $2, updated_at = $3 WHERE id = $1"
+        )
+        .bind(id)
+        .bind(password_hash)
+        .bind(OffsetDateTime::now_utc())
+        .execute(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to list users: {}", e))?;
+        .map_err(|e| AppError::database("Failed to update user password", e))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn delete(&self, conn: &mut PgConnection, id: &str) -> AppResult<bool> {
+        let result = sqlx::query("DELETE FROM users WHERE id = $1")
+            .bind(id)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| AppError::database("Failed to delete user", e))?;
 
-        Ok(users)
+        Ok(result.rows_affected() > 0)
     }
 }
@@ -0,0 +1,284 @@
+use crate::config::get_data_dir;
+use crate::error::{AppError, AppResult};
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Service name secrets are filed under in the OS secret store
+const KEYRING_SERVICE: &str = "editart";
+const SECRETS_FILE: &str = "secrets.json";
+const SECRETS_KEY_FILE: &str = "secrets.key";
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+
+/// Secrets already read, so the OS store is not asked on every config load
+static CACHE: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SecretStore {
+    /// Keychain, Credential Manager or Secret Service
+    #[serde(rename = "keyring")]
+    Keyring,
+    /// `secrets.json` next to the config, for machines without a secret store.
+    /// Only obfuscated: its key sits in the same directory.
+    #[serde(rename = "file")]
+    File,
+}
+
+/// What the config file keeps instead of a secret
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SecretRef {
+    pub store: SecretStore,
+    pub key: String,
+}
+
+/// Keeps `value` in the OS secret store, or obfuscated in `secrets.json` when
+/// the store is not available.
+pub fn store_secret(key: &str, value: &str) -> AppResult<SecretRef> {
+    let store = match keyring_entry(key).and_then(|entry| {
+        entry.set_password(value)
+            .map_err(|e| AppError::Config(format!("Failed to save secret in the OS secret store: {}", e)))
+    }) {
+        Ok(()) => {
+            // A copy left over from before the store was available
+            if let Err(e) = get_data_dir().and_then(|dir| remove_file_secret(&dir, key)) {
+                log::warn!("{}", e);
+            }
+            SecretStore::Keyring
+        }
+        Err(e) => {
+            log::warn!("{}; keeping it obfuscated in {} instead, readable by anyone who can read the data directory", e, SECRETS_FILE);
+            write_file_secret(&get_data_dir()?, key, value)?;
+            SecretStore::File
+        }
+    };
+
+    CACHE.lock().unwrap_or_else(|e| e.into_inner()).insert(key.to_string(), value.to_string());
+    Ok(SecretRef { store, key: key.to_string() })
+}
+
+pub fn load_secret(secret: &SecretRef) -> AppResult<String> {
+    if let Some(value) = CACHE.lock().unwrap_or_else(|e| e.into_inner()).get(&secret.key) {
+        return Ok(value.clone());
+    }
+
+    let value = match secret.store {
+        SecretStore::Keyring => keyring_entry(&secret.key)?
+            .get_password()
+            .map_err(|e| AppError::Config(format!("Failed to read secret from the OS secret store: {}", e)))?,
+        SecretStore::File => read_file_secret(&get_data_dir()?, &secret.key)?,
+    };
+
+    CACHE.lock().unwrap_or_else(|e| e.into_inner()).insert(secret.key.clone(), value.clone());
+    Ok(value)
+}
+
+pub fn delete_secret(secret: &SecretRef) -> AppResult<()> {
+    CACHE.lock().unwrap_or_else(|e| e.into_inner()).remove(&secret.key);
+    match secret.store {
+        SecretStore::Keyring => match keyring_entry(&secret.key)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(AppError::Config(format!("Failed to delete secret from the OS secret store: {}", e))),
+        },
+        SecretStore::File => remove_file_secret(&get_data_dir()?, &secret.key),
+    }
+}
+
+fn keyring_entry(key: &str) -> AppResult<keyring::Entry> {
+    keyring::Entry::new(KEYRING_SERVICE, key)
+        .map_err(|e| AppError::Config(format!("Failed to open the OS secret store: {}", e)))
+}
+
+/// One entry of `secrets.json`, hex encoded. The secret's key is the
+/// associated data, so entries cannot be swapped around.
+#[derive(Debug, Serialize, Deserialize)]
+struct EncryptedSecret {
+    nonce: String,
+    ciphertext: String,
+}
+
+fn read_file_secret(dir: &Path, key: &str) -> AppResult<String> {
+    let secrets = read_secrets_file(dir)?;
+    let entry = secrets.get(key)
+        .ok_or_else(|| AppError::Config(format!("Secret {} not found in {}", key, SECRETS_FILE)))?;
+
+    let nonce = from_hex(&entry.nonce)?;
+    if nonce.len() != NONCE_LEN {
+        return Err(AppError::Config(format!("Secret {} in {} is corrupted", key, SECRETS_FILE)));
+    }
+    let ciphertext = from_hex(&entry.ciphertext)?;
+    let plaintext = obfuscation_cipher(dir)?
+        .decrypt(Nonce::from_slice(&nonce), Payload { msg: &ciphertext, aad: key.as_bytes() })
+        .map_err(|_| AppError::Config(format!("Failed to decrypt secret {}: {} does not match {}", key, SECRETS_FILE, SECRETS_KEY_FILE)))?;
+
+    String::from_utf8(plaintext)
+        .map_err(|e| AppError::Config(format!("Failed to decrypt secret {}: {}", key, e)))
+}
+
+fn write_file_secret(dir: &Path, key: &str, value: &str) -> AppResult<()> {
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+    let ciphertext = obfuscation_cipher(dir)?
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: value.as_bytes(), aad: key.as_bytes() })
+        .map_err(|e| AppError::Config(format!("Failed to encrypt secret: {}", e)))?;
+
+    let mut secrets = read_secrets_file(dir)?;
+    secrets.insert(key.to_string(), EncryptedSecret {
+        nonce: to_hex(&nonce),
+        ciphertext: to_hex(&ciphertext),
+    });
+    write_secrets_file(dir, &secrets)
+}
+
+fn remove_file_secret(dir: &Path, key: &str) -> AppResult<()> {
+    let mut secrets = read_secrets_file(dir)?;
+    if secrets.remove(key).is_some() {
+        write_secrets_file(dir, &secrets)?;
+    }
+    Ok(())
+}
+
+fn read_secrets_file(dir: &Path) -> AppResult<BTreeMap<String, EncryptedSecret>> {
+    let path = dir.join(SECRETS_FILE);
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let content = fs::read_to_string(&path)
+        .map_err(|e| AppError::io(&format!("Failed to read {}", SECRETS_FILE), e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| AppError::Config(format!("Failed to parse {}: {}", SECRETS_FILE, e)))
+}
+
+fn write_secrets_file(dir: &Path, secrets: &BTreeMap<String, EncryptedSecret>) -> AppResult<()> {
+    let content = serde_json::to_string_pretty(secrets)
+        .map_err(|e| AppError::Config(format!("Failed to serialize {}: {}", SECRETS_FILE, e)))?;
+    write_private(&dir.join(SECRETS_FILE), content.as_bytes())
+}
+
+/// Obfuscation, not protection: the key for `secrets.json` is created on
+/// first use in the same directory. It keeps the password out of a config
+/// file that gets copied or shown around, but anyone who can read the data
+/// directory can read the secrets too. The OS secret store is the real
+/// protection; this is only the fallback where none is available.
+fn obfuscation_cipher(dir: &Path) -> AppResult<Aes256Gcm> {
+    let path = dir.join(SECRETS_KEY_FILE);
+    let key = if path.exists() {
+        fs::read(&path).map_err(|e| AppError::io(&format!("Failed to read {}", SECRETS_KEY_FILE), e))?
+    } else {
+        let mut key = vec![0u8; KEY_LEN];
+        OsRng.fill_bytes(&mut key);
+        write_private(&path, &key)?;
+        key
+    };
+
+    Aes256Gcm::new_from_slice(&key)
+        .map_err(|_| AppError::Config(format!("{} is corrupted", SECRETS_KEY_FILE)))
+}
+
+/// Writes a file only the current user can read, where the platform allows it.
+fn write_private(path: &Path, content: &[u8]) -> AppResult<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| AppError::io(&format!("Failed to create directory {:?}", dir), e))?;
+    }
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(path)
+        .map_err(|e| AppError::io(&format!("Failed to write {:?}", path), e))?;
+    file.write_all(content)
+        .map_err(|e| AppError::io(&format!("Failed to write {:?}", path), e))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(hex: &str) -> AppResult<Vec<u8>> {
+    if hex.len() % 2 == 1 || !hex.is_ascii() {
+        return Err(AppError::Config(format!("Invalid hex in {}", SECRETS_FILE)));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16)
+            .map_err(|_| AppError::Config(format!("Invalid hex in {}", SECRETS_FILE))))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("editart-secrets-{}", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn file_secrets_round_trip_and_are_removed() {
+        let dir = temp_dir();
+        write_file_secret(&dir, "database.password", "s3cret").unwrap();
+        write_file_secret(&dir, "smtp.password", "other").unwrap();
+        assert_eq!(read_file_secret(&dir, "database.password").unwrap(), "s3cret");
+
+        write_file_secret(&dir, "database.password", "changed").unwrap();
+        assert_eq!(read_file_secret(&dir, "database.password").unwrap(), "changed");
+
+        remove_file_secret(&dir, "database.password").unwrap();
+        let missing = read_file_secret(&dir, "database.password");
+        let other = read_file_secret(&dir, "smtp.password");
+        let on_disk = fs::read_to_string(dir.join(SECRETS_FILE)).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(matches!(missing, Err(AppError::Config(_))));
+        assert_eq!(other.unwrap(), "other");
+        assert!(!on_disk.contains("other"), "the value is stored in the clear");
+    }
+
+    #[test]
+    fn file_secrets_cannot_be_swapped_between_keys() {
+        let dir = temp_dir();
+        write_file_secret(&dir, "a", "first").unwrap();
+        write_file_secret(&dir, "b", "second").unwrap();
+        let mut secrets = read_secrets_file(&dir).unwrap();
+        let a = secrets.remove("a").unwrap();
+        secrets.insert("b".to_string(), a);
+        write_secrets_file(&dir, &secrets).unwrap();
+
+        let swapped = read_file_secret(&dir, "b");
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(swapped, Err(AppError::Config(_))));
+    }
+
+    #[test]
+    fn file_secrets_need_their_key_file() {
+        let dir = temp_dir();
+        write_file_secret(&dir, "database.password", "s3cret").unwrap();
+        // A new key is generated in place of the lost one
+        fs::remove_file(dir.join(SECRETS_KEY_FILE)).unwrap();
+
+        let result = read_file_secret(&dir, "database.password");
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(result, Err(AppError::Config(_))));
+    }
+
+    #[test]
+    fn hex_round_trips_and_rejects_odd_or_invalid_input() {
+        assert_eq!(from_hex(&to_hex(&[0, 15, 255])).unwrap(), vec![0, 15, 255]);
+        assert!(from_hex("abc").is_err());
+        assert!(from_hex("zz").is_err());
+        assert!(from_hex("é0").is_err());
+    }
+}
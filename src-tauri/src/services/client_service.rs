@@ -1,21 +1,48 @@
-use crate::dto::{CreateClientDto, UpdateClientDto, ClientResponseDto};
-use crate::repositories::ClientRepository;
+use crate::database::{acquire_connection, begin_transaction, commit_transaction};
+use crate::dto::{CreateClientDto, UpdateClientDto, ClientResponseDto, ClientFilter, ClientSortField, Page, PageRequest};
+use crate::dto::{DuplicateMatch, DuplicateClientGroupDto, MergeClientsDto, MergeClientsResultDto, RenumberedOrderDto};
+use crate::models::Client;
+use crate::nuit;
+use crate::repositories::{ClientRepository, OrderRepository, SequenceRepository};
+use crate::error::{AppError, AppResult};
+use crate::validation::{Validate, Validator};
+use sqlx::PgConnection;
+use std::collections::{BTreeMap, HashSet};
+
+/// Legal forms left out when comparing names, as words after `name_key`
+/// has dropped the punctuation ("S.A." becomes "s a").
+const LEGAL_FORMS: &[&[&str]] = &[&["lda"], &["limitada"], &["sa"], &["s", "a"], &["sarl"]];
+
+/// What two clients must share to be listed as duplicates; empty matches nothing
+type DuplicateKey = fn(&Client) -> String;
+
+/// Shorter contacts are placeholders rather than phone numbers
+const MIN_PHONE_DIGITS: usize = 6;
 
 pub struct ClientService {
     repository: ClientRepository,
+    order_repository: OrderRepository,
+    sequence_repository: SequenceRepository,
 }
 
 impl ClientService {
     pub fn new() -> Self {
         Self {
             repository: ClientRepository,
+            order_repository: OrderRepository,
+            sequence_repository: SequenceRepository,
         }
     }
 
-    pub async fn create_client(&self, dto: CreateClientDto) -> Result<ClientResponseDto, String> {
+    pub async fn create_client(&self, dto: CreateClientDto) -> AppResult<ClientResponseDto> {
+        dto.validate()?;
+        let nuit = nuit::normalize(&dto.nuit);
+        let mut conn = acquire_connection().await?;
+        self.ensure_nuit_available(&mut conn, &nuit, None).await?;
         let client = self.repository.create(
+            &mut conn,
             dto.name,
-            dto.nuit,
+            nuit,
             dto.contact,
             dto.category,
             dto.observations,
@@ -24,26 +51,68 @@ impl ClientService {
         Ok(ClientResponseDto::from(client))
     }
 
-    pub async fn get_client_by_id(&self, id: &str) -> Result<Option<ClientResponseDto>, String> {
-        let client = self.repository.get_by_id(id).await?;
+    pub async fn get_client_by_id(&self, id: &str) -> AppResult<Option<ClientResponseDto>> {
+        let mut conn = acquire_connection().await?;
+        let client = self.repository.get_by_id(&mut conn, id).await?;
         Ok(client.map(ClientResponseDto::from))
     }
 
-    pub async fn get_clients_by_name(&self, name: &str) -> Result<Vec<ClientResponseDto>, String> {
-        let clients = self.repository.get_by_name(name).await?;
+    pub async fn find_client_by_nuit(&self, nuit: &str) -> AppResult<Option<ClientResponseDto>> {
+        let nuit = nuit::normalize(nuit);
+        Validator::new()
+            .required("nuit", &nuit)
+            .nuit("nuit", &nuit)
+            .finish()?;
+
+        let mut conn = acquire_connection().await?;
+        let client = self.repository.get_by_nuit(&mut conn, &nuit).await?;
+        Ok(client.map(ClientResponseDto::from))
+    }
+
+    pub async fn get_clients_by_name(&self, name: &str) -> AppResult<Vec<ClientResponseDto>> {
+        let mut conn = acquire_connection().await?;
+        let clients = self.repository.get_by_name(&mut conn, name).await?;
         Ok(clients.into_iter().map(ClientResponseDto::from).collect())
     }
 
-    pub async fn list_clients(&self) -> Result<Vec<ClientResponseDto>, String> {
-        let clients = self.repository.list().await?;
+    pub async fn list_clients(&self) -> AppResult<Vec<ClientResponseDto>> {
+        let mut conn = acquire_connection().await?;
+        let clients = self.repository.list(&mut conn).await?;
         Ok(clients.into_iter().map(ClientResponseDto::from).collect())
     }
 
-    pub async fn update_client(&self, id: &str, dto: UpdateClientDto) -> Result<Option<ClientResponseDto>, String> {
+    pub async fn list_clients_page(&self, filter: ClientFilter, page: PageRequest<ClientSortField>) -> AppResult<Page<ClientResponseDto>> {
+        let mut conn = acquire_connection().await?;
+        let (clients, total) = self.repository.list_page(&mut conn, &filter, &page).await?;
+        Ok(Page {
+            items: clients.into_iter().map(ClientResponseDto::from).collect(),
+            total,
+            offset: page.offset(),
+            limit: page.limit(),
+        })
+    }
+
+    pub async fn update_client(&self, id: &str, dto: UpdateClientDto) -> AppResult<Option<ClientResponseDto>> {
+        let mut conn = acquire_connection().await?;
+        let Some(current) = self.repository.get_by_id(&mut conn, id).await? else {
+            return Ok(None);
+        };
+
+        // An unchanged NUIT is kept as is, even one that fails the check digit
+        let nuit = dto.nuit.as_deref()
+            .map(nuit::normalize)
+            .filter(|nuit| *nuit != current.nuit);
+        dto.rules(Validator::new())
+            .optional(nuit.as_ref(), |v, nuit| v.nuit("nuit", nuit))
+            .finish()?;
+        if let Some(nuit) = &nuit {
+            self.ensure_nuit_available(&mut conn, nuit, Some(id)).await?;
+        }
         let client = self.repository.update(
+            &mut conn,
             id,
             dto.name,
-            dto.nuit,
+            nuit,
             dto.contact,
             dto.category,
             dto.observations,
@@ -52,11 +121,291 @@ impl ClientService {
         Ok(client.map(ClientResponseDto::from))
     }
 
-    pub async fn delete_client(&self, id: &str) -> Result<bool, String> {
-        self.repository.delete(id).await
+    pub async fn delete_client(&self, id: &str) -> AppResult<bool> {
+        let mut conn = acquire_connection().await?;
+        self.repository.delete(&mut conn, id).await
+    }
+
+    /// Groups of clients that share a normalized name, NUIT or contact. A
+    /// client can show up in several groups.
+    pub async fn find_duplicate_clients(&self) -> AppResult<Vec<DuplicateClientGroupDto>> {
+        let mut conn = acquire_connection().await?;
+        let mut clients = self.repository.list(&mut conn).await?;
+        clients.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.id.cmp(&b.id)));
+
+        let keys: [(DuplicateMatch, DuplicateKey); 3] = [
+            (DuplicateMatch::Name, |client| name_key(&client.name)),
+            (DuplicateMatch::Nuit, |client| nuit::normalize(&client.nuit)),
+            (DuplicateMatch::Contact, |client| contact_key(&client.contact)),
+        ];
+
+        let mut groups = Vec::new();
+        for (matched_by, key_of) in keys {
+            let mut by_key: BTreeMap<String, Vec<&Client>> = BTreeMap::new();
+            for client in &clients {
+                let key = key_of(client);
+                if !key.is_empty() {
+                    by_key.entry(key).or_default().push(client);
+                }
+            }
+            groups.extend(by_key.into_iter()
+                .filter(|(_, clients)| clients.len() > 1)
+                .map(|(key, clients)| DuplicateClientGroupDto {
+                    matched_by,
+                    key,
+                    clients: clients.into_iter().cloned().map(ClientResponseDto::from).collect(),
+                }));
+        }
+        Ok(groups)
+    }
+
+    /// Moves the orders of `source_ids` to `target_id` and deletes the sources.
+    /// Moved orders keep their requisition number unless the target already
+    /// uses it; those take the target's next one. The target keeps its name
+    /// and category, takes a NUIT or contact it lacks and gets the sources'
+    /// observations appended.
+    pub async fn merge_clients(&self, dto: MergeClientsDto) -> AppResult<MergeClientsResultDto> {
+        dto.validate()?;
+        let mut tx = begin_transaction().await?;
+
+        // Locked in id order, so two merges over the same clients cannot deadlock
+        let mut ids: Vec<&str> = dto.source_ids.iter().map(String::as_str).collect();
+        ids.push(&dto.target_id);
+        ids.sort_unstable();
+        for id in ids {
+            if !self.repository.lock(&mut tx, id).await? {
+                return Err(AppError::not_found("client", id));
+            }
+        }
+
+        let target = self.repository.get_by_id(&mut tx, &dto.target_id).await?
+            .ok_or_else(|| AppError::not_found("client", &dto.target_id))?;
+        let mut sources = Vec::with_capacity(dto.source_ids.len());
+        for id in &dto.source_ids {
+            sources.push(self.repository.get_by_id(&mut tx, id).await?
+                .ok_or_else(|| AppError::not_found("client", id))?);
+        }
+
+        // NUITs are unique, so two of them are two different taxpayers
+        let nuits: Vec<&str> = std::iter::once(&target).chain(&sources)
+            .map(|client| client.nuit.as_str())
+            .filter(|nuit| !nuit.is_empty())
+            .collect();
+        if nuits.len() > 1 {
+            return Err(AppError::Conflict(format!(
+                "Clients with different NUITs cannot be merged: {}", nuits.join(", ")
+            )));
+        }
+        let nuit = nuits.first().map(|nuit| nuit.to_string());
+        let contact = if target.contact.trim().is_empty() {
+            sources.iter()
+                .map(|source| source.contact.trim())
+                .find(|contact| !contact.is_empty())
+                .map(str::to_string)
+        } else {
+            None
+        };
+        let observations = merged_observations(&target, &sources);
+
+        self.sequence_repository.sync_requisition_counter(&mut tx, &target.id).await?;
+        let mut taken: HashSet<i32> = self.order_repository.get_by_client_id(&mut tx, &target.id).await?
+            .into_iter()
+            .map(|order| order.client_requisition_number)
+            .collect();
+
+        let mut moved_orders = 0;
+        let mut renumbered_orders = Vec::new();
+        for source in &sources {
+            for order in self.order_repository.get_by_client_id(&mut tx, &source.id).await? {
+                let requisition_number = if taken.contains(&order.client_requisition_number) {
+                    let next = self.sequence_repository.next_requisition_number(&mut tx, &target.id).await?;
+                    renumbered_orders.push(RenumberedOrderDto {
+                        order_id: order.id.clone(),
+                        order_number: order.order_number,
+                        previous_requisition_number: order.client_requisition_number,
+                        requisition_number: next,
+                    });
+                    next
+                } else {
+                    order.client_requisition_number
+                };
+                taken.insert(requisition_number);
+                self.order_repository.move_to_client(&mut tx, &order.id, &target.id, requisition_number).await?;
+                moved_orders += 1;
+            }
+        }
+        self.sequence_repository.sync_requisition_counter(&mut tx, &target.id).await?;
+
+        for source in &sources {
+            self.repository.delete(&mut tx, &source.id).await?;
+            self.sequence_repository.delete_requisition_counter(&mut tx, &source.id).await?;
+        }
+
+        // Only now, with the sources gone, is a source's NUIT free for the target
+        self.repository.update(&mut tx, &target.id, None, nuit, contact, None, Some(observations)).await?;
+        self.repository.update_client_debt(&mut tx, &target.id).await?;
+        let client = self.repository.get_by_id(&mut tx, &target.id).await?
+            .ok_or_else(|| AppError::not_found("client", &target.id))?;
+        commit_transaction(tx).await?;
+
+        log::info!(
+            "Merged {} clients into {}: {} orders moved, {} renumbered",
+            sources.len(), target.id, moved_orders, renumbered_orders.len()
+        );
+        Ok(MergeClientsResultDto {
+            client: ClientResponseDto::from(client),
+            moved_orders,
+            renumbered_orders,
+        })
+    }
+
+    /// A clear error before the unique index on `clients.nuit` would reject it.
+    /// `client_id` is the client being updated, which may keep its own NUIT.
+    async fn ensure_nuit_available(&self, conn: &mut PgConnection, nuit: &str, client_id: Option<&str>) -> AppResult<()> {
+        if nuit.is_empty() {
+            return Ok(());
+        }
+        match self.repository.get_by_nuit(conn, nuit).await? {
+            Some(existing) if Some(existing.id.as_str()) != client_id => Err(AppError::Conflict(
+                format!("NUIT {} is already registered to client {}", nuit, existing.name),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    pub async fn update_client_debt(&self, client_id: &str) -> AppResult<bool> {
+        let mut conn = acquire_connection().await?;
+        self.repository.update_client_debt(&mut conn, client_id).await
+    }
+}
+
+/// Lower case, without accents, punctuation, repeated spaces or a trailing
+/// legal form, so "Escola X", "ESCOLA X " and "Escola X, Lda." all match.
+fn name_key(name: &str) -> String {
+    let folded: String = name.to_lowercase()
+        .chars()
+        .map(fold_accent)
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect();
+    let mut words: Vec<&str> = folded.split_whitespace().collect();
+    while let Some(form) = LEGAL_FORMS.iter().find(|form| words.len() > form.len() && words.ends_with(form)) {
+        words.truncate(words.len() - form.len());
+    }
+    words.join(" ")
+}
+
+fn fold_accent(c: char) -> char {
+    match c {
+        'á' | 'à' | 'â' | 'ã' | 'ä' => 'a',
+        'é' | 'è' | 'ê' | 'ë' => 'e',
+        'í' | 'ì' | 'î' | 'ï' => 'i',
+        'ó' | 'ò' | 'ô' | 'õ' | 'ö' => 'o',
+        'ú' | 'ù' | 'û' | 'ü' => 'u',
+        'ç' => 'c',
+        _ => c,
+    }
+}
+
+/// Emails compare case-insensitively, phone numbers by their digits without
+/// the 258 country code. Empty when there is nothing worth comparing.
+fn contact_key(contact: &str) -> String {
+    let contact = contact.trim();
+    if contact.contains('@') {
+        return contact.to_lowercase();
+    }
+    let digits: String = contact.chars().filter(char::is_ascii_digit).collect();
+    let local = digits.strip_prefix("00258")
+        .or_else(|| digits.strip_prefix("258").filter(|local| local.len() == 9))
+        .unwrap_or(&digits);
+    if local.len() < MIN_PHONE_DIGITS {
+        return String::new();
+    }
+    local.to_string()
+}
+
+/// The target's observations, then those of the sources it does not already have
+fn merged_observations(target: &Client, sources: &[Client]) -> String {
+    let mut parts: Vec<&str> = Vec::new();
+    for observations in std::iter::once(target).chain(sources).map(|client| client.observations.trim()) {
+        if !observations.is_empty() && !parts.contains(&observations) {
+            parts.push(observations);
+        }
+    }
+    parts.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dto::page_dto::SortDirection;
+    use crate::test_support::{create_client, with_app_database};
+    use uuid::Uuid;
+
+    #[test]
+    fn name_key_folds_case_accents_and_punctuation() {
+        assert_eq!(name_key("Construções  Silva"), "construcoes silva");
+        assert_eq!(name_key("CONSTRUCOES-SILVA"), "construcoes silva");
     }
 
-    pub async fn update_client_debt(&self, client_id: &str) -> Result<bool, String> {
-        self.repository.update_client_debt(client_id).await
+    #[test]
+    fn name_key_drops_trailing_legal_forms() {
+        assert_eq!(name_key("Silva, Lda."), "silva");
+        assert_eq!(name_key("Silva S.A."), "silva");
+        assert_eq!(name_key("Silva SARL"), "silva");
+        assert_eq!(name_key("Silva Limitada Lda"), "silva");
+        // Never strips the whole name, nor a form in the middle
+        assert_eq!(name_key("SA"), "sa");
+        assert_eq!(name_key("Lda Silva"), "lda silva");
+    }
+
+    #[test]
+    fn contact_key_compares_emails_case_insensitively() {
+        assert_eq!(contact_key(" Geral@Silva.CO.MZ "), "geral@silva.co.mz");
+    }
+
+    #[test]
+    fn contact_key_strips_formatting_and_country_code() {
+        assert_eq!(contact_key("84 123 4567"), "841234567");
+        assert_eq!(contact_key("+258 84 123 4567"), "841234567");
+        assert_eq!(contact_key("00258-84-123-4567"), "841234567");
+        // Only a 258 followed by a full local number is a country code
+        assert_eq!(contact_key("258123"), "258123");
+    }
+
+    #[test]
+    fn contact_key_ignores_placeholders() {
+        assert_eq!(contact_key(""), "");
+        assert_eq!(contact_key("n/a"), "");
+        assert_eq!(contact_key("12345"), "");
+    }
+
+    fn by_name(offset: i64, limit: i64, direction: SortDirection) -> PageRequest<ClientSortField> {
+        PageRequest { offset, limit, sort_by: Some(ClientSortField::Name), direction }
+    }
+
+    #[test]
+    fn pages_clients_sorted_by_name() {
+        with_app_database(|| async {
+            let tag = format!("Paging {}", Uuid::new_v4().simple());
+            for letter in ["B", "C", "A"] {
+                create_client(&format!("{} {}", tag, letter)).await;
+            }
+            let filter = ClientFilter { search: Some(tag.clone()), ..Default::default() };
+            let names = |page: Page<ClientResponseDto>| -> Vec<String> {
+                page.items.into_iter().map(|client| client.name[tag.len() + 1..tag.len() + 2].to_string()).collect()
+            };
+
+            let service = ClientService::new();
+            let page = service.list_clients_page(filter.clone(), by_name(0, 2, SortDirection::Asc)).await.unwrap();
+            assert_eq!(page.total, 3);
+            assert_eq!(names(page), ["A", "B"]);
+
+            let page = service.list_clients_page(filter.clone(), by_name(2, 2, SortDirection::Asc)).await.unwrap();
+            assert_eq!((page.total, page.offset), (3, 2));
+            assert_eq!(names(page), ["C"]);
+
+            let page = service.list_clients_page(filter, by_name(0, 2, SortDirection::Desc)).await.unwrap();
+            assert_eq!(names(page), ["C", "B"]);
+        });
     }
 }
@@ -1,6 +1,11 @@
+use crate::database::{acquire_connection, begin_transaction, commit_transaction};
+use crate::money;
 use crate::dto::{ClothesDto, CreateClothesDto, UpdateClothesDto, CreateClothingServiceDto, UpdateClothingServiceDto, ClothingServiceDto};
 use crate::repositories::{ClothesRepository, ClothingServiceRepository};
 use crate::services::OrderService;
+use rust_decimal::Decimal;
+use crate::error::{AppError, AppResult};
+use crate::validation::Validate;
 
 pub struct ClothesService {
     clothes_repository: ClothesRepository,
@@ -17,17 +22,22 @@ impl ClothesService {
         }
     }
 
-    pub async fn create_clothes(&self, dto: CreateClothesDto) -> Result<ClothesDto, String> {
+    pub async fn create_clothes(&self, dto: CreateClothesDto) -> AppResult<ClothesDto> {
+        dto.validate()?;
         let total_quantity = dto.calculate_total_quantity();
         let clothing_type_str = dto.to_clothing_type_string();
         let sizes_json = dto.sizes_to_json()?;
 
+        let mut tx = begin_transaction().await?;
+        self.order_service.lock_order(&mut tx, &dto.order_id).await?;
+
         // Create the clothes item
         let clothes = self.clothes_repository.create(
+            &mut tx,
             dto.order_id.clone(),
             clothing_type_str,
             dto.custom_type.clone(),
-            dto.unit_price,
+            money::parse_amount(dto.unit_price, "unit_price")?,
             sizes_json,
             dto.color.clone(),
             total_quantity,
@@ -36,39 +46,43 @@ impl ClothesService {
         // Create associated services
         let mut services = Vec::new();
         for service_dto in dto.services {
-                    let service = self.clothing_service_repository.create(
-            clothes.id.clone(),
-            service_dto.to_service_type_string(),
-            service_dto.to_location_string(),
-            service_dto.description.clone(),
-            service_dto.unit_price,
-        ).await?;
+            let service = self.clothing_service_repository.create(
+                &mut tx,
+                clothes.id.clone(),
+                service_dto.to_service_type_string(),
+                service_dto.to_location_string(),
+                service_dto.description.clone(),
+                money::parse_amount(service_dto.unit_price, "unit_price")?,
+            ).await?;
             services.push(service);
         }
 
         // Recalculate order totals using the unified method
-        self.order_service.recalculate_order_totals(&dto.order_id).await?;
+        self.order_service.recalculate_order_totals(&mut tx, &dto.order_id).await?;
+        commit_transaction(tx).await?;
 
         ClothesDto::from_model(clothes, services)
     }
 
-    pub async fn get_clothes_by_id(&self, id: &str) -> Result<Option<ClothesDto>, String> {
-        let clothes = match self.clothes_repository.get_by_id(id).await? {
+    pub async fn get_clothes_by_id(&self, id: &str) -> AppResult<Option<ClothesDto>> {
+        let mut conn = acquire_connection().await?;
+        let clothes = match self.clothes_repository.get_by_id(&mut conn, id).await? {
             Some(clothes) => clothes,
             None => return Ok(None),
         };
 
-        let services = self.clothing_service_repository.get_by_clothes_id(id).await?;
+        let services = self.clothing_service_repository.get_by_clothes_id(&mut conn, id).await?;
         let dto = ClothesDto::from_model(clothes, services)?;
         Ok(Some(dto))
     }
 
-    pub async fn get_clothes_by_order_id(&self, order_id: &str) -> Result<Vec<ClothesDto>, String> {
-        let clothes_list = self.clothes_repository.get_by_order_id(order_id).await?;
+    pub async fn get_clothes_by_order_id(&self, order_id: &str) -> AppResult<Vec<ClothesDto>> {
+        let mut conn = acquire_connection().await?;
+        let clothes_list = self.clothes_repository.get_by_order_id(&mut conn, order_id).await?;
         let mut result = Vec::new();
 
         for clothes in clothes_list {
-            let services = self.clothing_service_repository.get_by_clothes_id(&clothes.id).await?;
+            let services = self.clothing_service_repository.get_by_clothes_id(&mut conn, &clothes.id).await?;
             let dto = ClothesDto::from_model(clothes, services)?;
             result.push(dto);
         }
@@ -76,21 +90,26 @@ impl ClothesService {
         Ok(result)
     }
 
-    pub async fn update_clothes(&self, id: &str, dto: UpdateClothesDto) -> Result<Option<ClothesDto>, String> {
+    pub async fn update_clothes(&self, id: &str, dto: UpdateClothesDto) -> AppResult<Option<ClothesDto>> {
+        dto.validate()?;
+        let mut tx = begin_transaction().await?;
+
         // Get the order_id before updating
-        let current_clothes = self.clothes_repository.get_by_id(id).await?
-            .ok_or("Clothes not found")?;
+        let current_clothes = self.clothes_repository.get_by_id(&mut tx, id).await?
+            .ok_or_else(|| AppError::not_found("clothes", id))?;
         let order_id = current_clothes.order_id.clone();
+        self.order_service.lock_order(&mut tx, &order_id).await?;
 
         let clothing_type_str = dto.to_clothing_type_string();
         let sizes_json = dto.sizes_to_json()?;
         let total_quantity = dto.calculate_total_quantity();
 
         let updated_clothes = self.clothes_repository.update(
+            &mut tx,
             id,
             clothing_type_str,
             dto.custom_type,
-            dto.unit_price,
+            dto.unit_price.map(|price| money::parse_amount(price, "unit_price")).transpose()?,
             sizes_json,
             dto.color,
             total_quantity,
@@ -98,10 +117,11 @@ impl ClothesService {
 
         match updated_clothes {
             Some(clothes) => {
-                let services = self.clothing_service_repository.get_by_clothes_id(id).await?;
+                let services = self.clothing_service_repository.get_by_clothes_id(&mut tx, id).await?;
                 
                 // Recalculate order totals using the unified method
-                self.order_service.recalculate_order_totals(&order_id).await?;
+                self.order_service.recalculate_order_totals(&mut tx, &order_id).await?;
+                commit_transaction(tx).await?;
                 
                 let dto = ClothesDto::from_model(clothes, services)?;
                 Ok(Some(dto))
@@ -110,92 +130,111 @@ impl ClothesService {
         }
     }
 
-    pub async fn delete_clothes(&self, id: &str) -> Result<bool, String> {
+    pub async fn delete_clothes(&self, id: &str) -> AppResult<bool> {
+        let mut tx = begin_transaction().await?;
+
         // Get the order_id before deleting
-        let current_clothes = self.clothes_repository.get_by_id(id).await?
-            .ok_or("Clothes not found")?;
+        let current_clothes = self.clothes_repository.get_by_id(&mut tx, id).await?
+            .ok_or_else(|| AppError::not_found("clothes", id))?;
         let order_id = current_clothes.order_id.clone();
+        self.order_service.lock_order(&mut tx, &order_id).await?;
 
         // First delete all associated services
-        self.clothing_service_repository.delete_by_clothes_id(id).await?;
+        self.clothing_service_repository.delete_by_clothes_id(&mut tx, id).await?;
         
         // Then delete the clothes item
-        let result = self.clothes_repository.delete(id).await?;
+        let result = self.clothes_repository.delete(&mut tx, id).await?;
         
         // Recalculate order totals using the unified method
         if result {
-            self.order_service.recalculate_order_totals(&order_id).await?;
+            self.order_service.recalculate_order_totals(&mut tx, &order_id).await?;
         }
+        commit_transaction(tx).await?;
         
         Ok(result)
     }
 
-    pub async fn add_service_to_clothes(&self, clothes_id: &str, dto: CreateClothingServiceDto) -> Result<ClothingServiceDto, String> {
+    pub async fn add_service_to_clothes(&self, clothes_id: &str, dto: CreateClothingServiceDto) -> AppResult<ClothingServiceDto> {
+        dto.validate()?;
+        let mut tx = begin_transaction().await?;
+
         // Verify clothes exists and get order_id
-        let clothes = self.clothes_repository.get_by_id(clothes_id).await?
-            .ok_or("Clothes not found")?;
+        let clothes = self.clothes_repository.get_by_id(&mut tx, clothes_id).await?
+            .ok_or_else(|| AppError::not_found("clothes", clothes_id))?;
         let order_id = clothes.order_id.clone();
+        self.order_service.lock_order(&mut tx, &order_id).await?;
 
         let service = self.clothing_service_repository.create(
+            &mut tx,
             clothes_id.to_string(),
             dto.to_service_type_string(),
             dto.to_location_string(),
             dto.description.clone(),
-            dto.unit_price,
+            money::parse_amount(dto.unit_price, "unit_price")?,
         ).await?;
 
         // Recalculate order totals using the unified method
-        self.order_service.recalculate_order_totals(&order_id).await?;
+        self.order_service.recalculate_order_totals(&mut tx, &order_id).await?;
+        commit_transaction(tx).await?;
 
         ClothingServiceDto::from_model(service)
     }
 
-    pub async fn update_service(&self, service_id: &str, dto: UpdateClothingServiceDto) -> Result<Option<ClothingServiceDto>, String> {
+    pub async fn update_service(&self, service_id: &str, dto: UpdateClothingServiceDto) -> AppResult<Option<ClothingServiceDto>> {
+        dto.validate()?;
         // Get the clothes_id to find the order_id
-        let service = self.clothing_service_repository.get_by_id(service_id).await?
-            .ok_or("Service not found")?;
-        let clothes = self.clothes_repository.get_by_id(&service.clothes_id).await?
-            .ok_or("Clothes not found")?;
+        let mut tx = begin_transaction().await?;
+        let service = self.clothing_service_repository.get_by_id(&mut tx, service_id).await?
+            .ok_or_else(|| AppError::not_found("clothing service", service_id))?;
+        let clothes = self.clothes_repository.get_by_id(&mut tx, &service.clothes_id).await?
+            .ok_or_else(|| AppError::not_found("clothes", &service.clothes_id))?;
         let order_id = clothes.order_id.clone();
+        self.order_service.lock_order(&mut tx, &order_id).await?;
 
         let updated_service = self.clothing_service_repository.update(
+            &mut tx,
             service_id,
             dto.to_service_type_string(),
             dto.to_location_string(),
             dto.description,
-            dto.unit_price,
+            dto.unit_price.map(|price| money::parse_amount(price, "unit_price")).transpose()?,
         ).await?;
 
         match updated_service {
             Some(service) => {
                 // Recalculate order totals using the unified method
-                self.order_service.recalculate_order_totals(&order_id).await?;
+                self.order_service.recalculate_order_totals(&mut tx, &order_id).await?;
+                commit_transaction(tx).await?;
                 Ok(Some(ClothingServiceDto::from_model(service)?))
             }
             None => Ok(None),
         }
     }
 
-    pub async fn delete_service(&self, service_id: &str) -> Result<bool, String> {
+    pub async fn delete_service(&self, service_id: &str) -> AppResult<bool> {
         // Get the clothes_id to find the order_id before deleting
-        let service = self.clothing_service_repository.get_by_id(service_id).await?
-            .ok_or("Service not found")?;
-        let clothes = self.clothes_repository.get_by_id(&service.clothes_id).await?
-            .ok_or("Clothes not found")?;
+        let mut tx = begin_transaction().await?;
+        let service = self.clothing_service_repository.get_by_id(&mut tx, service_id).await?
+            .ok_or_else(|| AppError::not_found("clothing service", service_id))?;
+        let clothes = self.clothes_repository.get_by_id(&mut tx, &service.clothes_id).await?
+            .ok_or_else(|| AppError::not_found("clothes", &service.clothes_id))?;
         let order_id = clothes.order_id.clone();
+        self.order_service.lock_order(&mut tx, &order_id).await?;
 
-        let result = self.clothing_service_repository.delete(service_id).await?;
+        let result = self.clothing_service_repository.delete(&mut tx, service_id).await?;
         
         // Recalculate order totals using the unified method
         if result {
-            self.order_service.recalculate_order_totals(&order_id).await?;
+            self.order_service.recalculate_order_totals(&mut tx, &order_id).await?;
         }
+        commit_transaction(tx).await?;
         
         Ok(result)
     }
 
-    pub async fn get_services_by_clothes_id(&self, clothes_id: &str) -> Result<Vec<ClothingServiceDto>, String> {
-        let services = self.clothing_service_repository.get_by_clothes_id(clothes_id).await?;
+    pub async fn get_services_by_clothes_id(&self, clothes_id: &str) -> AppResult<Vec<ClothingServiceDto>> {
+        let mut conn = acquire_connection().await?;
+        let services = self.clothing_service_repository.get_by_clothes_id(&mut conn, clothes_id).await?;
         let mut result = Vec::new();
 
         for service in services {
@@ -206,7 +245,7 @@ impl ClothesService {
         Ok(result)
     }
 
-    pub async fn calculate_order_clothes_total(&self, order_id: &str) -> Result<f64, String> {
+    pub async fn calculate_order_clothes_total(&self, order_id: &str) -> AppResult<Decimal> {
         let clothes_list = self.get_clothes_by_order_id(order_id).await?;
         Ok(clothes_list.iter().map(|c| c.calculate_total_price()).sum())
     }
@@ -217,3 +256,62 @@ impl Default for ClothesService {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::clothes::ClothingSize;
+    use crate::models::{ClothingType, ServiceLocation, ServiceType};
+    use crate::test_support::{create_client, create_order, dec, with_app_database};
+    use std::collections::HashMap;
+
+    fn garment(order_id: &str, service_price: &str) -> CreateClothesDto {
+        CreateClothesDto {
+            order_id: order_id.to_string(),
+            clothing_type: ClothingType::CollaredTshirts,
+            custom_type: None,
+            unit_price: dec("10.00"),
+            sizes: HashMap::from([(ClothingSize::M, 5)]),
+            color: "Azul".to_string(),
+            services: vec![CreateClothingServiceDto {
+                service_type: ServiceType::Embroidery,
+                location: ServiceLocation::FrontLeft,
+                description: None,
+                unit_price: dec(service_price),
+            }],
+        }
+    }
+
+    #[test]
+    fn adding_a_garment_updates_the_order_total() {
+        with_app_database(|| async {
+            let client = create_client("Garments").await;
+            let order = create_order(&client.id, "100.00").await;
+
+            let service = ClothesService::new();
+            service.create_clothes(garment(&order.id, "2.00")).await.unwrap();
+
+            let order = OrderService::new().get_order_by_id(&order.id).await.unwrap().unwrap();
+            assert_eq!(order.total, dec("160.00"));
+            assert_eq!(service.get_clothes_by_order_id(&order.id).await.unwrap().len(), 1);
+        });
+    }
+
+    #[test]
+    fn a_failed_service_write_rolls_back_the_garment() {
+        with_app_database(|| async {
+            let client = create_client("Garments").await;
+            let order = create_order(&client.id, "100.00").await;
+
+            // Passes validation, but overflows NUMERIC(14,2) once the garment
+            // row was already written
+            let service = ClothesService::new();
+            let error = service.create_clothes(garment(&order.id, "1000000000000")).await.unwrap_err();
+            assert_eq!(error.code(), "database");
+
+            assert!(service.get_clothes_by_order_id(&order.id).await.unwrap().is_empty());
+            let order = OrderService::new().get_order_by_id(&order.id).await.unwrap().unwrap();
+            assert_eq!(order.total, dec("100.00"));
+        });
+    }
+}
@@ -0,0 +1,96 @@
+use crate::config::{database_overridden, load_config, load_file_config, save_config, DatabaseConfig};
+use crate::database::{connect, connection_error, is_connected, test_connection};
+use crate::dto::{DatabaseSettingsDto, DatabaseStatusDto, DatabaseTestResultDto};
+use crate::error::{AppError, AppResult};
+use crate::validation::Validate;
+
+pub struct DatabaseService;
+
+impl DatabaseService {
+    pub fn status() -> DatabaseStatusDto {
+        DatabaseStatusDto {
+            connected: is_connected(),
+            error: connection_error(),
+        }
+    }
+
+    pub fn get_settings() -> AppResult<DatabaseSettingsDto> {
+        let database = load_config()?.database;
+        Ok(DatabaseSettingsDto {
+            has_password: !database.password.is_empty(),
+            host: database.host,
+            port: database.port,
+            user: database.user,
+            password: None,
+            dbname: database.dbname,
+            ssl_mode: database.ssl_mode,
+            read_only: database_overridden(),
+        })
+    }
+
+    pub async fn test_settings(settings: DatabaseSettingsDto) -> AppResult<DatabaseTestResultDto> {
+        let config = Self::resolve(settings)?;
+        let server_version = test_connection(&config).await?;
+        Ok(DatabaseTestResultDto { server_version })
+    }
+
+    /// Connects with the new settings and only saves them once that worked,
+    /// so a typo never replaces settings that work. Returns whether the app
+    /// now talks to a different database than before.
+    pub async fn save_settings(settings: DatabaseSettingsDto) -> AppResult<bool> {
+        if database_overridden() {
+            return Err(AppError::Config("Database settings come from --database-url or EDITART_DATABASE_URL and cannot be changed from the app".to_string()));
+        }
+
+        let mut app_config = load_file_config()?;
+        let password_changed = settings.password.is_some();
+        let mut config = Self::resolve(settings)?;
+        let switched = config.host != app_config.database.host
+            || config.port != app_config.database.port
+            || config.dbname != app_config.database.dbname;
+
+        connect(&config).await?;
+
+        config.password_ref = app_config.database.password_ref.take();
+        if password_changed {
+            config.store_password()?;
+        }
+        app_config.database = config;
+        save_config(&app_config)?;
+        Ok(switched)
+    }
+
+    /// The settings as a `DatabaseConfig`. Without a new password the saved
+    /// one is reused, but only for the saved server and user: otherwise
+    /// anyone at the login screen could send it to a server of their own.
+    fn resolve(settings: DatabaseSettingsDto) -> AppResult<DatabaseConfig> {
+        settings.validate()?;
+        let host = settings.host.trim().to_string();
+        let user = settings.user.trim().to_string();
+
+        let password = match settings.password {
+            Some(password) => password,
+            None => {
+                let saved = load_config()?.database;
+                if saved.host != host || saved.port != settings.port || saved.user != user {
+                    return Err(AppError::invalid(
+                        "password",
+                        "required",
+                        "Enter the password when changing the host, port or user",
+                    ));
+                }
+                saved.password
+            }
+        };
+
+        Ok(DatabaseConfig {
+            host,
+            port: settings.port,
+            user,
+            password,
+            dbname: settings.dbname.trim().to_string(),
+            ssl_mode: settings.ssl_mode,
+            password_ref: None,
+        })
+    }
+}
@@ -1,6 +1,10 @@
+use crate::database::{acquire_connection, begin_transaction, commit_transaction};
+use crate::money;
 use crate::dto::{ImpressionDto, CreateImpressionDto, UpdateImpressionDto};
 use crate::repositories::{ImpressionRepository};
 use crate::services::OrderService;
+use crate::error::{AppError, AppResult};
+use crate::validation::Validate;
 
 pub struct ImpressionService {
     impression_repository: ImpressionRepository,
@@ -15,24 +19,31 @@ impl ImpressionService {
         }
     }
 
-    pub async fn create_impression(&self, dto: CreateImpressionDto) -> Result<ImpressionDto, String> {
+    pub async fn create_impression(&self, dto: CreateImpressionDto) -> AppResult<ImpressionDto> {
+        dto.validate()?;
+        let mut tx = begin_transaction().await?;
+        self.order_service.lock_order(&mut tx, &dto.order_id).await?;
+
         let impression = self.impression_repository.create(
+            &mut tx,
             dto.order_id.clone(),
             dto.name,
             dto.size,
             dto.material,
             dto.description,
-            dto.price,
+            money::parse_amount(dto.price, "price")?,
         ).await?;
 
         // Recalculate order totals using the unified method
-        self.order_service.recalculate_order_totals(&dto.order_id).await?;
+        self.order_service.recalculate_order_totals(&mut tx, &dto.order_id).await?;
+        commit_transaction(tx).await?;
 
         ImpressionDto::from_model(impression)
     }
 
-    pub async fn get_impression_by_id(&self, id: &str) -> Result<Option<ImpressionDto>, String> {
-        let impression = match self.impression_repository.get_by_id(id).await? {
+    pub async fn get_impression_by_id(&self, id: &str) -> AppResult<Option<ImpressionDto>> {
+        let mut conn = acquire_connection().await?;
+        let impression = match self.impression_repository.get_by_id(&mut conn, id).await? {
             Some(impression) => impression,
             None => return Ok(None),
         };
@@ -41,8 +52,9 @@ impl ImpressionService {
         Ok(Some(dto))
     }
 
-    pub async fn get_impressions_by_order_id(&self, order_id: &str) -> Result<Vec<ImpressionDto>, String> {
-        let impressions = self.impression_repository.get_by_order_id(order_id).await?;
+    pub async fn get_impressions_by_order_id(&self, order_id: &str) -> AppResult<Vec<ImpressionDto>> {
+        let mut conn = acquire_connection().await?;
+        let impressions = self.impression_repository.get_by_order_id(&mut conn, order_id).await?;
         let mut result = Vec::new();
 
         for impression in impressions {
@@ -53,22 +65,27 @@ impl ImpressionService {
         Ok(result)
     }
 
-    pub async fn update_impression(&self, id: &str, dto: UpdateImpressionDto) -> Result<Option<ImpressionDto>, String> {
-        let current_impression = self.impression_repository.get_by_id(id).await?
-            .ok_or("Impression not found")?;
+    pub async fn update_impression(&self, id: &str, dto: UpdateImpressionDto) -> AppResult<Option<ImpressionDto>> {
+        dto.validate()?;
+        let mut tx = begin_transaction().await?;
+        let current_impression = self.impression_repository.get_by_id(&mut tx, id).await?
+            .ok_or_else(|| AppError::not_found("impression", id))?;
+        self.order_service.lock_order(&mut tx, &current_impression.order_id).await?;
         
         let updated_impression = self.impression_repository.update(
+            &mut tx,
             id,
             dto.name,
             dto.size,
             dto.material,
             dto.description,
-            dto.price,
+            money::parse_amount(dto.price, "price")?,
         ).await?;
 
         if let Some(impression) = updated_impression {
             // Recalculate order totals using the unified method
-            self.order_service.recalculate_order_totals(&current_impression.order_id).await?;
+            self.order_service.recalculate_order_totals(&mut tx, &current_impression.order_id).await?;
+            commit_transaction(tx).await?;
             let dto = ImpressionDto::from_model(impression)?;
             Ok(Some(dto))
         } else {
@@ -76,17 +93,20 @@ impl ImpressionService {
         }
     }
 
-    pub async fn delete_impression(&self, id: &str) -> Result<bool, String> {
-        let current_impression = self.impression_repository.get_by_id(id).await?
-            .ok_or("Impression not found")?;
+    pub async fn delete_impression(&self, id: &str) -> AppResult<bool> {
+        let mut tx = begin_transaction().await?;
+        let current_impression = self.impression_repository.get_by_id(&mut tx, id).await?
+            .ok_or_else(|| AppError::not_found("impression", id))?;
         let order_id = current_impression.order_id.clone();
+        self.order_service.lock_order(&mut tx, &order_id).await?;
 
-        let result = self.impression_repository.delete(id).await?;
+        let result = self.impression_repository.delete(&mut tx, id).await?;
         
         // Recalculate order totals using the unified method
         if result {
-            self.order_service.recalculate_order_totals(&order_id).await?;
+            self.order_service.recalculate_order_totals(&mut tx, &order_id).await?;
         }
+        commit_transaction(tx).await?;
         
         Ok(result)
     }
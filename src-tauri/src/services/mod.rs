@@ -1,11 +1,15 @@
 pub mod client_service;
 pub mod clothes_service;
+pub mod database_service;
 pub mod impression_service;
 pub mod order_service;
+pub mod payment_service;
 pub mod user_service;
 
 pub use client_service::ClientService;
 pub use clothes_service::ClothesService;
+pub use database_service::DatabaseService;
 pub use impression_service::ImpressionService;
 pub use order_service::OrderService;
+pub use payment_service::PaymentService;
 pub use user_service::UserService;
@@ -1,151 +1,282 @@
-use crate::dto::{CreateOrderDto, UpdateOrderDto, OrderResponseDto};
-use crate::models::OrderStatus;
-use crate::repositories::OrderRepository;
+use crate::config::NumberingConfig;
+use crate::money;
+use crate::database::{acquire_connection, begin_transaction, commit_transaction};
+use crate::dto::{ClothesDto, CreateOrderDto, UpdateOrderDto, OrderResponseDto, OrderStatusChangeDto, CreatePaymentDto, OrderFilter, OrderSortField, Page, PageRequest};
+use crate::models::{OrderStatus, PaymentMethod};
+use crate::repositories::{ClothesRepository, ClothingServiceRepository, ImpressionRepository, OrderRepository, OrderStatusHistoryRepository};
+use sqlx::PgConnection;
 use time::Date;
+use rust_decimal::Decimal;
+use crate::error::{AppError, AppResult};
+use crate::validation::{Validate, Validator};
 
 pub struct OrderService {
     repository: OrderRepository,
+    history_repository: OrderStatusHistoryRepository,
 }
 
 impl OrderService {
     pub fn new() -> Self {
         Self {
             repository: OrderRepository,
+            history_repository: OrderStatusHistoryRepository,
         }
     }
 
-    pub async fn create_order(&self, dto: CreateOrderDto) -> Result<OrderResponseDto, String> {
-        let status_str = match dto.status.unwrap_or_default() {
-            OrderStatus::OrderReceived => "order_received".to_string(),
-            OrderStatus::InProduction => "in_production".to_string(),
-            OrderStatus::ReadyForDelivery => "ready_for_delivery".to_string(),
-            OrderStatus::Delivered => "delivered".to_string(),
-        };
+    pub async fn create_order(&self, dto: CreateOrderDto, created_by: Option<String>) -> AppResult<OrderResponseDto> {
+        dto.validate()?;
+        let status = dto.status.unwrap_or_default();
         
+        let mut tx = begin_transaction().await?;
         let order = self.repository.create(
+            &mut tx,
             dto.name,
             dto.client_id,
             dto.due_date,
-            dto.iva,
-            dto.discount,
-            status_str,
+            money::parse_amount(dto.iva, "iva")?,
+            dto.discount.map(|discount| money::parse_amount(discount, "discount")).transpose()?,
+            status.as_str().to_string(),
+            NumberingConfig::current().yearly_reset,
+        ).await?;
+
+        self.history_repository.record(
+            &mut tx,
+            &order.id,
+            None,
+            status.as_str(),
+            created_by,
+            None,
         ).await?;
 
         // Get the order with client info for the response
-        let order_with_client = self.repository.get_with_client_info(&order.id).await?;
+        let order_with_client = self.repository.get_with_client_info(&mut tx, &order.id).await?;
+        commit_transaction(tx).await?;
         match order_with_client {
-            Some((order, client_name, client_contact)) => {
-                Ok(OrderResponseDto::from((order, client_name, client_contact)))
-            }
-            None => Err("Failed to retrieve created order with client info".to_string()),
+            Some(order_with_client) => OrderResponseDto::try_from(order_with_client),
+            None => Err(AppError::Failed("Failed to retrieve created order with client info".to_string())),
         }
     }
 
-    pub async fn get_order_by_id(&self, id: &str) -> Result<Option<OrderResponseDto>, String> {
-        let order_with_client = self.repository.get_with_client_info(id).await?;
-        Ok(order_with_client.map(|(order, client_name, client_contact)| {
-            OrderResponseDto::from((order, client_name, client_contact))
-        }))
+    pub async fn get_order_by_id(&self, id: &str) -> AppResult<Option<OrderResponseDto>> {
+        let mut conn = acquire_connection().await?;
+        let order_with_client = self.repository.get_with_client_info(&mut conn, id).await?;
+        order_with_client.map(OrderResponseDto::try_from).transpose()
     }
 
-    pub async fn get_orders_by_client_id(&self, client_id: &str) -> Result<Vec<OrderResponseDto>, String> {
-        let orders_with_client = self.repository.get_by_client_id_with_client_info(client_id).await?;
-        Ok(orders_with_client.into_iter().map(|(order, client_name, client_contact)| {
-            OrderResponseDto::from((order, client_name, client_contact))
-        }).collect())
+    pub async fn get_orders_by_client_id(&self, client_id: &str) -> AppResult<Vec<OrderResponseDto>> {
+        let mut conn = acquire_connection().await?;
+        let orders_with_client = self.repository.get_by_client_id_with_client_info(&mut conn, client_id).await?;
+        orders_with_client.into_iter().map(OrderResponseDto::try_from).collect()
     }
 
-    pub async fn get_orders_by_date_range(&self, start_date: Date, end_date: Date) -> Result<Vec<OrderResponseDto>, String> {
-        let orders = self.repository.get_by_date_range(start_date, end_date).await?;
-        
-        // For each order, get client info
-        let mut order_responses = Vec::new();
-        for order in orders {
-            if let Some((order, client_name, client_contact)) = self.repository.get_with_client_info(&order.id).await? {
-                order_responses.push(OrderResponseDto::from((order, client_name, client_contact)));
-            }
-        }
-        
-        Ok(order_responses)
+    pub async fn get_orders_by_date_range(&self, start_date: Date, end_date: Date) -> AppResult<Vec<OrderResponseDto>> {
+        let mut conn = acquire_connection().await?;
+        let orders_with_client = self.repository.get_by_date_range_with_client_info(&mut conn, start_date, end_date).await?;
+        orders_with_client.into_iter().map(OrderResponseDto::try_from).collect()
     }
 
-    pub async fn list_orders(&self) -> Result<Vec<OrderResponseDto>, String> {
-        let orders_with_client = self.repository.list_with_client_info().await?;
-        Ok(orders_with_client.into_iter().map(|(order, client_name, client_contact)| {
-            OrderResponseDto::from((order, client_name, client_contact))
-        }).collect())
+    pub async fn list_orders(&self) -> AppResult<Vec<OrderResponseDto>> {
+        let mut conn = acquire_connection().await?;
+        let orders_with_client = self.repository.list_with_client_info(&mut conn).await?;
+        orders_with_client.into_iter().map(OrderResponseDto::try_from).collect()
     }
 
-    pub async fn update_order(&self, id: &str, dto: UpdateOrderDto) -> Result<Option<OrderResponseDto>, String> {
-        let status_str = dto.status.map(|status| match status {
-            OrderStatus::OrderReceived => "order_received".to_string(),
-            OrderStatus::InProduction => "in_production".to_string(),
-            OrderStatus::ReadyForDelivery => "ready_for_delivery".to_string(),
-            OrderStatus::Delivered => "delivered".to_string(),
-        });
-        
+    pub async fn list_orders_page(&self, filter: OrderFilter, page: PageRequest<OrderSortField>) -> AppResult<Page<OrderResponseDto>> {
+        let mut conn = acquire_connection().await?;
+        let (orders_with_client, total) = self.repository.list_page_with_client_info(&mut conn, &filter, &page).await?;
+        Ok(Page {
+            items: orders_with_client.into_iter().map(OrderResponseDto::try_from).collect::<AppResult<_>>()?,
+            total,
+            offset: page.offset(),
+            limit: page.limit(),
+        })
+    }
+
+    pub async fn update_order(&self, id: &str, dto: UpdateOrderDto, changed_by: Option<String>) -> AppResult<Option<OrderResponseDto>> {
+        let mut tx = begin_transaction().await?;
+        if !self.repository.lock(&mut tx, id).await? {
+            return Ok(None);
+        }
+
+        let current = self.repository.get_by_id(&mut tx, id).await?
+            .ok_or_else(|| AppError::not_found("order", id))?;
+
+        // An order already past its due date stays editable; only a new date is checked
+        let new_due_date = dto.due_date.filter(|due_date| *due_date != current.due_date);
+        dto.rules(Validator::new())
+            .optional(new_due_date.as_ref(), |v, due_date| v.not_in_past("due_date", *due_date))
+            .finish()?;
+
+        // Only transitions allowed by OrderStatus are accepted; same status is a no-op
+        let current_status = OrderStatus::parse(&current.status)
+            .ok_or_else(|| AppError::Database(format!("Order {} has unknown status '{}'", id, current.status)))?;
+        let status_change = match dto.status {
+            Some(next) if next != current_status => {
+                if !current_status.can_transition_to(next) {
+                    return Err(AppError::invalid(
+                        "status",
+                        "invalid_transition",
+                        format!("Cannot change order status from {} to {}", current_status.as_str(), next.as_str()),
+                    ));
+                }
+                Some(next)
+            }
+            _ => None,
+        };
+
+        let discount = dto.discount.map(|discount| money::parse_amount(discount, "discount")).transpose()?;
+        let iva = dto.iva.map(|iva| money::parse_amount(iva, "iva")).transpose()?;
+        let totals_changed = discount.is_some_and(|discount| discount != current.discount)
+            || iva.is_some_and(|iva| iva != current.iva);
+
         let order = self.repository.update(
+            &mut tx,
             id,
             dto.name,
             dto.client_id,
             dto.due_date,
-            dto.discount,
-            dto.iva,
-            dto.subtotal,
-            dto.total,
-            status_str,
+            discount,
+            iva,
+            status_change.map(|status| status.as_str().to_string()),
         ).await?;
 
-        match order {
-            Some(_) => {
-                // Get the updated order with client info
-                let order_with_client = self.repository.get_with_client_info(id).await?;
-                Ok(order_with_client.map(|(order, client_name, client_contact)| {
-                    OrderResponseDto::from((order, client_name, client_contact))
-                }))
-            }
-            None => Ok(None),
+        // Subtotal and total are always derived from the order's items
+        if order.is_some() && totals_changed {
+            self.recalculate_order_totals(&mut tx, id).await?;
+        }
+
+        if let Some(next) = status_change {
+            self.history_repository.record(
+                &mut tx,
+                id,
+                Some(current_status.as_str()),
+                next.as_str(),
+                changed_by,
+                dto.status_note,
+            ).await?;
         }
+
+        let order_with_client = match order {
+            // Get the updated order with client info
+            Some(_) => self.repository.get_with_client_info(&mut tx, id).await?,
+            None => None,
+        };
+        commit_transaction(tx).await?;
+
+        order_with_client.map(OrderResponseDto::try_from).transpose()
+    }
+
+    pub async fn get_order_status_history(&self, order_id: &str) -> AppResult<Vec<OrderStatusChangeDto>> {
+        let mut conn = acquire_connection().await?;
+        let history = self.history_repository.get_by_order_id(&mut conn, order_id).await?;
+        OrderStatusChangeDto::from_history(history)
+    }
+
+    pub async fn delete_order(&self, id: &str) -> AppResult<bool> {
+        let mut tx = begin_transaction().await?;
+        let result = self.repository.delete(&mut tx, id).await?;
+        commit_transaction(tx).await?;
+        Ok(result)
     }
 
-    pub async fn delete_order(&self, id: &str) -> Result<bool, String> {
-        self.repository.delete(id).await
+    /// Shortcut that records a cash payment dated today
+    pub async fn pay_order_debt(&self, id: &str, payment_amount: Decimal, received_by: Option<String>) -> AppResult<bool> {
+        let payment_service = crate::services::PaymentService::new();
+        payment_service.create_payment(CreatePaymentDto {
+            order_id: id.to_string(),
+            amount: payment_amount,
+            payment_date: None,
+            method: PaymentMethod::Cash,
+            reference: None,
+            received_by,
+        }).await?;
+
+        Ok(true)
     }
 
-    pub async fn pay_order_debt(&self, id: &str, payment_amount: f64) -> Result<bool, String> {
-        if payment_amount <= 0.0 {
-            return Err("Payment amount must be greater than 0".to_string());
+    /// Locks the order until the transaction ends; fails if it does not exist
+    pub async fn lock_order(&self, conn: &mut PgConnection, order_id: &str) -> AppResult<()> {
+        if self.repository.lock(conn, order_id).await? {
+            Ok(())
+        } else {
+            Err(AppError::not_found("order", order_id))
         }
-        
-        self.repository.pay_debt(id, payment_amount).await
     }
 
-    /// Recalcula os totais da order considerando clothes e impressions
-    pub async fn recalculate_order_totals(&self, order_id: &str) -> Result<(), String> {
+    /// Recalculates the order totals from its clothes and impressions.
+    /// Runs on the caller's connection so it shares the transaction of the change.
+    pub async fn recalculate_order_totals(&self, conn: &mut PgConnection, order_id: &str) -> AppResult<()> {
         // Get the order to access IVA and discount
-        let order = self.repository.get_by_id(order_id).await?
-            .ok_or("Order not found")?;
+        let order = self.repository.get_by_id(&mut *conn, order_id).await?
+            .ok_or_else(|| AppError::not_found("order", order_id))?;
         
-        // Calculate clothes total using the service to get DTOs with calculated totals
-        let clothes_service = crate::services::ClothesService::new();
-        let clothes_list = clothes_service.get_clothes_by_order_id(order_id).await?;
-        let clothes_total: f64 = clothes_list.iter().map(|clothes| clothes.calculate_total_price()).sum();
+        // Calculate clothes total using DTOs with calculated totals
+        let clothes_list = ClothesRepository.get_by_order_id(&mut *conn, order_id).await?;
+        let mut clothes_total = Decimal::ZERO;
+        for clothes in clothes_list {
+            let services = ClothingServiceRepository.get_by_clothes_id(&mut *conn, &clothes.id).await?;
+            clothes_total += ClothesDto::from_model(clothes, services)?.calculate_total_price();
+        }
         
         // Calculate impressions total
-        let impression_repo = crate::repositories::ImpressionRepository;
-        let impressions = impression_repo.get_by_order_id(order_id).await?;
-        let impressions_total: f64 = impressions.iter().map(|impression| impression.price).sum();
+        let impressions = ImpressionRepository.get_by_order_id(&mut *conn, order_id).await?;
+        let impressions_total: Decimal = impressions.iter().map(|impression| impression.price).sum();
         
         // Calculate combined subtotal
         let subtotal = clothes_total + impressions_total;
         
-        // Calculate total with IVA and discount
-        let iva_amount = subtotal * order.iva / 100.0;
-        let total = subtotal + iva_amount - order.discount;
+        // Calculate total with IVA and discount (rounding rules in crate::money)
+        let total = money::order_total(subtotal, order.iva, order.discount);
         
         // Update the order with new totals
-        self.repository.update_financial_values(order_id, subtotal, total).await?;
+        self.repository.update_financial_values(&mut *conn, order_id, subtotal, total).await?;
         
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dto::page_dto::{SortDirection, MAX_PAGE_LIMIT};
+    use crate::test_support::{create_client, create_order, dec, with_app_database};
+
+    #[test]
+    fn pages_orders_sorted_by_total() {
+        with_app_database(|| async {
+            let client = create_client("Paging").await;
+            for price in ["30.00", "10.00", "20.00"] {
+                create_order(&client.id, price).await;
+            }
+            let filter = OrderFilter { client_id: Some(client.id.clone()), ..Default::default() };
+            let page = |offset, direction| PageRequest { offset, limit: 2, sort_by: Some(OrderSortField::Total), direction };
+            let totals = |page: Page<OrderResponseDto>| -> Vec<Decimal> {
+                page.items.into_iter().map(|order| order.total).collect()
+            };
+
+            let service = OrderService::new();
+            let first = service.list_orders_page(filter.clone(), page(0, SortDirection::Asc)).await.unwrap();
+            assert_eq!((first.total, first.offset, first.limit), (3, 0, 2));
+            assert_eq!(totals(first), [dec("10.00"), dec("20.00")]);
+
+            let last = service.list_orders_page(filter.clone(), page(2, SortDirection::Asc)).await.unwrap();
+            assert_eq!(totals(last), [dec("30.00")]);
+
+            let descending = service.list_orders_page(filter, page(0, SortDirection::Desc)).await.unwrap();
+            assert_eq!(totals(descending), [dec("30.00"), dec("20.00")]);
+        });
+    }
+
+    #[test]
+    fn out_of_range_offsets_and_limits_are_clamped() {
+        with_app_database(|| async {
+            let client = create_client("Paging").await;
+            create_order(&client.id, "10.00").await;
+            let filter = OrderFilter { client_id: Some(client.id.clone()), ..Default::default() };
+
+            let page = PageRequest { offset: -5, limit: 100_000, sort_by: None, direction: SortDirection::Desc };
+            let page = OrderService::new().list_orders_page(filter, page).await.unwrap();
+            assert_eq!((page.offset, page.limit, page.items.len()), (0, MAX_PAGE_LIMIT, 1));
+        });
+    }
+}
@@ -0,0 +1,189 @@
+use crate::database::{acquire_connection, begin_transaction, commit_transaction};
+use crate::money;
+use crate::models::OrderStatus;
+use crate::dto::{CreatePaymentDto, PaymentDto};
+use crate::repositories::{OrderRepository, PaymentRepository};
+use time::OffsetDateTime;
+use rust_decimal::Decimal;
+use crate::error::{AppError, AppResult};
+use crate::validation::Validate;
+
+pub struct PaymentService {
+    payment_repository: PaymentRepository,
+    order_repository: OrderRepository,
+}
+
+impl PaymentService {
+    pub fn new() -> Self {
+        Self {
+            payment_repository: PaymentRepository,
+            order_repository: OrderRepository,
+        }
+    }
+
+    pub async fn create_payment(&self, dto: CreatePaymentDto) -> AppResult<PaymentDto> {
+        dto.validate()?;
+        let amount = money::round_money(dto.amount);
+        if amount <= Decimal::ZERO {
+            return Err(AppError::invalid("amount", "not_positive", "Payment amount must be greater than 0"));
+        }
+
+        let method = dto.to_method_string();
+        let payment_date = dto.payment_date.unwrap_or_else(|| OffsetDateTime::now_utc().date());
+
+        let mut tx = begin_transaction().await?;
+
+        // Serialise payments on the same order so the debt stays consistent
+        if !self.order_repository.lock(&mut tx, &dto.order_id).await? {
+            return Err(AppError::not_found("order", &dto.order_id));
+        }
+        let order = self.order_repository.get_by_id(&mut tx, &dto.order_id).await?
+            .ok_or_else(|| AppError::not_found("order", &dto.order_id))?;
+        // A cancelled order owes nothing, so a payment on it would go unaccounted
+        if OrderStatus::parse(&order.status) == Some(OrderStatus::Cancelled) {
+            return Err(AppError::invalid("order_id", "order_cancelled", "Payments cannot be recorded on a cancelled order"));
+        }
+
+        let payment = self.payment_repository.create(
+            &mut tx,
+            dto.order_id.clone(),
+            amount,
+            payment_date,
+            method,
+            dto.reference,
+            dto.received_by,
+        ).await?;
+
+        // Debt is derived from the ledger, so refresh it after every change
+        self.order_repository.refresh_debt(&mut tx, &dto.order_id).await?;
+        commit_transaction(tx).await?;
+
+        PaymentDto::from_model(payment)
+    }
+
+    pub async fn get_payment_by_id(&self, id: &str) -> AppResult<Option<PaymentDto>> {
+        let mut conn = acquire_connection().await?;
+        match self.payment_repository.get_by_id(&mut conn, id).await? {
+            Some(payment) => Ok(Some(PaymentDto::from_model(payment)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn get_payments_by_order_id(&self, order_id: &str) -> AppResult<Vec<PaymentDto>> {
+        let mut conn = acquire_connection().await?;
+        let payments = self.payment_repository.get_by_order_id(&mut conn, order_id).await?;
+        payments.into_iter().map(PaymentDto::from_model).collect()
+    }
+
+    pub async fn get_total_paid(&self, order_id: &str) -> AppResult<Decimal> {
+        let mut conn = acquire_connection().await?;
+        self.payment_repository.get_total_paid(&mut conn, order_id).await
+    }
+
+    pub async fn delete_payment(&self, id: &str) -> AppResult<bool> {
+        let mut tx = begin_transaction().await?;
+        let payment = self.payment_repository.get_by_id(&mut tx, id).await?
+            .ok_or_else(|| AppError::not_found("payment", id))?;
+        self.order_repository.lock(&mut tx, &payment.order_id).await?;
+
+        let result = self.payment_repository.delete(&mut tx, id).await?;
+
+        if result {
+            self.order_repository.refresh_debt(&mut tx, &payment.order_id).await?;
+        }
+        commit_transaction(tx).await?;
+
+        Ok(result)
+    }
+}
+
+impl Default for PaymentService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dto::UpdateOrderDto;
+    use crate::models::PaymentMethod;
+    use crate::services::{ClientService, OrderService};
+    use crate::test_support::{create_client, create_order, dec, with_app_database};
+
+    fn payment(order_id: &str, amount: &str) -> CreatePaymentDto {
+        CreatePaymentDto {
+            order_id: order_id.to_string(),
+            amount: dec(amount),
+            payment_date: None,
+            method: PaymentMethod::Cash,
+            reference: None,
+            received_by: None,
+        }
+    }
+
+    async fn order_debt(order_id: &str) -> Decimal {
+        OrderService::new().get_order_by_id(order_id).await.unwrap().unwrap().debt
+    }
+
+    #[test]
+    fn debt_is_the_total_less_the_payments() {
+        with_app_database(|| async {
+            let client = create_client("Ledger").await;
+            let order = create_order(&client.id, "100.00").await;
+            assert_eq!(order.debt, dec("100.00"));
+
+            let service = PaymentService::new();
+            let deposit = service.create_payment(payment(&order.id, "30.00")).await.unwrap();
+            service.create_payment(payment(&order.id, "25.50")).await.unwrap();
+            assert_eq!(order_debt(&order.id).await, dec("44.50"));
+            assert_eq!(service.get_total_paid(&order.id).await.unwrap(), dec("55.50"));
+
+            // Removing a payment puts what it covered back into the debt
+            assert!(service.delete_payment(&deposit.id).await.unwrap());
+            assert_eq!(order_debt(&order.id).await, dec("74.50"));
+            let client = ClientService::new().get_client_by_id(&client.id).await.unwrap().unwrap();
+            assert_eq!(client.debt, dec("74.50"));
+        });
+    }
+
+    #[test]
+    fn paying_more_than_the_total_leaves_no_debt() {
+        with_app_database(|| async {
+            let client = create_client("Overpaid").await;
+            let order = create_order(&client.id, "40.00").await;
+            PaymentService::new().create_payment(payment(&order.id, "50.00")).await.unwrap();
+            assert_eq!(order_debt(&order.id).await, Decimal::ZERO);
+        });
+    }
+
+    #[test]
+    fn rejects_payments_on_cancelled_or_missing_orders() {
+        with_app_database(|| async {
+            let client = create_client("Cancelled").await;
+            let order = create_order(&client.id, "40.00").await;
+            OrderService::new()
+                .update_order(&order.id, UpdateOrderDto {
+                    name: None,
+                    client_id: None,
+                    due_date: None,
+                    discount: None,
+                    iva: None,
+                    status: Some(OrderStatus::Cancelled),
+                    status_note: None,
+                }, None)
+                .await
+                .unwrap();
+
+            let service = PaymentService::new();
+            match service.create_payment(payment(&order.id, "10.00")).await {
+                Err(AppError::Validation(fields)) => assert_eq!(fields[0].code, "order_cancelled"),
+                other => panic!("expected a validation error, got {:?}", other),
+            }
+            assert!(service.get_payments_by_order_id(&order.id).await.unwrap().is_empty());
+
+            let missing = service.create_payment(payment("no-such-order", "10.00")).await;
+            assert!(matches!(missing, Err(AppError::NotFound { entity: "order", .. })));
+        });
+    }
+}
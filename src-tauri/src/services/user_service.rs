@@ -1,54 +1,221 @@
-use crate::models::User;
-use crate::repositories::user_repository::UserRepository;
-use crate::dto::{LoginDto, CreateUserDto, LoginResponseDto};
+use crate::auth::password::{hash_password, validate_password_strength, verify_dummy_password, verify_password, PasswordCheck};
+use crate::database::{acquire_connection, begin_transaction, commit_transaction};
+use crate::models::{Role, User};
+use crate::repositories::UserRepository;
+use crate::dto::{LoginDto, CreateUserDto, LoginResponseDto, InitialSetupDto, UpdateUserDto, ChangePasswordDto, UserResponseDto};
+use crate::error::{AppError, AppResult};
+use crate::validation::Validate;
 
-pub struct UserService;
+/// Accounts older versions created on every install. Logging in with one of
+/// these passwords only allows changing it.
+const DEFAULT_CREDENTIALS: &[(&str, &str)] = &[("admin", "admin123"), ("user", "user123")];
+
+pub struct UserService {
+    repository: UserRepository,
+}
 
 impl UserService {
-    pub async fn login(login_dto: LoginDto) -> Result<LoginResponseDto, String> {
-        // Buscar usuário por login e password
-        match UserRepository::find_by_login_and_password(&login_dto.login, &login_dto.password).await? {
-            Some(user) => {
+    pub fn new() -> Self {
+        Self {
+            repository: UserRepository,
+        }
+    }
+
+    pub async fn login(&self, login_dto: LoginDto) -> AppResult<LoginResponseDto> {
+        let mut conn = acquire_connection().await?;
+        let user = self.repository.get_by_login(&mut conn, login_dto.login.trim()).await?;
+        let check = match &user {
+            Some(user) => verify_password(&login_dto.password, &user.password),
+            None => {
+                verify_dummy_password(&login_dto.password);
+                PasswordCheck::Invalid
+            }
+        };
+
+        match (user, check) {
+            (Some(user), PasswordCheck::Valid | PasswordCheck::ValidNeedsUpgrade) => {
+                if !user.active {
+                    return Ok(Self::failed_login("This account is disabled"));
+                }
+
+                // Plaintext passwords from before hashing are re-hashed on their first login
+                if let PasswordCheck::ValidNeedsUpgrade = check {
+                    let password_hash = hash_password(&login_dto.password)?;
+                    self.repository.update_password(&mut conn, &user.id, &password_hash).await?;
+                    log::info!("Upgraded plaintext password for user {}", user.login);
+                }
+
+                let must_change_password = DEFAULT_CREDENTIALS.contains(&(user.login.as_str(), login_dto.password.as_str()));
+                if must_change_password {
+                    log::warn!("User {} logged in with the default password and must change it", user.login);
+                }
+
                 Ok(LoginResponseDto {
                     id: user.id,
                     login: user.login,
                     role: user.role,
                     success: true,
-                    message: "Login realizado com sucesso".to_string(),
+                    message: "Logged in successfully".to_string(),
+                    token: String::new(),
+                    must_change_password,
                 })
             }
-            None => Ok(LoginResponseDto {
-                id: String::new(),
-                login: String::new(),
-                role: String::new(),
-                success: false,
-                message: "Login ou password inválidos".to_string(),
-            })
+            _ => Ok(Self::failed_login("Invalid login or password")),
         }
     }
 
-    pub async fn create_user(create_user_dto: CreateUserDto) -> Result<User, String> {
-        // Verificar se o role é válido
-        if create_user_dto.role != "admin" && create_user_dto.role != "user" {
-            return Err("Role deve ser 'admin' ou 'user'".to_string());
+    fn failed_login(message: &str) -> LoginResponseDto {
+        LoginResponseDto {
+            id: String::new(),
+            login: String::new(),
+            role: String::new(),
+            success: false,
+            message: message.to_string(),
+            token: String::new(),
+            must_change_password: false,
         }
+    }
+
+    pub async fn create_user(&self, create_user_dto: CreateUserDto) -> AppResult<UserResponseDto> {
+        create_user_dto.validate()?;
+        let login = create_user_dto.login.trim().to_string();
+        let mut conn = acquire_connection().await?;
 
-        // Verificar se já existe um usuário com este login
-        if let Some(_) = UserRepository::find_by_login(&create_user_dto.login).await? {
-            return Err("Já existe um usuário com este login".to_string());
+        if self.repository.get_by_login(&mut conn, &login).await?.is_some() {
+            return Err(AppError::Conflict("A user with this login already exists".to_string()));
         }
 
-        // Criar novo usuário
         let new_user = User::new(
-            create_user_dto.login,
-            create_user_dto.password,
+            login,
+            hash_password(&create_user_dto.password)?,
             create_user_dto.role,
         );
 
-        UserRepository::create_user(&new_user).await
+        Ok(self.repository.create(&mut conn, &new_user).await?.into())
+    }
+
+    pub async fn list_users(&self) -> AppResult<Vec<UserResponseDto>> {
+        let mut conn = acquire_connection().await?;
+        let users = self.repository.list(&mut conn).await?;
+        Ok(users.into_iter().map(UserResponseDto::from).collect())
+    }
+
+    /// True while there is no user yet (first run)
+    pub async fn needs_initial_setup(&self) -> AppResult<bool> {
+        let mut conn = acquire_connection().await?;
+        Ok(self.repository.count(&mut conn).await? == 0)
+    }
+
+    /// Creates the first admin; only works while there is no user at all
+    pub async fn create_initial_admin(&self, setup_dto: InitialSetupDto) -> AppResult<UserResponseDto> {
+        setup_dto.validate()?;
+        let login = setup_dto.login.trim().to_string();
+
+        let admin = User::new(
+            login,
+            hash_password(&setup_dto.password)?,
+            Role::Admin.as_str().to_string(),
+        );
+
+        let mut tx = begin_transaction().await?;
+        self.repository.lock(&mut tx).await?;
+        let created = self.repository.create_first(&mut tx, &admin).await?;
+        commit_transaction(tx).await?;
+
+        created
+            .map(UserResponseDto::from)
+            .ok_or_else(|| AppError::Conflict("Initial setup has already been completed".to_string()))
+    }
+
+    /// Changes a user's login and/or role
+    pub async fn update_user(&self, id: &str, dto: UpdateUserDto) -> AppResult<Option<UserResponseDto>> {
+        dto.validate()?;
+
+        let mut tx = begin_transaction().await?;
+        self.repository.lock(&mut tx).await?;
+        let Some(current) = self.repository.get_by_id(&mut tx, id).await? else {
+            return Ok(None);
+        };
+
+        let login = match dto.login {
+            Some(login) => {
+                let login = login.trim().to_string();
+                if let Some(existing) = self.repository.get_by_login(&mut tx, &login).await? {
+                    if existing.id != id {
+                        return Err(AppError::Conflict("A user with this login already exists".to_string()));
+                    }
+                }
+                login
+            }
+            None => current.login,
+        };
+
+        let demoting = current.role == "admin" && dto.role.as_deref().is_some_and(|role| role != "admin");
+        if demoting && current.active {
+            self.repository.ensure_other_active_admin(&mut tx, id).await?;
+        }
+
+        let role = dto.role.unwrap_or(current.role);
+        let user = self.repository.update(&mut tx, id, &login, &role).await?;
+        commit_transaction(tx).await?;
+        Ok(user.map(UserResponseDto::from))
+    }
+
+    /// Enables or disables an account without deleting it
+    pub async fn set_user_active(&self, id: &str, active: bool) -> AppResult<Option<UserResponseDto>> {
+        let mut tx = begin_transaction().await?;
+        self.repository.lock(&mut tx).await?;
+
+        if !active {
+            let user = self.repository.get_by_id(&mut tx, id).await?;
+            if user.is_some_and(|user| user.role == "admin") {
+                self.repository.ensure_other_active_admin(&mut tx, id).await?;
+            }
+        }
+
+        let user = self.repository.set_active(&mut tx, id, active).await?;
+        commit_transaction(tx).await?;
+        Ok(user.map(UserResponseDto::from))
     }
 
-    pub async fn list_users() -> Result<Vec<User>, String> {
-        UserRepository::list_users().await
+    pub async fn delete_user(&self, id: &str) -> AppResult<bool> {
+        let mut tx = begin_transaction().await?;
+        self.repository.lock(&mut tx).await?;
+
+        let user = self.repository.get_by_id(&mut tx, id).await?;
+        if user.is_some_and(|user| user.role == "admin") {
+            self.repository.ensure_other_active_admin(&mut tx, id).await?;
+        }
+
+        let deleted = self.repository.delete(&mut tx, id).await?;
+        commit_transaction(tx).await?;
+        Ok(deleted)
+    }
+
+    /// A user changes their own password, confirming the current one
+    pub async fn change_password(&self, user_id: &str, dto: ChangePasswordDto) -> AppResult<bool> {
+        dto.validate()?;
+        let mut conn = acquire_connection().await?;
+        let user = self.repository.get_by_id(&mut conn, user_id).await?
+            .ok_or_else(|| AppError::not_found("user", user_id))?;
+
+        if verify_password(&dto.current_password, &user.password) == PasswordCheck::Invalid {
+            return Err(AppError::invalid("current_password", "incorrect", "Current password is incorrect"));
+        }
+        if DEFAULT_CREDENTIALS.contains(&(user.login.as_str(), dto.new_password.as_str())) {
+            return Err(AppError::invalid("new_password", "default_password", "The new password cannot be the default one"));
+        }
+
+        let password_hash = hash_password(&dto.new_password)?;
+        self.repository.update_password(&mut conn, user_id, &password_hash).await
+    }
+
+    /// An admin sets a new password for another user
+    pub async fn reset_password(&self, id: &str, new_password: &str) -> AppResult<bool> {
+        validate_password_strength("new_password", new_password)?;
+
+        let password_hash = hash_password(new_password)?;
+        let mut conn = acquire_connection().await?;
+        self.repository.update_password(&mut conn, id, &password_hash).await
     }
 }
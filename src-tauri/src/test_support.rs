@@ -0,0 +1,142 @@
+//! Helpers for tests that need PostgreSQL. They run against the server in
+//! `EDITART_TEST_DATABASE_URL` (e.g. `postgres://postgres@localhost/postgres`)
+//! and are skipped when it is not set. Each test process creates and
+//! migrates a database of its own (`editart_test_*`, left on the server),
+//! which the services use as the app's pool; tests that change the schema or
+//! clear tables get a separate one that is dropped afterwards.
+
+use crate::config::DatabaseConfig;
+use crate::database;
+use crate::dto::{ClientResponseDto, CreateClientDto, CreateImpressionDto, CreateOrderDto, OrderResponseDto};
+use crate::local_time::today;
+use crate::services::{ClientService, ImpressionService, OrderService};
+use rust_decimal::Decimal;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::{Connection, Executor, PgConnection, PgPool};
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use tokio::runtime::Runtime;
+use uuid::Uuid;
+
+const DATABASE_URL_VAR: &str = "EDITART_TEST_DATABASE_URL";
+
+/// A pool belongs to the runtime that opened it, so every database test
+/// runs on this one.
+fn runtime() -> &'static Runtime {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .expect("Failed to start the test runtime")
+    })
+}
+
+fn server_config() -> Option<DatabaseConfig> {
+    let url = std::env::var(DATABASE_URL_VAR).ok().filter(|url| !url.trim().is_empty());
+    if url.is_none() {
+        eprintln!("{} is not set, skipping database test", DATABASE_URL_VAR);
+    }
+    url.map(|url| DatabaseConfig::from_url(&url).expect("Invalid test database URL"))
+}
+
+async fn server_connection(server: &DatabaseConfig) -> PgConnection {
+    PgConnection::connect_with(&server.connect_options())
+        .await
+        .expect("Failed to connect to the test database server")
+}
+
+async fn create_database(server: &DatabaseConfig) -> DatabaseConfig {
+    let dbname = format!("editart_test_{}", Uuid::new_v4().simple());
+    server_connection(server).await
+        .execute(format!("CREATE DATABASE {}", dbname).as_str())
+        .await
+        .expect("Failed to create the test database");
+    DatabaseConfig { dbname, ..server.clone() }
+}
+
+/// Runs `test` with the app connected to this process's test database, which
+/// is fully migrated and shared with the other tests: use data of your own.
+pub fn with_app_database<Fut: Future<Output = ()>>(test: impl FnOnce() -> Fut) {
+    static CONNECTED: OnceLock<bool> = OnceLock::new();
+    let connected = *CONNECTED.get_or_init(|| match server_config() {
+        Some(server) => runtime().block_on(async {
+            let config = create_database(&server).await;
+            database::connect(&config).await.expect("Failed to connect to the test database");
+            true
+        }),
+        None => false,
+    });
+    if connected {
+        runtime().block_on(test());
+    }
+}
+
+/// Runs `test` on a new, empty database that is dropped afterwards
+pub fn with_empty_database<Fut: Future<Output = ()>>(test: impl FnOnce(PgPool) -> Fut) {
+    let Some(server) = server_config() else {
+        return;
+    };
+    runtime().block_on(async {
+        let config = create_database(&server).await;
+        let pool = PgPoolOptions::new()
+            .connect_with(config.connect_options())
+            .await
+            .expect("Failed to connect to the test database");
+        test(pool.clone()).await;
+
+        pool.close().await;
+        server_connection(&server).await
+            .execute(format!("DROP DATABASE {} WITH (FORCE)", config.dbname).as_str())
+            .await
+            .expect("Failed to drop the test database");
+    });
+}
+
+pub fn dec(value: &str) -> Decimal {
+    Decimal::from_str(value).unwrap()
+}
+
+/// A client named `name` plus a unique suffix, so tests sharing the app
+/// database can search for their own rows
+pub async fn create_client(name: &str) -> ClientResponseDto {
+    ClientService::new()
+        .create_client(CreateClientDto {
+            name: format!("{} {}", name, Uuid::new_v4().simple()),
+            nuit: String::new(),
+            contact: String::new(),
+            category: "Empresa".to_string(),
+            observations: String::new(),
+        })
+        .await
+        .expect("Failed to create test client")
+}
+
+/// An order without IVA holding one impression at `price`
+pub async fn create_order(client_id: &str, price: &str) -> OrderResponseDto {
+    let orders = OrderService::new();
+    let order = orders
+        .create_order(CreateOrderDto {
+            name: "Test order".to_string(),
+            client_id: client_id.to_string(),
+            due_date: today() + time::Duration::days(30),
+            iva: Decimal::ZERO,
+            discount: None,
+            status: None,
+        }, None)
+        .await
+        .expect("Failed to create test order");
+    ImpressionService::new()
+        .create_impression(CreateImpressionDto {
+            order_id: order.id.clone(),
+            name: "Banner".to_string(),
+            size: "A3".to_string(),
+            material: "Vinil".to_string(),
+            description: String::new(),
+            price: dec(price),
+        })
+        .await
+        .expect("Failed to add an impression to the test order");
+    orders.get_order_by_id(&order.id).await.unwrap().expect("Test order disappeared")
+}
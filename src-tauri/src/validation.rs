@@ -0,0 +1,210 @@
+use crate::error::{AppError, AppResult, FieldError};
+use crate::local_time::today;
+use crate::nuit;
+use rust_decimal::Decimal;
+use time::Date;
+
+/// Checks a request DTO before the service touches a repository.
+pub trait Validate {
+    /// Adds this request's rules to `validator`
+    fn rules(&self, validator: Validator) -> Validator;
+
+    fn validate(&self) -> AppResult<()> {
+        self.rules(Validator::new()).finish()
+    }
+}
+
+/// Collects every failed rule, so all invalid fields come back in one
+/// `AppError::Validation`. Rules are chained:
+/// `validator.required("name", &self.name).percentage("iva", self.iva)`.
+#[derive(Debug, Default)]
+pub struct Validator {
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an error for `field` unless `valid`
+    pub fn check(mut self, valid: bool, field: &str, code: &str, message: impl Into<String>) -> Self {
+        if !valid {
+            self.errors.push(FieldError::new(field, code, message));
+        }
+        self
+    }
+
+    /// Runs `rules` only when the value is present, for partial updates
+    pub fn optional<T>(self, value: Option<&T>, rules: impl FnOnce(Self, &T) -> Self) -> Self
+    where
+        T: ?Sized,
+    {
+        match value {
+            Some(value) => rules(self, value),
+            None => self,
+        }
+    }
+
+    /// Validates a nested item, reporting its fields as `prefix.field`
+    pub fn nested(mut self, prefix: &str, item: &impl Validate) -> Self {
+        let nested = item.rules(Validator::new());
+        self.errors.extend(nested.errors.into_iter().map(|error| FieldError {
+            field: format!("{}.{}", prefix, error.field),
+            ..error
+        }));
+        self
+    }
+
+    pub fn required(self, field: &str, value: &str) -> Self {
+        let valid = !value.trim().is_empty();
+        self.check(valid, field, "required", format!("{} is required", field))
+    }
+
+    pub fn min_chars(self, field: &str, value: &str, min: usize) -> Self {
+        let valid = value.chars().count() >= min;
+        self.check(valid, field, "too_short", format!("{} must be at least {} characters long", field, min))
+    }
+
+    pub fn non_negative(self, field: &str, value: Decimal) -> Self {
+        let valid = !value.is_sign_negative();
+        self.check(valid, field, "negative", format!("{} cannot be negative", field))
+    }
+
+    pub fn positive(self, field: &str, value: Decimal) -> Self {
+        let valid = value > Decimal::ZERO;
+        self.check(valid, field, "not_positive", format!("{} must be greater than 0", field))
+    }
+
+    /// Between 0 and 100 inclusive
+    pub fn percentage(self, field: &str, value: Decimal) -> Self {
+        let valid = value >= Decimal::ZERO && value <= Decimal::ONE_HUNDRED;
+        self.check(valid, field, "out_of_range", format!("{} must be between 0 and 100", field))
+    }
+
+    /// Today or later, in local time
+    pub fn not_in_past(self, field: &str, value: Date) -> Self {
+        let valid = value >= today();
+        self.check(valid, field, "in_past", format!("{} cannot be in the past", field))
+    }
+
+    pub fn one_of(self, field: &str, value: &str, allowed: &[&str]) -> Self {
+        let valid = allowed.contains(&value);
+        self.check(valid, field, "invalid", format!("{} must be one of: {}", field, allowed.join(", ")))
+    }
+
+    /// A Mozambican NUIT, checked after `nuit::normalize`. A missing NUIT
+    /// (empty or a placeholder) is valid.
+    pub fn nuit(self, field: &str, value: &str) -> Self {
+        let nuit = nuit::normalize(value);
+        if nuit.is_empty() {
+            return self;
+        }
+        if !nuit::is_well_formed(&nuit) {
+            return self.check(false, field, "invalid_format", format!("{} must have {} digits", field, nuit::NUIT_LENGTH));
+        }
+        let valid = nuit::has_valid_check_digit(&nuit);
+        self.check(valid, field, "invalid_check_digit", format!("{} has an invalid check digit", field))
+    }
+
+    pub fn finish(self) -> AppResult<()> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(AppError::Validation(self.errors))
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn dec(value: &str) -> Decimal {
+        Decimal::from_str(value).unwrap()
+    }
+
+    /// `(field, code)` of every failed rule, in order
+    fn failures(validator: Validator) -> Vec<(String, String)> {
+        match validator.finish() {
+            Ok(()) => Vec::new(),
+            Err(AppError::Validation(errors)) => errors.into_iter().map(|error| (error.field, error.code)).collect(),
+            Err(other) => panic!("expected a validation error, got {:?}", other),
+        }
+    }
+
+    fn failed(field: &str, code: &str) -> Vec<(String, String)> {
+        vec![(field.to_string(), code.to_string())]
+    }
+
+    #[test]
+    fn collects_every_failed_rule() {
+        let validator = Validator::new()
+            .required("name", " ")
+            .required("contact", "84 123 4567")
+            .percentage("iva", dec("101"))
+            .non_negative("discount", dec("-1"));
+        assert_eq!(failures(validator), [
+            ("name".to_string(), "required".to_string()),
+            ("iva".to_string(), "out_of_range".to_string()),
+            ("discount".to_string(), "negative".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn numeric_rules_include_their_bounds() {
+        assert!(failures(Validator::new().percentage("iva", dec("0")).percentage("iva", dec("100"))).is_empty());
+        assert_eq!(failures(Validator::new().percentage("iva", dec("-0.01"))), failed("iva", "out_of_range"));
+        assert!(failures(Validator::new().non_negative("price", Decimal::ZERO)).is_empty());
+        assert_eq!(failures(Validator::new().positive("amount", Decimal::ZERO)), failed("amount", "not_positive"));
+        assert!(failures(Validator::new().positive("amount", dec("0.01"))).is_empty());
+    }
+
+    #[test]
+    fn text_rules() {
+        assert_eq!(failures(Validator::new().min_chars("password", "abc", 4)), failed("password", "too_short"));
+        // Counts characters, not bytes
+        assert!(failures(Validator::new().min_chars("password", "ção!", 4)).is_empty());
+        assert!(failures(Validator::new().one_of("role", "admin", &["admin", "user"])).is_empty());
+        assert_eq!(failures(Validator::new().one_of("role", "root", &["admin", "user"])), failed("role", "invalid"));
+    }
+
+    #[test]
+    fn due_dates_may_be_today_but_not_earlier() {
+        assert!(failures(Validator::new().not_in_past("due_date", today())).is_empty());
+        let yesterday = today() - time::Duration::days(1);
+        assert_eq!(failures(Validator::new().not_in_past("due_date", yesterday)), failed("due_date", "in_past"));
+    }
+
+    #[test]
+    fn nuits_may_be_missing_but_not_malformed() {
+        for nuit in ["", "n/a", "400 339 910"] {
+            assert!(failures(Validator::new().nuit("nuit", nuit)).is_empty(), "{} should be accepted", nuit);
+        }
+        assert_eq!(failures(Validator::new().nuit("nuit", "40033991")), failed("nuit", "invalid_format"));
+        assert_eq!(failures(Validator::new().nuit("nuit", "400339911")), failed("nuit", "invalid_check_digit"));
+    }
+
+    struct Item {
+        price: Decimal,
+    }
+
+    impl Validate for Item {
+        fn rules(&self, validator: Validator) -> Validator {
+            validator.non_negative("price", self.price)
+        }
+    }
+
+    #[test]
+    fn optional_and_nested_rules() {
+        let missing: Option<&str> = None;
+        assert!(failures(Validator::new().optional(missing, |v, name| v.required("name", name))).is_empty());
+        assert_eq!(failures(Validator::new().optional(Some(""), |v, name| v.required("name", name))), failed("name", "required"));
+
+        let item = Item { price: dec("-5") };
+        assert_eq!(failures(Validator::new().nested("items[2]", &item)), failed("items[2].price", "negative"));
+        assert!(Item { price: dec("5") }.validate().is_ok());
+    }
+}
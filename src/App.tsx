@@ -6,12 +6,17 @@ import Orders from "./pages/Orders";
 import OrderView from "./pages/OrderView";
 import Settings from "./pages/Settings";
 import Login from "./pages/Login";
+import Setup from "./pages/Setup";
+import ChangePassword from "./pages/ChangePassword";
+import { invoke, getSessionToken, setSessionToken, SESSION_ENDED_EVENT, type SessionEndedReason } from "./lib/api";
+import type { SessionInfoDto } from "./types/auth";
 import "./App.css";
 
 interface User {
   id: string;
   login: string;
   role: string;
+  must_change_password?: boolean;
 }
 
 function App() {
@@ -19,6 +24,8 @@ function App() {
   const [pageParams, setPageParams] = useState<any>(null);
   const [user, setUser] = useState<User | null>(null);
   const [isLoading, setIsLoading] = useState(true);
+  const [needsSetup, setNeedsSetup] = useState(false);
+  const [sessionNotice, setSessionNotice] = useState("");
   
 
   // Simple routing based on hash
@@ -28,18 +35,49 @@ function App() {
     return { page: page || "home", params };
   };
 
-  // Check for existing user session on app load
+  // Check for existing user session on app load. Sessions live in the
+  // backend's memory, so a token from a previous run is no longer valid.
   useEffect(() => {
-    const savedUser = localStorage.getItem("user");
-    if (savedUser) {
+    const restoreSession = async () => {
+      if (getSessionToken()) {
+        try {
+          const session = await invoke<SessionInfoDto>("get_current_session");
+          setUser({
+            id: session.user_id,
+            login: session.login,
+            role: session.role,
+            must_change_password: session.must_change_password
+          });
+          return;
+        } catch (error) {
+          console.error("Saved session is no longer valid:", error);
+          setSessionToken(null);
+        }
+      }
+      localStorage.removeItem("user");
+
+      // A fresh install has no account yet: the first admin is created here
       try {
-        setUser(JSON.parse(savedUser));
+        setNeedsSetup(await invoke<boolean>("needs_initial_setup"));
       } catch (error) {
-        console.error("Error parsing saved user:", error);
-        localStorage.removeItem("user");
+        console.error("Failed to check initial setup:", error);
       }
-    }
-    setIsLoading(false);
+    };
+    restoreSession().finally(() => setIsLoading(false));
+  }, []);
+
+  // The backend rejected the token (expired or revoked): back to login
+  useEffect(() => {
+    const handleSessionEnded = (event: Event) => {
+      const reason = (event as CustomEvent<{ reason: SessionEndedReason }>).detail?.reason;
+      setSessionNotice(reason === "session_expired"
+        ? "A sua sessão expirou por inatividade. Entre novamente."
+        : "A sua sessão terminou. Entre novamente.");
+      localStorage.removeItem("user");
+      setUser(null);
+    };
+    window.addEventListener(SESSION_ENDED_EVENT, handleSessionEnded);
+    return () => window.removeEventListener(SESSION_ENDED_EVENT, handleSessionEnded);
   }, []);
 
   // Update page when hash changes
@@ -86,12 +124,17 @@ function App() {
   };
 
   const handleLoginSuccess = (userData: User) => {
+    setNeedsSetup(false);
+    setSessionNotice("");
     setUser(userData);
     setCurrentPage("home");
     window.location.hash = "home";
   };
 
   const handleLogout = () => {
+    setSessionNotice("");
+    invoke("logout").catch((error) => console.error("Logout error:", error));
+    setSessionToken(null);
     localStorage.removeItem("user");
     setUser(null);
     setCurrentPage("home");
@@ -129,7 +172,25 @@ function App() {
 
   // Show login page if not authenticated
   if (!user) {
-    return <Login onLoginSuccess={handleLoginSuccess} />;
+    if (needsSetup) {
+      return <Setup onSetupComplete={handleLoginSuccess} />;
+    }
+    return <Login onLoginSuccess={handleLoginSuccess} notice={sessionNotice} />;
+  }
+
+  // Shipped default passwords must be replaced before anything else
+  if (user.must_change_password) {
+    return (
+      <ChangePassword
+        login={user.login}
+        onPasswordChanged={() => {
+          const updated = { ...user, must_change_password: false };
+          localStorage.setItem("user", JSON.stringify(updated));
+          setUser(updated);
+        }}
+        onLogout={handleLogout}
+      />
+    );
   }
 
   return (
@@ -1,5 +1,5 @@
 import { useState, useEffect } from "react";
-import { invoke } from "@tauri-apps/api/core";
+import { invoke } from "../../lib/api";
 import { User, Shirt, Printer } from 'lucide-react';
 import { Button } from "../ui/button";
 import SidePanel from "../ui/SidePanel";
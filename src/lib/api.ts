@@ -0,0 +1,52 @@
+import { invoke as tauriInvoke, type InvokeArgs } from "@tauri-apps/api/core";
+
+const TOKEN_KEY = "token";
+
+/**
+ * Disparado quando o backend rejeita a sessão. `detail.reason` é
+ * `"session_expired"` (inatividade) ou `"invalid_session"` (revogada).
+ */
+export const SESSION_ENDED_EVENT = "session-ended";
+
+export type SessionEndedReason = "session_expired" | "invalid_session";
+
+export function getSessionToken(): string | null {
+  return localStorage.getItem(TOKEN_KEY);
+}
+
+export function setSessionToken(token: string | null) {
+  if (token) {
+    localStorage.setItem(TOKEN_KEY, token);
+  } else {
+    localStorage.removeItem(TOKEN_KEY);
+  }
+}
+
+/**
+ * Chama um comando do backend com o token da sessão atual.
+ * Os comandos que não recebem `token` simplesmente o ignoram.
+ */
+export async function invoke<T>(command: string, args: Record<string, unknown> = {}): Promise<T> {
+  try {
+    return await tauriInvoke<T>(command, { ...args, token: getSessionToken() } as InvokeArgs);
+  } catch (error) {
+    if (isUnauthorized(error)) {
+      setSessionToken(null);
+      const reason = (error as { details?: { reason?: SessionEndedReason } }).details?.reason ?? "invalid_session";
+      window.dispatchEvent(new CustomEvent(SESSION_ENDED_EVENT, { detail: { reason } }));
+    }
+    throw error;
+  }
+}
+
+function isUnauthorized(error: unknown): boolean {
+  return typeof error === "object" && error !== null && (error as { code?: string }).code === "unauthorized";
+}
+
+/** Mensagem de um erro do backend (`{ code, message, details }`) ou `fallback` */
+export function errorMessage(error: unknown, fallback: string): string {
+  if (typeof error === "object" && error !== null && typeof (error as { message?: unknown }).message === "string") {
+    return (error as { message: string }).message;
+  }
+  return typeof error === "string" ? error : fallback;
+}
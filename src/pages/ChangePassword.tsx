@@ -0,0 +1,146 @@
+import { useState } from "react";
+import { KeyRound, Lock, AlertCircle, LogOut } from "lucide-react";
+import { Button } from "../components/ui/button";
+import { errorMessage, invoke } from "../lib/api";
+
+const MIN_PASSWORD_LENGTH = 8;
+
+interface ChangePasswordProps {
+  login: string;
+  onPasswordChanged: () => void;
+  onLogout: () => void;
+}
+
+/**
+ * Mostrada a quem entrou com uma senha padrão (admin123/user123) de versões
+ * antigas. O backend recusa qualquer outro comando até a senha mudar.
+ */
+export default function ChangePassword({ login, onPasswordChanged, onLogout }: ChangePasswordProps) {
+  const [currentPassword, setCurrentPassword] = useState("");
+  const [newPassword, setNewPassword] = useState("");
+  const [confirmation, setConfirmation] = useState("");
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState("");
+
+  const handleSubmit = async (e: React.FormEvent) => {
+    e.preventDefault();
+    setError("");
+
+    if (newPassword.length < MIN_PASSWORD_LENGTH) {
+      setError(`A nova senha deve ter pelo menos ${MIN_PASSWORD_LENGTH} caracteres.`);
+      return;
+    }
+    if (newPassword !== confirmation) {
+      setError("As senhas não coincidem.");
+      return;
+    }
+
+    setLoading(true);
+    try {
+      await invoke("change_password", {
+        dto: { current_password: currentPassword, new_password: newPassword }
+      });
+      onPasswordChanged();
+    } catch (err) {
+      setError(errorMessage(err, "Erro ao alterar a senha. Tente novamente."));
+      console.error("Change password error:", err);
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  const inputClassName = "w-full px-4 py-3 bg-primary-800/50 border border-primary-600 rounded-xl text-primary-100 placeholder-primary-400 focus:outline-none focus:ring-2 focus:ring-secondary-500 focus:border-secondary-500 transition-all";
+
+  return (
+    <div className="min-h-screen bg-primary-950 flex items-center justify-center px-4">
+      <div className="w-full max-w-md">
+        <div className="text-center mb-8">
+          <p className="text-primary-100 text-lg font-medium">Olá, {login}</p>
+          <p className="text-primary-300">
+            Esta conta ainda usa a senha padrão. Defina uma nova senha para continuar.
+          </p>
+        </div>
+
+        <div className="glass-effect p-8 rounded-xl">
+          <form onSubmit={handleSubmit} className="space-y-6">
+            <div className="space-y-3">
+              <label className="block text-primary-100 font-medium flex items-center gap-2">
+                <Lock className="w-4 h-4" />
+                Senha atual
+              </label>
+              <input
+                type="password"
+                value={currentPassword}
+                onChange={(e) => setCurrentPassword(e.target.value)}
+                required
+                disabled={loading}
+                autoComplete="current-password"
+                className={inputClassName}
+              />
+            </div>
+
+            <div className="space-y-3">
+              <label className="block text-primary-100 font-medium flex items-center gap-2">
+                <KeyRound className="w-4 h-4" />
+                Nova senha
+              </label>
+              <input
+                type="password"
+                value={newPassword}
+                onChange={(e) => setNewPassword(e.target.value)}
+                placeholder={`Pelo menos ${MIN_PASSWORD_LENGTH} caracteres`}
+                required
+                disabled={loading}
+                autoComplete="new-password"
+                className={inputClassName}
+              />
+            </div>
+
+            <div className="space-y-3">
+              <label className="block text-primary-100 font-medium flex items-center gap-2">
+                <KeyRound className="w-4 h-4" />
+                Confirmar nova senha
+              </label>
+              <input
+                type="password"
+                value={confirmation}
+                onChange={(e) => setConfirmation(e.target.value)}
+                required
+                disabled={loading}
+                autoComplete="new-password"
+                className={inputClassName}
+              />
+            </div>
+
+            {error && (
+              <div className="flex items-center gap-3 p-4 bg-red-500/20 border border-red-500/50 rounded-xl">
+                <AlertCircle className="w-5 h-5 text-red-400 flex-shrink-0" />
+                <span className="text-red-300 text-sm">{error}</span>
+              </div>
+            )}
+
+            <Button
+              type="submit"
+              disabled={loading}
+              className="w-full px-6 py-3 rounded-xl font-medium hover-lift transition-all disabled:opacity-50 disabled:cursor-not-allowed"
+            >
+              <span className="flex items-center justify-center gap-2">
+                <KeyRound className="w-4 h-4" />
+                {loading ? "Salvando..." : "Alterar senha"}
+              </span>
+            </Button>
+
+            <button
+              type="button"
+              onClick={onLogout}
+              className="w-full flex items-center justify-center gap-2 text-sm text-primary-300 hover:text-primary-100"
+            >
+              <LogOut className="w-4 h-4" />
+              Sair
+            </button>
+          </form>
+        </div>
+      </div>
+    </div>
+  );
+}
@@ -2,19 +2,22 @@ import { useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { LogIn, User as UserIcon, Lock, AlertCircle } from "lucide-react";
 import { Button } from "../components/ui/button";
+import { setSessionToken } from "../lib/api";
 import type { LoginDto, LoginResponseDto } from "../types/auth";
 
 interface LoginProps {
-  onLoginSuccess: (user: { id: string; login: string; role: string }) => void;
+  onLoginSuccess: (user: { id: string; login: string; role: string; must_change_password: boolean }) => void;
+  /** Porque a sessão anterior terminou; mostrado até à próxima tentativa */
+  notice?: string;
 }
 
-export default function Login({ onLoginSuccess }: LoginProps) {
+export default function Login({ onLoginSuccess, notice }: LoginProps) {
   const [formData, setFormData] = useState<LoginDto>({
     login: "",
     password: ""
   });
   const [loading, setLoading] = useState(false);
-  const [error, setError] = useState("");
+  const [error, setError] = useState(notice ?? "");
 
   const handleSubmit = async (e: React.FormEvent) => {
     e.preventDefault();
@@ -27,18 +30,17 @@ export default function Login({ onLoginSuccess }: LoginProps) {
       });
 
       if (response.success) {
-        // Salvar informações do usuário no localStorage
-        localStorage.setItem("user", JSON.stringify({
-          id: response.id,
-          login: response.login,
-          role: response.role
-        }));
-        
-        onLoginSuccess({
+        setSessionToken(response.token);
+        const user = {
           id: response.id,
           login: response.login,
-          role: response.role
-        });
+          role: response.role,
+          must_change_password: response.must_change_password
+        };
+        // Salvar informações do usuário no localStorage
+        localStorage.setItem("user", JSON.stringify(user));
+
+        onLoginSuccess(user);
       } else {
         setError(response.message);
       }
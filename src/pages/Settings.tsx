@@ -2,7 +2,7 @@ import { Wrench, Save, RefreshCw, FileText, UserPlus, AlertTriangle, X, Eye, Eye
 import { Button } from '../components/ui/button';
 import { Input } from '../components/ui/input';
 import { Label } from '../components/ui/label';
-import { invoke } from '@tauri-apps/api/core';
+import { invoke } from '../lib/api';
 import { useState } from 'react';
 
 interface SettingsProps {
@@ -0,0 +1,160 @@
+import { useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { ShieldCheck, User as UserIcon, Lock, AlertCircle } from "lucide-react";
+import { Button } from "../components/ui/button";
+import { errorMessage, setSessionToken } from "../lib/api";
+import type { LoginResponseDto } from "../types/auth";
+
+const MIN_PASSWORD_LENGTH = 8;
+
+interface SetupProps {
+  onSetupComplete: (user: { id: string; login: string; role: string; must_change_password: boolean }) => void;
+}
+
+/** Primeira execução: ainda não existe nenhum usuário, cria-se o administrador */
+export default function Setup({ onSetupComplete }: SetupProps) {
+  const [login, setLogin] = useState("");
+  const [password, setPassword] = useState("");
+  const [confirmation, setConfirmation] = useState("");
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState("");
+
+  const handleSubmit = async (e: React.FormEvent) => {
+    e.preventDefault();
+    setError("");
+
+    if (password.length < MIN_PASSWORD_LENGTH) {
+      setError(`A senha deve ter pelo menos ${MIN_PASSWORD_LENGTH} caracteres.`);
+      return;
+    }
+    if (password !== confirmation) {
+      setError("As senhas não coincidem.");
+      return;
+    }
+
+    setLoading(true);
+    try {
+      await invoke("create_initial_admin", {
+        setupDto: { login: login.trim(), password }
+      });
+
+      // Entrar logo com a conta acabada de criar
+      const response: LoginResponseDto = await invoke("login", {
+        loginDto: { login: login.trim(), password }
+      });
+      if (!response.success) {
+        setError(response.message);
+        return;
+      }
+
+      setSessionToken(response.token);
+      const user = {
+        id: response.id,
+        login: response.login,
+        role: response.role,
+        must_change_password: response.must_change_password
+      };
+      localStorage.setItem("user", JSON.stringify(user));
+      onSetupComplete(user);
+    } catch (err) {
+      setError(errorMessage(err, "Erro ao criar o administrador. Tente novamente."));
+      console.error("Setup error:", err);
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  const inputClassName = "w-full px-4 py-3 bg-primary-800/50 border border-primary-600 rounded-xl text-primary-100 placeholder-primary-400 focus:outline-none focus:ring-2 focus:ring-secondary-500 focus:border-secondary-500 transition-all";
+
+  return (
+    <div className="min-h-screen bg-primary-950 flex items-center justify-center px-4">
+      <div className="w-full max-w-md">
+        <div className="text-center mb-8">
+          <div className="flex justify-center mb-6">
+            <img
+              src="/editartlogo.png"
+              alt="EditArt Logo"
+              className="w-full h-full object-contain"
+            />
+          </div>
+          <p className="text-primary-300">
+            Primeira utilização: crie a conta de administrador
+          </p>
+        </div>
+
+        <div className="glass-effect p-8 rounded-xl">
+          <form onSubmit={handleSubmit} className="space-y-6">
+            <div className="space-y-3">
+              <label className="block text-primary-100 font-medium flex items-center gap-2">
+                <UserIcon className="w-4 h-4" />
+                Login
+              </label>
+              <input
+                type="text"
+                value={login}
+                onChange={(e) => setLogin(e.target.value)}
+                placeholder="Login do administrador"
+                required
+                disabled={loading}
+                autoComplete="off"
+                className={inputClassName}
+              />
+            </div>
+
+            <div className="space-y-3">
+              <label className="block text-primary-100 font-medium flex items-center gap-2">
+                <Lock className="w-4 h-4" />
+                Password
+              </label>
+              <input
+                type="password"
+                value={password}
+                onChange={(e) => setPassword(e.target.value)}
+                placeholder={`Pelo menos ${MIN_PASSWORD_LENGTH} caracteres`}
+                required
+                disabled={loading}
+                autoComplete="new-password"
+                className={inputClassName}
+              />
+            </div>
+
+            <div className="space-y-3">
+              <label className="block text-primary-100 font-medium flex items-center gap-2">
+                <Lock className="w-4 h-4" />
+                Confirmar password
+              </label>
+              <input
+                type="password"
+                value={confirmation}
+                onChange={(e) => setConfirmation(e.target.value)}
+                placeholder="Repita a senha"
+                required
+                disabled={loading}
+                autoComplete="new-password"
+                className={inputClassName}
+              />
+            </div>
+
+            {error && (
+              <div className="flex items-center gap-3 p-4 bg-red-500/20 border border-red-500/50 rounded-xl">
+                <AlertCircle className="w-5 h-5 text-red-400 flex-shrink-0" />
+                <span className="text-red-300 text-sm">{error}</span>
+              </div>
+            )}
+
+            <Button
+              type="submit"
+              disabled={loading}
+              className="w-full px-6 py-3 rounded-xl font-medium hover-lift transition-all disabled:opacity-50 disabled:cursor-not-allowed"
+            >
+              <span className="flex items-center justify-center gap-2">
+                <ShieldCheck className="w-4 h-4" />
+                {loading ? "Criando..." : "Criar administrador"}
+              </span>
+            </Button>
+          </form>
+        </div>
+      </div>
+    </div>
+  );
+}
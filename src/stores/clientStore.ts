@@ -1,6 +1,6 @@
 import { create } from 'zustand';
 import { subscribeWithSelector } from 'zustand/middleware';
-import { invoke } from '@tauri-apps/api/core';
+import { invoke } from '../lib/api';
 import { Client, CreateClientDto, UpdateClientDto } from '../types/client';
 
 // ===== TIPOS DO STORE =====
@@ -1,6 +1,6 @@
 import { create } from 'zustand';
 import { Impression, CreateImpression, UpdateImpression } from '../types/impression';
-import { invoke } from '@tauri-apps/api/core';
+import { invoke } from '../lib/api';
 
 interface ImpressionState {
   impressions: Impression[];
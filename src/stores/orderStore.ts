@@ -1,6 +1,6 @@
 import { create } from 'zustand';
 import { subscribeWithSelector } from 'zustand/middleware';
-import { invoke } from '@tauri-apps/api/core';
+import { invoke } from '../lib/api';
 import { Order, CreateOrderDto, UpdateOrderDto, OrderStatus } from '../types/order';
 import { Client } from '../types/client';
 import { Clothes } from '../types/clothes';
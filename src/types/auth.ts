@@ -9,6 +9,17 @@ export interface LoginResponseDto {
   role: 'admin' | 'user';
   success: boolean;
   message: string;
+  /** Token da sessão, enviado em todos os outros comandos; vazio se o login falhar */
+  token: string;
+  /** Entrou com uma senha padrão: tem de a alterar antes de continuar */
+  must_change_password: boolean;
+}
+
+export interface SessionInfoDto {
+  user_id: string;
+  login: string;
+  role: 'admin' | 'user';
+  must_change_password: boolean;
 }
 
 export interface User {
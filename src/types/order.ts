@@ -36,8 +36,6 @@ export interface UpdateOrderDto {
   due_date?: string;
   discount?: number;
   iva?: number;
-  subtotal?: number;
-  total?: number;
   status?: OrderStatus;
 }
 